@@ -0,0 +1,186 @@
+//! Subscription to the answers of feeds.
+//!
+//! Pushes the typed `RoundData` of every new answer of the selected feeds instead of having
+//! clients decode the `AnswerUpdated` events of every block.
+
+use std::{marker::PhantomData, sync::Arc};
+
+use codec::Codec;
+use futures::{future, FutureExt, SinkExt, StreamExt};
+use jsonrpc_core::Result;
+use jsonrpc_derive::rpc;
+use jsonrpc_pubsub::{manager::SubscriptionManager, typed::Subscriber, SubscriptionId};
+use pallet_chainlink_feed::{RoundData, RoundId};
+use pallet_chainlink_feed_runtime_api::ChainlinkFeedRoundApi;
+use sc_client_api::{BlockImportNotification, BlockchainEvents};
+use serde::{Deserialize, Serialize};
+use sp_api::ProvideRuntimeApi;
+use sp_blockchain::HashAndNumber;
+use sp_runtime::{
+	generic::BlockId,
+	traits::{Block as BlockT, Header as HeaderT, NumberFor},
+};
+
+/// A new answer of a feed.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct AnswerNotification<FeedId, BlockNumber, Value, Hash> {
+	/// The feed that was answered.
+	pub feed_id: FeedId,
+	/// The answered round.
+	pub round_id: RoundId,
+	/// The data of the round.
+	pub round: RoundData<BlockNumber, Value>,
+	/// The block the answer was updated in.
+	pub block_hash: Hash,
+	/// Whether the block was retracted by a chain reorganisation, i.e. the answer is no longer
+	/// part of the best chain.
+	pub retracted: bool,
+}
+
+/// Chainlink feed pubsub RPC methods.
+#[rpc]
+pub trait ChainlinkFeedAnswersApi<FeedId, BlockNumber, Value, Hash> {
+	/// RPC metadata
+	type Metadata;
+
+	/// Subscribe to the new answers of the given feeds.
+	///
+	/// Pushes a notification for every answer updated in a block that becomes part of the best
+	/// chain. On chain reorganisations the answers of the retracted blocks are pushed again with
+	/// `retracted` set, followed by the answers of the newly enacted blocks.
+	#[pubsub(
+		subscription = "chainlinkFeed_answers",
+		subscribe,
+		name = "chainlinkFeed_subscribeAnswers"
+	)]
+	fn subscribe_answers(
+		&self,
+		metadata: Self::Metadata,
+		subscriber: Subscriber<AnswerNotification<FeedId, BlockNumber, Value, Hash>>,
+		feed_ids: Vec<FeedId>,
+	);
+
+	/// Unsubscribe from the answers of feeds.
+	#[pubsub(
+		subscription = "chainlinkFeed_answers",
+		unsubscribe,
+		name = "chainlinkFeed_unsubscribeAnswers"
+	)]
+	fn unsubscribe_answers(
+		&self,
+		metadata: Option<Self::Metadata>,
+		id: SubscriptionId,
+	) -> Result<bool>;
+}
+
+/// Provides the answer subscriptions by watching the blocks imported by a client.
+pub struct ChainlinkFeedAnswers<C, Block> {
+	client: Arc<C>,
+	manager: SubscriptionManager,
+	_marker: PhantomData<Block>,
+}
+
+impl<C, Block> ChainlinkFeedAnswers<C, Block> {
+	/// Create a new instance watching `client` and running its subscriptions with `manager`.
+	pub fn new(client: Arc<C>, manager: SubscriptionManager) -> Self {
+		Self {
+			client,
+			manager,
+			_marker: Default::default(),
+		}
+	}
+}
+
+impl<C, Block, FeedId, Value> ChainlinkFeedAnswersApi<FeedId, NumberFor<Block>, Value, Block::Hash>
+	for ChainlinkFeedAnswers<C, Block>
+where
+	Block: BlockT,
+	C: ProvideRuntimeApi<Block> + BlockchainEvents<Block> + Send + Sync + 'static,
+	C::Api: ChainlinkFeedRoundApi<Block, FeedId, NumberFor<Block>, Value>,
+	FeedId: Codec + Copy + Send + Sync + Serialize + 'static,
+	Value: Codec + Send + Sync + Serialize + 'static,
+{
+	type Metadata = sc_rpc_api::Metadata;
+
+	fn subscribe_answers(
+		&self,
+		_metadata: Self::Metadata,
+		subscriber: Subscriber<AnswerNotification<FeedId, NumberFor<Block>, Value, Block::Hash>>,
+		feed_ids: Vec<FeedId>,
+	) {
+		let client = self.client.clone();
+		let answers = self
+			.client
+			.import_notification_stream()
+			.filter(|notification| future::ready(notification.is_new_best))
+			.flat_map(move |notification| {
+				futures::stream::iter(answers_of(&*client, &feed_ids, notification))
+			})
+			.map(|answer| Ok::<_, ()>(Ok(answer)));
+
+		self.manager.add(subscriber, |sink| {
+			answers
+				.forward(sink.sink_map_err(|e| log::warn!("Error sending notifications: {:?}", e)))
+				.map(|_| ())
+		});
+	}
+
+	fn unsubscribe_answers(
+		&self,
+		_metadata: Option<Self::Metadata>,
+		id: SubscriptionId,
+	) -> Result<bool> {
+		Ok(self.manager.cancel(id))
+	}
+}
+
+/// The answers updated in the blocks retracted and enacted by the import of a new best block.
+fn answers_of<C, Block, FeedId, Value>(
+	client: &C,
+	feed_ids: &[FeedId],
+	notification: BlockImportNotification<Block>,
+) -> Vec<AnswerNotification<FeedId, NumberFor<Block>, Value, Block::Hash>>
+where
+	Block: BlockT,
+	C: ProvideRuntimeApi<Block>,
+	C::Api: ChainlinkFeedRoundApi<Block, FeedId, NumberFor<Block>, Value>,
+	FeedId: Codec + Copy,
+	Value: Codec,
+{
+	let imported = HashAndNumber {
+		hash: notification.hash,
+		number: *notification.header.number(),
+	};
+	let (retracted, enacted) = match &notification.tree_route {
+		Some(route) => (route.retracted().to_vec(), route.enacted().to_vec()),
+		None => (Vec::new(), Vec::new()),
+	};
+
+	let mut answers = Vec::new();
+	for (block, is_retracted) in retracted
+		.into_iter()
+		.map(|block| (block, true))
+		.chain(enacted.into_iter().chain(Some(imported)).map(|block| (block, false)))
+	{
+		for feed_id in feed_ids {
+			match client.runtime_api().rounds_updated_at(
+				&BlockId::Hash(block.hash),
+				*feed_id,
+				block.number,
+			) {
+				Ok(rounds) => answers.extend(rounds.into_iter().map(|(round_id, round)| {
+					AnswerNotification {
+						feed_id: *feed_id,
+						round_id,
+						round,
+						block_hash: block.hash,
+						retracted: is_retracted,
+					}
+				})),
+				Err(e) => log::debug!("Unable to read the answers at {}: {:?}", block.hash, e),
+			}
+		}
+	}
+	answers
+}
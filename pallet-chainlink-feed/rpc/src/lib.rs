@@ -0,0 +1,124 @@
+//! RPC interface for the chainlink price feed pallet.
+//!
+//! Serves the round history the pallet writes to the offchain index, which outlives pruning.
+//! Requires the node to run with offchain indexing enabled (`--enable-offchain-indexing true`).
+//!
+//! The [`answers`] module provides a subscription to the new answers of feeds and the [`proof`]
+//! module storage proofs of their latest rounds.
+
+use std::sync::Arc;
+
+use codec::{Decode, Encode};
+use jsonrpc_core::{Error as RpcError, ErrorCode, Result};
+use jsonrpc_derive::rpc;
+use pallet_chainlink_feed::{round_history_key, RoundData, RoundId};
+use parking_lot::RwLock;
+use sp_core::offchain::OffchainStorage;
+
+pub mod answers;
+pub mod proof;
+
+pub use answers::{AnswerNotification, ChainlinkFeedAnswers, ChainlinkFeedAnswersApi};
+pub use proof::{ChainlinkFeedProofApi, ChainlinkFeedProofs, LatestRoundProof};
+
+/// Maximum number of rounds returned by a single `chainlinkFeed_roundHistory` call.
+pub const MAX_ROUNDS_PER_REQUEST: RoundId = 1_000;
+
+/// Chainlink feed RPC methods.
+#[rpc]
+pub trait ChainlinkFeedApi<FeedId, BlockNumber, Value> {
+	/// Return the answered rounds in `[from, to]` of the given feed from the offchain index.
+	///
+	/// Rounds that were never answered (or not indexed by this node) are skipped.
+	///
+	/// The offchain index is not fork aware: if a round was answered differently on a fork that
+	/// was imported after the canonical block, the answer of the fork is returned. Clients that
+	/// need certainty have to check the answers against the state of the canonical chain (e.g. the
+	/// `Rounds` storage at the canonical block with the number `updated_at`) before relying on them.
+	#[rpc(name = "chainlinkFeed_roundHistory")]
+	fn round_history(
+		&self,
+		feed_id: FeedId,
+		from: RoundId,
+		to: RoundId,
+	) -> Result<Vec<(RoundId, RoundData<BlockNumber, Value>)>>;
+}
+
+/// Provides the round history from the offchain storage of a node.
+pub struct ChainlinkFeed<S> {
+	storage: Arc<RwLock<S>>,
+}
+
+impl<S: OffchainStorage> ChainlinkFeed<S> {
+	/// Create a new instance reading from the given offchain storage.
+	pub fn new(storage: S) -> Self {
+		Self {
+			storage: Arc::new(RwLock::new(storage)),
+		}
+	}
+}
+
+/// Error codes of the chainlink feed RPC.
+pub enum Error {
+	/// The requested range is empty or too large.
+	InvalidRange,
+	/// An indexed round could not be decoded.
+	DecodeError,
+	/// The runtime or the state of the node could not be queried.
+	RuntimeError,
+	/// The feed does not exist or has not been answered, yet.
+	NotAnswered,
+}
+
+impl From<Error> for i64 {
+	fn from(e: Error) -> i64 {
+		match e {
+			Error::InvalidRange => 1,
+			Error::DecodeError => 2,
+			Error::RuntimeError => 3,
+			Error::NotAnswered => 4,
+		}
+	}
+}
+
+impl<S, FeedId, BlockNumber, Value> ChainlinkFeedApi<FeedId, BlockNumber, Value>
+	for ChainlinkFeed<S>
+where
+	S: OffchainStorage + 'static,
+	FeedId: Encode + Copy,
+	BlockNumber: Decode,
+	Value: Decode,
+{
+	fn round_history(
+		&self,
+		feed_id: FeedId,
+		from: RoundId,
+		to: RoundId,
+	) -> Result<Vec<(RoundId, RoundData<BlockNumber, Value>)>> {
+		if to < from || to - from >= MAX_ROUNDS_PER_REQUEST {
+			return Err(RpcError {
+				code: ErrorCode::ServerError(Error::InvalidRange.into()),
+				message: format!(
+					"Invalid round range, at most {} rounds can be requested at once.",
+					MAX_ROUNDS_PER_REQUEST
+				),
+				data: None,
+			});
+		}
+
+		let storage = self.storage.read();
+		let mut rounds = Vec::new();
+		for round_id in from..=to {
+			let key = round_history_key(feed_id, round_id);
+			if let Some(encoded) = storage.get(sp_offchain::STORAGE_PREFIX, &key) {
+				let data = Decode::decode(&mut &encoded[..]).map_err(|e| RpcError {
+					code: ErrorCode::ServerError(Error::DecodeError.into()),
+					message: format!("Unable to decode round {}.", round_id),
+					data: Some(format!("{:?}", e).into()),
+				})?;
+				rounds.push((round_id, data));
+			}
+		}
+		Ok(rounds)
+	}
+}
@@ -0,0 +1,123 @@
+//! Storage proofs of the latest round of feeds.
+//!
+//! The proofs can be checked against the state root of the block with
+//! `pallet_chainlink_feed_verifier::verify_latest_round`, so bridges and light clients do not
+//! have to trust the node serving them.
+
+use std::{marker::PhantomData, sync::Arc};
+
+use codec::Codec;
+use jsonrpc_core::{Error as RpcError, ErrorCode, Result};
+use jsonrpc_derive::rpc;
+use pallet_chainlink_feed::{RoundData, RoundId};
+use pallet_chainlink_feed_runtime_api::ChainlinkFeedRoundApi;
+use pallet_chainlink_feed_verifier::{feed_config_key, round_key, DEFAULT_PALLET_NAME};
+use sc_client_api::ProofProvider;
+use serde::{Deserialize, Serialize};
+use sp_api::ProvideRuntimeApi;
+use sp_blockchain::HeaderBackend;
+use sp_core::Bytes;
+use sp_runtime::{
+	generic::BlockId,
+	traits::{Block as BlockT, NumberFor},
+};
+
+use crate::Error;
+
+/// The latest round of a feed with a proof of it.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct LatestRoundProof<Hash, BlockNumber, Value> {
+	/// The block whose state the proof is against.
+	pub block_hash: Hash,
+	/// The latest answered round of the feed.
+	pub round_id: RoundId,
+	/// The data of the round (unverified, for convenience).
+	pub round: RoundData<BlockNumber, Value>,
+	/// The trie nodes proving the config of the feed and the round.
+	pub proof: Vec<Bytes>,
+}
+
+/// Chainlink feed storage proof RPC methods.
+#[rpc]
+pub trait ChainlinkFeedProofApi<BlockHash, FeedId, BlockNumber, Value> {
+	/// Return the latest answered round of the given feed at the block `at` (the best block if
+	/// not given) with a storage proof against the state root of that block.
+	#[rpc(name = "chainlinkFeed_latestRoundProof")]
+	fn latest_round_proof(
+		&self,
+		feed_id: FeedId,
+		at: Option<BlockHash>,
+	) -> Result<LatestRoundProof<BlockHash, BlockNumber, Value>>;
+}
+
+/// Provides the storage proofs from the state of a client.
+pub struct ChainlinkFeedProofs<C, Block> {
+	client: Arc<C>,
+	_marker: PhantomData<Block>,
+}
+
+impl<C, Block> ChainlinkFeedProofs<C, Block> {
+	/// Create a new instance proving from the state of `client`.
+	pub fn new(client: Arc<C>) -> Self {
+		Self {
+			client,
+			_marker: Default::default(),
+		}
+	}
+}
+
+impl<C, Block, FeedId, Value> ChainlinkFeedProofApi<Block::Hash, FeedId, NumberFor<Block>, Value>
+	for ChainlinkFeedProofs<C, Block>
+where
+	Block: BlockT,
+	C: ProvideRuntimeApi<Block> + HeaderBackend<Block> + ProofProvider<Block>,
+	C: Send + Sync + 'static,
+	C::Api: ChainlinkFeedRoundApi<Block, FeedId, NumberFor<Block>, Value>,
+	FeedId: Codec + Copy,
+	Value: Codec,
+{
+	fn latest_round_proof(
+		&self,
+		feed_id: FeedId,
+		at: Option<Block::Hash>,
+	) -> Result<LatestRoundProof<Block::Hash, NumberFor<Block>, Value>> {
+		let block_hash = at.unwrap_or_else(|| self.client.info().best_hash);
+		let at = BlockId::Hash(block_hash);
+
+		let (round_id, round) = self
+			.client
+			.runtime_api()
+			.latest_round_data(&at, feed_id)
+			.map_err(|e| RpcError {
+				code: ErrorCode::ServerError(Error::RuntimeError.into()),
+				message: "Unable to query the latest round.".into(),
+				data: Some(format!("{:?}", e).into()),
+			})?
+			.ok_or_else(|| RpcError {
+				code: ErrorCode::ServerError(Error::NotAnswered.into()),
+				message: "The feed does not exist or has not been answered, yet.".into(),
+				data: None,
+			})?;
+
+		let keys = [
+			feed_config_key(DEFAULT_PALLET_NAME, &feed_id),
+			round_key(DEFAULT_PALLET_NAME, &feed_id, round_id),
+		];
+		let proof = self
+			.client
+			.read_proof(&at, &mut keys.iter().map(|key| key.as_slice()))
+			.map_err(|e| RpcError {
+				code: ErrorCode::ServerError(Error::RuntimeError.into()),
+				message: "Unable to create the storage proof.".into(),
+				data: Some(format!("{:?}", e).into()),
+			})?;
+
+		Ok(LatestRoundProof {
+			block_hash,
+			round_id,
+			round,
+			proof: proof.iter_nodes().map(Into::into).collect(),
+		})
+	}
+}
@@ -0,0 +1,47 @@
+//! Runtime API definition for the chainlink price feed pallet.
+
+#![cfg_attr(not(feature = "std"), no_std)]
+
+use codec::Codec;
+use pallet_chainlink_feed::{FeedHealth, FundHealth, RoundData, RoundId};
+use sp_std::prelude::*;
+
+sp_api::decl_runtime_apis! {
+	/// Queries of off-chain oracle clients and node-side monitoring against the feed pallet.
+	pub trait ChainlinkFeedApi<AccountId, FeedId, BlockNumber, Balance> where
+		AccountId: Codec,
+		FeedId: Codec,
+		BlockNumber: Codec,
+		Balance: Codec,
+	{
+		/// Return the rounds `oracle` can currently submit to as `(feed_id, round_id)` pairs.
+		fn eligible_rounds(oracle: AccountId) -> Vec<(FeedId, RoundId)>;
+
+		/// Return the ids of all feeds.
+		fn feed_ids() -> Vec<FeedId>;
+
+		/// Return the health of the given feed (`None` if it does not exist).
+		fn feed_health(feed_id: FeedId) -> Option<FeedHealth<BlockNumber>>;
+
+		/// Return the free balance of the pallet fund and its debt towards the oracles.
+		fn fund_health() -> FundHealth<Balance>;
+	}
+
+	/// Queries of off-chain feed consumers against the feed pallet.
+	pub trait ChainlinkFeedRoundApi<FeedId, BlockNumber, Value> where
+		FeedId: Codec,
+		BlockNumber: Codec,
+		Value: Codec,
+	{
+		/// Return the latest answered round of the given feed with its data (`None` if the feed
+		/// does not exist or has not been answered, yet).
+		fn latest_round_data(feed_id: FeedId) -> Option<(RoundId, RoundData<BlockNumber, Value>)>;
+
+		/// Return the rounds of the given feed updated in the block `updated_at` with their data,
+		/// oldest first. Meant to be called on the state of that block.
+		fn rounds_updated_at(
+			feed_id: FeedId,
+			updated_at: BlockNumber,
+		) -> Vec<(RoundId, RoundData<BlockNumber, Value>)>;
+	}
+}
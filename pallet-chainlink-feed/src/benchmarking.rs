@@ -4,7 +4,7 @@ use frame_support::traits::Get;
 use frame_system::RawOrigin;
 use sp_std::fmt::Debug;
 
-use crate::Module as ChainlinkFeed;
+use crate::Pallet as ChainlinkFeed;
 
 const SEED: u32 = 0;
 
@@ -17,21 +17,19 @@ fn assert_is_ok<T: Debug, E: Debug>(r: Result<T, E>) {
 	assert!(r.is_ok());
 }
 
-fn whitelisted_account<T: Trait>(name: &'static str, counter: u32) -> T::AccountId {
+fn whitelisted_account<T: Config>(name: &'static str, counter: u32) -> T::AccountId {
 	let acc = account(name, counter, SEED);
 	whitelist_acc::<T>(&acc);
 	acc
 }
 
-fn whitelist_acc<T: Trait>(acc: &T::AccountId) {
+fn whitelist_acc<T: Config>(acc: &T::AccountId) {
 	frame_benchmarking::benchmarking::add_to_whitelist(
 		frame_system::Account::<T>::hashed_key_for(acc).into(),
 	);
 }
 
 benchmarks! {
-	_ {}
-
 	create_feed {
 		let o in 1 .. T::OracleCountLimit::get();
 
@@ -44,7 +42,7 @@ benchmarks! {
 	}: _(
 			RawOrigin::Signed(caller.clone()),
 			600u32.into(),
-			Zero::zero(),
+			RoundTimeout::Blocks(Zero::zero()),
 			(1u8.into(), 100u8.into()),
 			1u8.into(),
 			5u8.into(),
@@ -67,7 +65,7 @@ benchmarks! {
 		assert_is_ok(ChainlinkFeed::<T>::create_feed(
 			RawOrigin::Signed(caller.clone()).into(),
 			600u32.into(),
-			Zero::zero(),
+			RoundTimeout::Blocks(Zero::zero()),
 			(1u8.into(), 100u8.into()),
 			1u8.into(),
 			5u8.into(),
@@ -92,7 +90,7 @@ benchmarks! {
 		assert_is_ok(ChainlinkFeed::<T>::create_feed(
 			RawOrigin::Signed(caller.clone()).into(),
 			600u32.into(),
-			Zero::zero(),
+			RoundTimeout::Blocks(Zero::zero()),
 			(1u8.into(), 100u8.into()),
 			1u8.into(),
 			5u8.into(),
@@ -124,7 +122,7 @@ benchmarks! {
 		assert_is_ok(ChainlinkFeed::<T>::create_feed(
 			RawOrigin::Signed(caller.clone()).into(),
 			600u32.into(),
-			Zero::zero(),
+			RoundTimeout::Blocks(Zero::zero()),
 			(1u8.into(), 100u8.into()),
 			1,
 			5u8.into(),
@@ -145,7 +143,7 @@ benchmarks! {
 		let round: RoundId = 2;
 		assert_eq!(ChainlinkFeed::<T>::round(feed, round), None);
 		// make sure we hit the `Debt` storage item
-		let fund_account = T::ModuleId::get().into_account();
+		let fund_account = T::PalletId::get().into_account();
 		T::Currency::make_free_balance_be(&fund_account, Zero::zero());
 	}: submit(
 			RawOrigin::Signed(oracle.clone()),
@@ -157,10 +155,13 @@ benchmarks! {
 		let f = Feed::<T>::read_only_from(feed).unwrap();
 		// previous round should be cleared
 		assert_eq!(f.details(prev_round), None);
+		let timestamp = ChainlinkFeed::<T>::timestamp();
 		let expected_round = Round {
 			started_at: One::one(),
+			started_at_timestamp: timestamp,
 			answer: Some(answer),
 			updated_at: Some(One::one()),
+			updated_at_timestamp: Some(timestamp),
 			answered_in_round: Some(2)
 		};
 		assert_eq!(ChainlinkFeed::<T>::round(feed, round), Some(expected_round));
@@ -181,7 +182,7 @@ benchmarks! {
 		assert_is_ok(ChainlinkFeed::<T>::create_feed(
 			RawOrigin::Signed(caller.clone()).into(),
 			600u32.into(),
-			Zero::zero(),
+			RoundTimeout::Blocks(Zero::zero()),
 			(1u8.into(), 100u8.into()),
 			oracles.len() as u32,
 			5u8.into(),
@@ -199,14 +200,14 @@ benchmarks! {
 			assert_is_ok(ChainlinkFeed::<T>::submit(RawOrigin::Signed(o.clone()).into(), feed, prev_round, answer));
 		}
 		// advance the block number so we can supersede the prev round
-		frame_system::Module::<T>::set_block_number(1u8.into());
+		frame_system::Pallet::<T>::set_block_number(1u8.into());
 		let round: RoundId = 2;
 		for (o, _a) in oracles.iter().skip(1) {
 			assert_is_ok(ChainlinkFeed::<T>::submit(RawOrigin::Signed(o.clone()).into(), feed, round, answer));
 		}
-		assert_eq!(ChainlinkFeed::<T>::round(feed, round), Some(Round::new(One::one())));
+		assert_eq!(ChainlinkFeed::<T>::round(feed, round), Some(Round::new(One::one(), ChainlinkFeed::<T>::timestamp())));
 		// make sure we hit the `Debt` storage item
-		let fund_account = T::ModuleId::get().into_account();
+		let fund_account = T::PalletId::get().into_account();
 		T::Currency::make_free_balance_be(&fund_account, Zero::zero());
 	}: submit(
 			RawOrigin::Signed(oracle.clone()),
@@ -215,15 +216,119 @@ benchmarks! {
 			answer
 		)
 	verify {
+		let timestamp = ChainlinkFeed::<T>::timestamp();
 		let expected_round = Round {
 			started_at: One::one(),
+			started_at_timestamp: timestamp,
 			answer: Some(answer),
 			updated_at: Some(One::one()),
+			updated_at_timestamp: Some(timestamp),
 			answered_in_round: Some(2)
 		};
 		assert_eq!(ChainlinkFeed::<T>::round(feed, round), Some(expected_round));
 	}
 
+	// Closing a timed out round is done as part of opening the next one.
+	submit_closing_timed_out_round {
+		let caller: T::AccountId = whitelisted_caller();
+		let pallet_admin: T::AccountId = ChainlinkFeed::<T>::pallet_admin();
+		assert_is_ok(ChainlinkFeed::<T>::set_feed_creator(RawOrigin::Signed(pallet_admin.clone()).into(), caller.clone()));
+		let admin: T::AccountId = account("oracle_admin", 0, SEED);
+		let oracle = whitelisted_account::<T>("oracle", 0);
+		let other_oracle: T::AccountId = account("oracle", 1, SEED);
+		let oracles: Vec<(T::AccountId, T::AccountId)> = vec![(oracle.clone(), admin.clone()), (other_oracle.clone(), admin.clone())];
+		let description = vec![1; T::StringLimit::get() as usize];
+		assert_is_ok(ChainlinkFeed::<T>::create_feed(
+			RawOrigin::Signed(caller.clone()).into(),
+			600u32.into(),
+			RoundTimeout::Blocks(One::one()),
+			(1u8.into(), 100u8.into()),
+			oracles.len() as u32,
+			5u8.into(),
+			description,
+			Zero::zero(),
+			oracles.clone(),
+		));
+		let feed: T::FeedId = Zero::zero();
+		let prev_round: RoundId = 1;
+		let answer: T::Value = 5u8.into();
+		// rounds started at block zero never time out
+		frame_system::Pallet::<T>::set_block_number(1u8.into());
+		// create the previous round that will time out
+		assert_is_ok(ChainlinkFeed::<T>::submit(
+			RawOrigin::Signed(other_oracle.clone()).into(),
+			feed,
+			prev_round,
+			answer
+		));
+		frame_system::Pallet::<T>::set_block_number(3u8.into());
+		let round: RoundId = 2;
+		// make sure we hit the `Debt` storage item
+		let fund_account = T::PalletId::get().into_account();
+		T::Currency::make_free_balance_be(&fund_account, Zero::zero());
+	}: submit(
+			RawOrigin::Signed(oracle.clone()),
+			feed,
+			round,
+			answer
+		)
+	verify {
+		let timestamp = ChainlinkFeed::<T>::timestamp();
+		let expected_round = Round {
+			started_at: One::one(),
+			started_at_timestamp: timestamp,
+			answer: Some(Zero::zero()),
+			updated_at: Some(3u8.into()),
+			updated_at_timestamp: Some(timestamp),
+			answered_in_round: Some(0)
+		};
+		assert_eq!(ChainlinkFeed::<T>::round(feed, prev_round), Some(expected_round));
+		assert_eq!(ChainlinkFeed::<T>::round(feed, round), Some(Round::new(3u8.into(), timestamp)));
+	}
+
+	// A plain submission neither opens a round nor updates the answer.
+	submit_plain {
+		let o = 3;
+		let caller: T::AccountId = whitelisted_caller();
+		let pallet_admin: T::AccountId = ChainlinkFeed::<T>::pallet_admin();
+		assert_is_ok(ChainlinkFeed::<T>::set_feed_creator(RawOrigin::Signed(pallet_admin.clone()).into(), caller.clone()));
+		let admin: T::AccountId = account("oracle_admin", 0, SEED);
+		let oracles: Vec<(T::AccountId, T::AccountId)> = (0..o).map(|n| (account("oracle", n, SEED), admin.clone())).collect();
+		let description = vec![1; T::StringLimit::get() as usize];
+		assert_is_ok(ChainlinkFeed::<T>::create_feed(
+			RawOrigin::Signed(caller.clone()).into(),
+			600u32.into(),
+			RoundTimeout::Blocks(Zero::zero()),
+			(1u8.into(), 100u8.into()),
+			oracles.len() as u32,
+			5u8.into(),
+			description,
+			Zero::zero(),
+			oracles.clone(),
+		));
+		let feed: T::FeedId = Zero::zero();
+		let round: RoundId = 1;
+		let answer: T::Value = 42u8.into();
+		let oracle = oracles.first().map(|(o, _a)| o.clone()).expect("first oracle should be there");
+		whitelist_acc::<T>(&oracle);
+		let other_oracle = oracles.iter().nth(1).map(|(o, _a)| o.clone()).expect("there should be a second oracle");
+		// open the round
+		assert_is_ok(ChainlinkFeed::<T>::submit(RawOrigin::Signed(other_oracle.clone()).into(), feed, round, answer));
+		// make sure we hit the `Debt` storage item
+		let fund_account = T::PalletId::get().into_account();
+		T::Currency::make_free_balance_be(&fund_account, Zero::zero());
+	}: submit(
+			RawOrigin::Signed(oracle.clone()),
+			feed,
+			round,
+			answer
+		)
+	verify {
+		let f = Feed::<T>::read_only_from(feed).unwrap();
+		assert_eq!(f.details(round).expect("details should be there").submissions, vec![answer, answer]);
+		assert_eq!(ChainlinkFeed::<T>::round(feed, round).expect("round should be there").answer, None);
+	}
+
 	change_oracles {
 		let d in 1 .. T::OracleCountLimit::get();
 		let n in 1 .. T::OracleCountLimit::get();
@@ -238,7 +343,7 @@ benchmarks! {
 		assert_is_ok(ChainlinkFeed::<T>::create_feed(
 			RawOrigin::Signed(caller.clone()).into(),
 			600u32.into(),
-			Zero::zero(),
+			RoundTimeout::Blocks(Zero::zero()),
 			(1u8.into(), 100u8.into()),
 			1u8.into(),
 			5u8.into(),
@@ -269,7 +374,7 @@ benchmarks! {
 		assert_is_ok(ChainlinkFeed::<T>::create_feed(
 			RawOrigin::Signed(caller.clone()).into(),
 			600u32.into(),
-			Zero::zero(),
+			RoundTimeout::Blocks(Zero::zero()),
 			(1u8.into(), 100u8.into()),
 			1u8.into(),
 			5u8.into(),
@@ -278,7 +383,7 @@ benchmarks! {
 			oracles.clone(),
 		));
 		let payment: BalanceOf<T> = 42u32.into();
-		let timeout: T::BlockNumber = 3u8.into();
+		let timeout: RoundTimeoutOf<T> = RoundTimeout::Blocks(3u8.into());
 		let feed: T::FeedId = Zero::zero();
 	}: _(
 			RawOrigin::Signed(caller.clone()),
@@ -306,7 +411,7 @@ benchmarks! {
 		assert_is_ok(ChainlinkFeed::<T>::create_feed(
 			RawOrigin::Signed(caller.clone()).into(),
 			600u32.into(),
-			Zero::zero(),
+			RoundTimeout::Blocks(Zero::zero()),
 			(1u8.into(), 100u8.into()),
 			1u8.into(),
 			5u8.into(),
@@ -330,17 +435,20 @@ benchmarks! {
 		// rounds until `r` should be pruned
 		assert_eq!(ChainlinkFeed::<T>::round(feed, RoundId::one()), None);
 		assert_eq!(ChainlinkFeed::<T>::round(feed, r), None);
+		let timestamp = ChainlinkFeed::<T>::timestamp();
 		let expected_round = Round {
 			started_at: Zero::zero(),
+			started_at_timestamp: timestamp,
 			answer: Some(answer),
 			updated_at: Some(Zero::zero()),
+			updated_at_timestamp: Some(timestamp),
 			answered_in_round: Some(r + 1)
 		};
 		// round `r+1` should be kept
 		assert_eq!(ChainlinkFeed::<T>::round(feed, r + 1), Some(expected_round));
 	}
 
-	set_requester {
+	set_auto_pruning {
 		let caller: T::AccountId = whitelisted_caller();
 		let pallet_admin: T::AccountId = ChainlinkFeed::<T>::pallet_admin();
 		assert_is_ok(ChainlinkFeed::<T>::set_feed_creator(RawOrigin::Signed(pallet_admin.clone()).into(), caller.clone()));
@@ -350,7 +458,31 @@ benchmarks! {
 		assert_is_ok(ChainlinkFeed::<T>::create_feed(
 			RawOrigin::Signed(caller.clone()).into(),
 			600u32.into(),
+			RoundTimeout::Blocks(Zero::zero()),
+			(1u8.into(), 100u8.into()),
+			1u8.into(),
+			5u8.into(),
+			description,
 			Zero::zero(),
+			vec![(oracle, admin)],
+		));
+		let feed = Zero::zero();
+	}: _(RawOrigin::Signed(caller.clone()), feed, true)
+	verify {
+		assert_eq!(ChainlinkFeed::<T>::auto_pruning(feed), Some(RoundId::one()));
+	}
+
+	set_requester {
+		let caller: T::AccountId = whitelisted_caller();
+		let pallet_admin: T::AccountId = ChainlinkFeed::<T>::pallet_admin();
+		assert_is_ok(ChainlinkFeed::<T>::set_feed_creator(RawOrigin::Signed(pallet_admin.clone()).into(), caller.clone()));
+		let oracle: T::AccountId = account("oracle", 0, SEED);
+		let admin: T::AccountId = account("oracle_admin", 0, SEED);
+		let description = vec![1; T::StringLimit::get() as usize];
+		assert_is_ok(ChainlinkFeed::<T>::create_feed(
+			RawOrigin::Signed(caller.clone()).into(),
+			600u32.into(),
+			RoundTimeout::Blocks(Zero::zero()),
 			(1u8.into(), 100u8.into()),
 			1u8.into(),
 			5u8.into(),
@@ -376,7 +508,7 @@ benchmarks! {
 		assert_is_ok(ChainlinkFeed::<T>::create_feed(
 			RawOrigin::Signed(caller.clone()).into(),
 			600u32.into(),
-			Zero::zero(),
+			RoundTimeout::Blocks(Zero::zero()),
 			(1u8.into(), 100u8.into()),
 			1u8.into(),
 			5u8.into(),
@@ -404,7 +536,7 @@ benchmarks! {
 		assert_is_ok(ChainlinkFeed::<T>::create_feed(
 			RawOrigin::Signed(caller.clone()).into(),
 			600u32.into(),
-			Zero::zero(),
+			RoundTimeout::Blocks(Zero::zero()),
 			(1u8.into(), 100u8.into()),
 			1,
 			5u8.into(),
@@ -448,7 +580,7 @@ benchmarks! {
 		assert_is_ok(ChainlinkFeed::<T>::create_feed(
 			RawOrigin::Signed(caller.clone()).into(),
 			payment,
-			Zero::zero(),
+			RoundTimeout::Blocks(Zero::zero()),
 			(1u8.into(), 100u8.into()),
 			1,
 			5u8.into(),
@@ -467,7 +599,7 @@ benchmarks! {
 			answer
 		));
 		let recipient: T::AccountId = account("recipient", 0, SEED);
-		let fund_account = T::ModuleId::get().into_account();
+		let fund_account = T::PalletId::get().into_account();
 		T::Currency::make_free_balance_be(&fund_account, payment + payment);
 	}: _(
 		RawOrigin::Signed(admin.clone()),
@@ -536,7 +668,7 @@ benchmarks! {
 		whitelist_acc::<T>(&pallet_admin);
 		let payment: BalanceOf<T> = 600u32.into(); // ExistentialDeposit is 500
 		let recipient: T::AccountId = account("recipient", 0, SEED);
-		let fund_account = T::ModuleId::get().into_account();
+		let fund_account = T::PalletId::get().into_account();
 		let multiplier = 1001u32.into();
 		T::Currency::make_free_balance_be(&fund_account, payment * multiplier);
 	}: _(
@@ -559,7 +691,7 @@ benchmarks! {
 		assert_is_ok(ChainlinkFeed::<T>::create_feed(
 			RawOrigin::Signed(caller.clone()).into(),
 			payment,
-			Zero::zero(),
+			RoundTimeout::Blocks(Zero::zero()),
 			(1u8.into(), 100u8.into()),
 			1u8.into(),
 			5u8.into(),
@@ -570,7 +702,7 @@ benchmarks! {
 		let feed = Zero::zero();
 		let answer: T::Value = 42u8.into();
 		let rounds: RoundId = 4;
-		let fund_account = T::ModuleId::get().into_account();
+		let fund_account = T::PalletId::get().into_account();
 		T::Currency::make_free_balance_be(&fund_account, Zero::zero());
 		for round in 1..(rounds + 1) {
 			assert_is_ok(ChainlinkFeed::<T>::submit(RawOrigin::Signed(oracle.clone()).into(), feed, round, answer));
@@ -677,6 +809,20 @@ mod tests {
 		});
 	}
 
+	#[test]
+	fn submit_closing_timed_out_round() {
+		new_test_ext().execute_with(|| {
+			assert_ok!(test_benchmark_submit_closing_timed_out_round::<Test>());
+		});
+	}
+
+	#[test]
+	fn submit_plain() {
+		new_test_ext().execute_with(|| {
+			assert_ok!(test_benchmark_submit_plain::<Test>());
+		});
+	}
+
 	#[test]
 	fn change_oracles() {
 		new_test_ext().execute_with(|| {
@@ -698,6 +844,13 @@ mod tests {
 		});
 	}
 
+	#[test]
+	fn set_auto_pruning() {
+		new_test_ext().execute_with(|| {
+			assert_ok!(test_benchmark_set_auto_pruning::<Test>());
+		});
+	}
+
 	#[test]
 	fn set_requester() {
 		new_test_ext().execute_with(|| {
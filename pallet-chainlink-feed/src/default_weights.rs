@@ -1,4 +1,10 @@
-//! THIS FILE WAS AUTO-GENERATED USING THE SUBSTRATE BENCHMARK CLI VERSION 2.0.1
+//! Weights for pallet_chainlink_feed.
+//!
+//! Generated using the Substrate benchmark CLI version 2.0.1, except for
+//! `submit_closing_timed_out_round`, `submit_plain` and `set_auto_pruning`: their benchmarks
+//! were added later and these figures are estimates derived from the neighbouring benchmarks.
+//! All figures predate the current version of the pallet: regenerate the file (together with the
+//! runtime or default weights) with `make benchmark-feed` before relying on it in production.
 
 #![allow(unused_parens)]
 #![allow(unused_imports)]
@@ -35,6 +41,18 @@ impl crate::WeightInfo for () {
 			.saturating_add(DbWeight::get().reads(8 as Weight))
 			.saturating_add(DbWeight::get().writes(7 as Weight))
 	}
+	// estimated, not benchmarked yet
+	fn submit_closing_timed_out_round() -> Weight {
+		(471_305_000 as Weight)
+			.saturating_add(DbWeight::get().reads(8 as Weight))
+			.saturating_add(DbWeight::get().writes(8 as Weight))
+	}
+	// estimated, not benchmarked yet
+	fn submit_plain() -> Weight {
+		(291_516_000 as Weight)
+			.saturating_add(DbWeight::get().reads(6 as Weight))
+			.saturating_add(DbWeight::get().writes(5 as Weight))
+	}
 	fn change_oracles(d: u32, n: u32, ) -> Weight {
 		(0 as Weight)
 			.saturating_add((74_485_000 as Weight).saturating_mul(d as Weight))
@@ -58,6 +76,12 @@ impl crate::WeightInfo for () {
 			.saturating_add(DbWeight::get().writes(1 as Weight))
 			.saturating_add(DbWeight::get().writes((2 as Weight).saturating_mul(r as Weight)))
 	}
+	// estimated, not benchmarked yet
+	fn set_auto_pruning() -> Weight {
+		(71_230_000 as Weight)
+			.saturating_add(DbWeight::get().reads(2 as Weight))
+			.saturating_add(DbWeight::get().writes(1 as Weight))
+	}
 	fn set_requester() -> Weight {
 		(99_375_000 as Weight)
 			.saturating_add(DbWeight::get().reads(2 as Weight))
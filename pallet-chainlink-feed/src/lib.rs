@@ -8,84 +8,64 @@ mod benchmarking;
 mod tests;
 
 pub mod default_weights;
+pub mod migrations;
 mod utils;
 
+pub use pallet::*;
+
 use sp_std::prelude::*;
 
-use codec::{Decode, Encode};
-use frame_support::traits::{Currency, ExistenceRequirement, Get, ReservableCurrency};
+use codec::{Decode, Encode, MaxEncodedLen};
+use frame_support::traits::{Currency, ExistenceRequirement, Get, ReservableCurrency, UnixTime};
 use frame_support::{
-	decl_error, decl_event, decl_module, decl_storage,
-	dispatch::{DispatchError, DispatchResult, DispatchResultWithPostInfo, HasCompact},
-	ensure,
+	dispatch::{DispatchError, DispatchResult},
+	ensure, require_transactional, transactional,
 	weights::Weight,
-	Parameter, RuntimeDebug,
+	BoundedVec, Parameter, RuntimeDebug,
 };
-use frame_system::ensure_signed;
 use sp_arithmetic::traits::BaseArithmetic;
-use sp_runtime::{
-	traits::{AccountIdConversion, CheckedAdd, CheckedSub, Member, One, Saturating, Zero},
-	ModuleId,
+use sp_runtime::traits::{
+	AccountIdConversion, CheckedAdd, CheckedSub, MaybeSerializeDeserialize, One,
+	SaturatedConversion, Saturating, Zero,
 };
 use sp_std::convert::{TryFrom, TryInto};
 
-use utils::{median, with_transaction_result};
+use utils::median;
 
 pub type BalanceOf<T> =
-	<<T as Trait>::Currency as Currency<<T as frame_system::Trait>::AccountId>>::Balance;
+	<<T as Config>::Currency as Currency<<T as frame_system::Config>::AccountId>>::Balance;
 
 pub type RoundId = u32;
 
-pub trait Trait: frame_system::Trait {
-	type Event: From<Event<Self>> + Into<<Self as frame_system::Trait>::Event>;
-
-	/// Type for feed indexing.
-	type FeedId: Member + Parameter + Default + Copy + HasCompact + BaseArithmetic;
-
-	/// Oracle feed values.
-	type Value: Member + Parameter + Default + Copy + HasCompact + PartialEq + BaseArithmetic;
-
-	/// Interface used for balance transfers.
-	type Currency: ReservableCurrency<Self::AccountId>;
-
-	/// The module id used to determine the account for storing the funds used to pay the oracles.
-	type ModuleId: Get<ModuleId>;
-
-	/// The minimum amount of funds that need to be present in the fund account.
-	type MinimumReserve: Get<BalanceOf<Self>>;
-
-	/// Maximum allowed string length.
-	type StringLimit: Get<u32>;
-
-	/// Maximum number of oracles per feed.
-	type OracleCountLimit: Get<u32>;
-
-	/// Maximum number of feeds.
-	type FeedLimit: Get<Self::FeedId>;
-
-	/// Number of rounds to keep around per feed.
-	type PruningWindow: Get<RoundId>;
-
-	/// The weight for this pallet's extrinsics.
-	type WeightInfo: WeightInfo;
-}
+/// Milliseconds since the unix epoch.
+pub type Timestamp = u64;
 
 /// The configuration for an oracle feed.
-#[derive(Clone, Encode, Decode, Default, Eq, PartialEq, RuntimeDebug)]
+#[derive(Clone, Encode, Decode, Default, Eq, PartialEq, RuntimeDebug, MaxEncodedLen)]
+#[cfg_attr(feature = "std", derive(serde::Serialize))]
+#[cfg_attr(feature = "std", serde(rename_all = "camelCase"))]
 pub struct FeedConfig<
 	AccountId: Parameter,
 	Balance: Parameter,
 	BlockNumber: Parameter,
 	Value: Parameter,
+	BoundedString,
 > {
 	owner: AccountId,
 	pending_owner: Option<AccountId>,
 	submission_value_bounds: (Value, Value),
 	submission_count_bounds: (u32, u32),
 	payment: Balance,
-	timeout: BlockNumber,
+	timeout: RoundTimeout<BlockNumber>,
 	decimals: u8,
-	description: Vec<u8>,
+	#[cfg_attr(
+		feature = "std",
+		serde(
+			serialize_with = "utils::serialize_lossy_str",
+			bound(serialize = "BoundedString: sp_std::ops::Deref<Target = Vec<u8>>")
+		)
+	)]
+	description: BoundedString,
 	restart_delay: RoundId,
 	reporting_round: RoundId,
 	latest_round: RoundId,
@@ -93,60 +73,124 @@ pub struct FeedConfig<
 	oracle_count: u32,
 }
 pub type FeedConfigOf<T> = FeedConfig<
-	<T as frame_system::Trait>::AccountId,
+	<T as frame_system::Config>::AccountId,
 	BalanceOf<T>,
-	<T as frame_system::Trait>::BlockNumber,
-	<T as Trait>::Value,
+	<T as frame_system::Config>::BlockNumber,
+	<T as Config>::Value,
+	BoundedVec<u8, <T as Config>::StringLimit>,
 >;
 
+impl<AccountId, Balance, BlockNumber, Value, BoundedString>
+	FeedConfig<AccountId, Balance, BlockNumber, Value, BoundedString>
+where
+	AccountId: Parameter,
+	Balance: Parameter,
+	BlockNumber: Parameter,
+	Value: Parameter,
+{
+	/// The latest answered round of the feed.
+	pub fn latest_round(&self) -> RoundId {
+		self.latest_round
+	}
+}
+
+/// The time after which a round can be superseded even if it has not been answered.
+///
+/// Block based timeouts are counted in blocks and drift when block production slows down.
+/// Timestamp based timeouts are counted in milliseconds as reported by `Config::Time`.
+#[derive(Clone, Copy, Encode, Decode, Eq, PartialEq, RuntimeDebug, MaxEncodedLen)]
+#[cfg_attr(feature = "std", derive(serde::Serialize, serde::Deserialize))]
+pub enum RoundTimeout<BlockNumber> {
+	/// The round times out after the given number of blocks.
+	Blocks(BlockNumber),
+	/// The round times out after the given number of milliseconds.
+	Millis(Timestamp),
+}
+pub type RoundTimeoutOf<T> = RoundTimeout<<T as frame_system::Config>::BlockNumber>;
+
+impl<BlockNumber: Default> Default for RoundTimeout<BlockNumber> {
+	fn default() -> Self {
+		RoundTimeout::Blocks(Default::default())
+	}
+}
+
+impl<BlockNumber> From<BlockNumber> for RoundTimeout<BlockNumber> {
+	fn from(blocks: BlockNumber) -> Self {
+		RoundTimeout::Blocks(blocks)
+	}
+}
+
 /// Round data relevant to consumers.
 /// Will only be constructed once minimum amount of submissions have
 /// been provided.
-#[derive(Clone, Encode, Decode, Default, Eq, PartialEq, RuntimeDebug)]
+#[derive(Clone, Encode, Decode, Default, Eq, PartialEq, RuntimeDebug, MaxEncodedLen)]
+#[cfg_attr(feature = "std", derive(serde::Serialize))]
+#[cfg_attr(feature = "std", serde(rename_all = "camelCase"))]
 pub struct Round<BlockNumber, Value> {
 	started_at: BlockNumber,
+	started_at_timestamp: Timestamp,
 	answer: Option<Value>,
 	updated_at: Option<BlockNumber>,
+	updated_at_timestamp: Option<Timestamp>,
 	answered_in_round: Option<RoundId>,
 }
-pub type RoundOf<T> = Round<<T as frame_system::Trait>::BlockNumber, <T as Trait>::Value>;
+pub type RoundOf<T> = Round<<T as frame_system::Config>::BlockNumber, <T as Config>::Value>;
 
 impl<BlockNumber, Value> Round<BlockNumber, Value>
 where
 	BlockNumber: Default, // BlockNumber
 	Value: Default,       // Value
 {
-	/// Create a new Round with the given starting block.
-	fn new(started_at: BlockNumber) -> Self {
+	/// Create a new Round with the given starting block and timestamp.
+	fn new(started_at: BlockNumber, started_at_timestamp: Timestamp) -> Self {
 		Self {
 			started_at,
+			started_at_timestamp,
 			..Default::default()
 		}
 	}
 }
 
 /// Round data relevant to oracles.
-#[derive(Clone, Encode, Decode, Default, Eq, PartialEq, RuntimeDebug)]
-pub struct RoundDetails<Balance, BlockNumber, Value> {
-	submissions: Vec<Value>,
+///
+/// `Submissions` is a bounded vector of the values submitted in the round.
+#[derive(Clone, Encode, Decode, Default, Eq, PartialEq, RuntimeDebug, MaxEncodedLen)]
+#[cfg_attr(feature = "std", derive(serde::Serialize))]
+#[cfg_attr(feature = "std", serde(rename_all = "camelCase"))]
+pub struct RoundDetails<Balance, BlockNumber, Submissions> {
+	#[cfg_attr(
+		feature = "std",
+		serde(
+			serialize_with = "utils::serialize_deref",
+			bound(serialize = "Submissions: sp_std::ops::Deref, Submissions::Target: serde::Serialize")
+		)
+	)]
+	submissions: Submissions,
 	submission_count_bounds: (u32, u32),
 	payment: Balance,
-	timeout: BlockNumber,
+	timeout: RoundTimeout<BlockNumber>,
 }
-pub type RoundDetailsOf<T> =
-	RoundDetails<BalanceOf<T>, <T as frame_system::Trait>::BlockNumber, <T as Trait>::Value>;
+pub type RoundDetailsOf<T> = RoundDetails<
+	BalanceOf<T>,
+	<T as frame_system::Config>::BlockNumber,
+	BoundedVec<<T as Config>::Value, <T as Config>::OracleCountLimit>,
+>;
 
 /// Meta data tracking withdrawable rewards and admin for an oracle.
-#[derive(Clone, Encode, Decode, Default, Eq, PartialEq, RuntimeDebug)]
+#[derive(Clone, Encode, Decode, Default, Eq, PartialEq, RuntimeDebug, MaxEncodedLen)]
+#[cfg_attr(feature = "std", derive(serde::Serialize))]
+#[cfg_attr(feature = "std", serde(rename_all = "camelCase"))]
 pub struct OracleMeta<AccountId, Balance> {
 	withdrawable: Balance,
 	admin: AccountId,
 	pending_admin: Option<AccountId>,
 }
-pub type OracleMetaOf<T> = OracleMeta<<T as frame_system::Trait>::AccountId, BalanceOf<T>>;
+pub type OracleMetaOf<T> = OracleMeta<<T as frame_system::Config>::AccountId, BalanceOf<T>>;
 
 /// Meta data tracking the oracle status for a feed.
-#[derive(Clone, Encode, Decode, Default, Eq, PartialEq, RuntimeDebug)]
+#[derive(Clone, Encode, Decode, Default, Eq, PartialEq, RuntimeDebug, MaxEncodedLen)]
+#[cfg_attr(feature = "std", derive(serde::Serialize))]
+#[cfg_attr(feature = "std", serde(rename_all = "camelCase"))]
 pub struct OracleStatus<Value> {
 	starting_round: RoundId,
 	ending_round: Option<RoundId>,
@@ -154,7 +198,7 @@ pub struct OracleStatus<Value> {
 	last_started_round: Option<RoundId>,
 	latest_submission: Option<Value>,
 }
-pub type OracleStatusOf<T> = OracleStatus<<T as Trait>::Value>;
+pub type OracleStatusOf<T> = OracleStatus<<T as Config>::Value>;
 
 impl<Value> OracleStatus<Value>
 where
@@ -170,7 +214,9 @@ where
 }
 
 /// Used to store round requester permissions for accounts.
-#[derive(Clone, Encode, Decode, Default, Eq, PartialEq, RuntimeDebug)]
+#[derive(Clone, Encode, Decode, Default, Eq, PartialEq, RuntimeDebug, MaxEncodedLen)]
+#[cfg_attr(feature = "std", derive(serde::Serialize))]
+#[cfg_attr(feature = "std", serde(rename_all = "camelCase"))]
 pub struct Requester {
 	delay: RoundId,
 	last_started_round: Option<RoundId>,
@@ -178,13 +224,61 @@ pub struct Requester {
 
 /// Round data as served by the `FeedInterface`.
 #[derive(Clone, Encode, Decode, Default, Eq, PartialEq, RuntimeDebug)]
+#[cfg_attr(feature = "std", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "std", serde(rename_all = "camelCase"))]
 pub struct RoundData<BlockNumber, Value> {
 	pub started_at: BlockNumber,
+	pub started_at_timestamp: Timestamp,
 	pub answer: Value,
 	pub updated_at: BlockNumber,
+	pub updated_at_timestamp: Timestamp,
 	pub answered_in_round: RoundId,
 }
-pub type RoundDataOf<T> = RoundData<<T as frame_system::Trait>::BlockNumber, <T as Trait>::Value>;
+pub type RoundDataOf<T> = RoundData<<T as frame_system::Config>::BlockNumber, <T as Config>::Value>;
+
+/// Health of a feed as served to node-side monitoring.
+#[derive(Clone, Encode, Decode, Default, Eq, PartialEq, RuntimeDebug)]
+#[cfg_attr(feature = "std", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "std", serde(rename_all = "camelCase"))]
+pub struct FeedHealth<BlockNumber> {
+	/// The latest answered round.
+	pub latest_round: RoundId,
+	/// The block the latest answer was updated at (`None` if there is no answer yet).
+	pub updated_at: Option<BlockNumber>,
+	/// The round oracles are currently reporting for.
+	pub reporting_round: RoundId,
+	/// The submissions to the reporting round if it has not been answered yet.
+	pub pending_submissions: u32,
+	/// The submissions needed to answer a round.
+	pub min_submissions: u32,
+}
+pub type FeedHealthOf<T> = FeedHealth<<T as frame_system::Config>::BlockNumber>;
+
+/// The funds of the pallet as served to node-side monitoring.
+#[derive(Clone, Encode, Decode, Default, Eq, PartialEq, RuntimeDebug)]
+#[cfg_attr(feature = "std", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "std", serde(rename_all = "camelCase"))]
+pub struct FundHealth<Balance> {
+	/// The free balance of the pallet fund account.
+	pub free_balance: Balance,
+	/// The debt of the pallet towards the oracles.
+	pub debt: Balance,
+}
+pub type FundHealthOf<T> = FundHealth<BalanceOf<T>>;
+
+/// Prefix of the offchain index keys the answered rounds are stored under.
+pub const ROUND_HISTORY_PREFIX: &[u8] = b"chainlink-feed/round";
+
+/// The offchain index key for the `RoundData` of the given feed and round.
+///
+/// Answered rounds are written to the offchain index (see `sp_io::offchain_index`) so the full
+/// history stays available on nodes with offchain indexing enabled even after pruning.
+/// The index is written on import of every block, including blocks on forks, so the value under a
+/// key is the one written by the block imported last, which is not necessarily part of the
+/// canonical chain.
+pub fn round_history_key<FeedId: Encode>(feed_id: FeedId, round_id: RoundId) -> Vec<u8> {
+	(ROUND_HISTORY_PREFIX, feed_id, round_id).encode()
+}
 
 /// Possible error when converting from `Round` to `RoundData`.
 #[derive(Clone, Encode, Decode, Eq, PartialEq, RuntimeDebug)]
@@ -197,20 +291,26 @@ impl<B, V> TryFrom<Round<B, V>> for RoundData<B, V> {
 	type Error = RoundConversionError;
 
 	fn try_from(r: Round<B, V>) -> Result<Self, Self::Error> {
-		if r.answered_in_round.is_none() || r.answer.is_none() || r.updated_at.is_none() {
+		if r.answered_in_round.is_none()
+			|| r.answer.is_none()
+			|| r.updated_at.is_none()
+			|| r.updated_at_timestamp.is_none()
+		{
 			return Err(RoundConversionError::MissingField);
 		}
 		Ok(Self {
 			started_at: r.started_at,
+			started_at_timestamp: r.started_at_timestamp,
 			answer: r.answer.unwrap(),
 			updated_at: r.updated_at.unwrap(),
+			updated_at_timestamp: r.updated_at_timestamp.unwrap(),
 			answered_in_round: r.answered_in_round.unwrap(),
 		})
 	}
 }
 
 /// Trait for interacting with the feeds in the pallet.
-pub trait FeedOracle<T: frame_system::Trait> {
+pub trait FeedOracle<T: frame_system::Config> {
 	type FeedId: Parameter + BaseArithmetic;
 	type Feed: FeedInterface<T>;
 	type MutableFeed: MutableFeedInterface<T>;
@@ -227,7 +327,7 @@ pub trait FeedOracle<T: frame_system::Trait> {
 }
 
 /// Trait for read-only access to a feed.
-pub trait FeedInterface<T: frame_system::Trait> {
+pub trait FeedInterface<T: frame_system::Config> {
 	type Value: Parameter + BaseArithmetic;
 
 	/// Returns the id of the first round that contains non-default data.
@@ -252,116 +352,334 @@ pub trait FeedInterface<T: frame_system::Trait> {
 }
 
 /// Trait for read-write access to a feed.
-pub trait MutableFeedInterface<T: frame_system::Trait>: FeedInterface<T> {
+pub trait MutableFeedInterface<T: frame_system::Config>: FeedInterface<T> {
 	/// Request that a new oracle round be started.
 	///
 	/// **Warning:** Fallible function that changes storage.
 	fn request_new_round(&mut self, requester: T::AccountId) -> DispatchResult;
 }
 
-decl_storage! {
-	trait Store for Module<T: Trait> as ChainlinkFeed {
-		/// The account controlling the funds for this pallet.
-		pub PalletAdmin get(fn pallet_admin) config(): T::AccountId;
-		// possible optimization: put together with admin?
-		/// The account to set as future pallet admin.
-		pub PendingPalletAdmin: Option<T::AccountId>;
-
-		/// Tracks the amount of debt accrued by the pallet towards the oracles.
-		pub Debt get(fn debt): BalanceOf<T>;
-
-		/// A running counter used internally to determine the next feed id.
-		pub FeedCounter get(fn feed_counter): T::FeedId;
-
-		/// Configuration for a feed.
-		pub Feeds get(fn feed_config):
-			map hasher(twox_64_concat) T::FeedId => Option<FeedConfigOf<T>>;
-
-		/// Accounts allowed to create feeds.
-		pub FeedCreators: map hasher(blake2_128_concat) T::AccountId => Option<()>;
+/// Minimum and Maximum number of submissions allowed per round.
+pub type SubmissionBounds = (u32, u32);
 
-		/// User-facing round data.
-		pub Rounds get(fn round):
-			double_map hasher(twox_64_concat) T::FeedId,
-			           hasher(twox_64_concat) RoundId => Option<RoundOf<T>>;
+/// A feed created at genesis.
+///
+/// Mirrors the parameters of `create_feed` and additionally allows setting requesters and an
+/// initial answer (stored as answered round 1) so the feed is usable from the first block.
+#[derive(Clone, Eq, PartialEq, RuntimeDebug)]
+#[cfg_attr(feature = "std", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "std", serde(rename_all = "camelCase"))]
+pub struct GenesisFeed<AccountId, Balance, BlockNumber, Value> {
+	pub owner: AccountId,
+	pub payment: Balance,
+	pub timeout: RoundTimeout<BlockNumber>,
+	pub submission_value_bounds: (Value, Value),
+	pub min_submissions: u32,
+	pub decimals: u8,
+	pub description: Vec<u8>,
+	pub restart_delay: RoundId,
+	/// The oracles of the feed as `(oracle, admin)` pairs.
+	pub oracles: Vec<(AccountId, AccountId)>,
+	/// The accounts allowed to request new rounds as `(requester, delay)` pairs.
+	pub requesters: Vec<(AccountId, RoundId)>,
+	pub initial_answer: Option<Value>,
+}
+pub type GenesisFeedOf<T> = GenesisFeed<
+	<T as frame_system::Config>::AccountId,
+	BalanceOf<T>,
+	<T as frame_system::Config>::BlockNumber,
+	<T as Config>::Value,
+>;
 
-		/// Operator-facing round data.
-		pub Details get(fn round_details):
-			double_map hasher(twox_64_concat) T::FeedId,
-			           hasher(twox_64_concat) RoundId => Option<RoundDetailsOf<T>>;
+#[frame_support::pallet]
+pub mod pallet {
+	use super::*;
+	use codec::HasCompact;
+	use frame_support::pallet_prelude::*;
+	use frame_support::PalletId;
+	use frame_system::pallet_prelude::*;
+
+	/// The storage version of the pallet. See `migrations` for the changes between versions.
+	pub(crate) const STORAGE_VERSION: StorageVersion = StorageVersion::new(2);
+
+	#[pallet::pallet]
+	#[pallet::generate_store(pub(super) trait Store)]
+	#[pallet::storage_version(STORAGE_VERSION)]
+	#[pallet::generate_storage_info]
+	pub struct Pallet<T>(_);
+
+	#[pallet::config]
+	pub trait Config: frame_system::Config {
+		type Event: From<Event<Self>> + IsType<<Self as frame_system::Config>::Event>;
+
+		/// Type for feed indexing.
+		type FeedId: Member
+			+ Parameter
+			+ Default
+			+ Copy
+			+ HasCompact
+			+ BaseArithmetic
+			+ MaxEncodedLen
+			+ MaybeSerializeDeserialize;
+
+		/// Oracle feed values.
+		type Value: Member
+			+ Parameter
+			+ Default
+			+ Copy
+			+ HasCompact
+			+ PartialEq
+			+ BaseArithmetic
+			+ MaxEncodedLen
+			+ MaybeSerializeDeserialize;
+
+		/// Interface used for balance transfers.
+		type Currency: ReservableCurrency<Self::AccountId>;
+
+		/// The pallet id used to determine the account for storing the funds used to pay the oracles.
+		#[pallet::constant]
+		type PalletId: Get<PalletId>;
+
+		/// The minimum amount of funds that need to be present in the fund account.
+		#[pallet::constant]
+		type MinimumReserve: Get<BalanceOf<Self>>;
+
+		/// Maximum allowed string length (bounds the feed description).
+		#[pallet::constant]
+		type StringLimit: Get<u32>;
+
+		/// Maximum number of oracles per feed (bounds the submissions of a round).
+		#[pallet::constant]
+		type OracleCountLimit: Get<u32>;
+
+		/// Maximum number of feeds.
+		#[pallet::constant]
+		type FeedLimit: Get<Self::FeedId>;
+
+		/// Maximum number of submissions in a `submit_batch` call.
+		#[pallet::constant]
+		type MaxBatchSize: Get<u32>;
+
+		/// Number of rounds to keep around per feed.
+		#[pallet::constant]
+		type PruningWindow: Get<RoundId>;
+
+		/// Maximum weight to spend per block on automatically pruning feeds that opted into it.
+		#[pallet::constant]
+		type PruningWeightLimit: Get<Weight>;
+
+		/// Source of the timestamps recorded for rounds and used for timestamp based timeouts.
+		///
+		/// Use `NoTimestamp` if the runtime does not keep track of time.
+		type Time: UnixTime;
 
-		/// Global oracle meta data including admin and withdrawable funds.
-		pub Oracles get(fn oracle):
-			map hasher(blake2_128_concat) T::AccountId => Option<OracleMetaOf<T>>;
+		/// The weight for this pallet's extrinsics.
+		type WeightInfo: WeightInfo;
+	}
 
-		/// Feed local oracle status data.
-		pub OracleStatuses get(fn oracle_status):
-			double_map hasher(twox_64_concat) T::FeedId,
-			           hasher(blake2_128_concat) T::AccountId => Option<OracleStatusOf<T>>;
+	/// The account controlling the funds for this pallet.
+	#[pallet::storage]
+	#[pallet::getter(fn pallet_admin)]
+	pub type PalletAdmin<T: Config> = StorageValue<_, T::AccountId, ValueQuery>;
+
+	// possible optimization: put together with admin?
+	/// The account to set as future pallet admin.
+	#[pallet::storage]
+	pub type PendingPalletAdmin<T: Config> = StorageValue<_, T::AccountId, OptionQuery>;
+
+	/// Tracks the amount of debt accrued by the pallet towards the oracles.
+	#[pallet::storage]
+	#[pallet::getter(fn debt)]
+	pub type Debt<T: Config> = StorageValue<_, BalanceOf<T>, ValueQuery>;
+
+	/// A running counter used internally to determine the next feed id.
+	#[pallet::storage]
+	#[pallet::getter(fn feed_counter)]
+	pub type FeedCounter<T: Config> = StorageValue<_, T::FeedId, ValueQuery>;
+
+	/// Configuration for a feed.
+	#[pallet::storage]
+	#[pallet::getter(fn feed_config)]
+	pub type Feeds<T: Config> =
+		StorageMap<_, Twox64Concat, T::FeedId, FeedConfigOf<T>, OptionQuery>;
+
+	/// Accounts allowed to create feeds.
+	#[pallet::storage]
+	pub type FeedCreators<T: Config> = StorageMap<_, Blake2_128Concat, T::AccountId, (), OptionQuery>;
+
+	/// User-facing round data.
+	#[pallet::storage]
+	#[pallet::getter(fn round)]
+	pub type Rounds<T: Config> =
+		StorageDoubleMap<_, Twox64Concat, T::FeedId, Twox64Concat, RoundId, RoundOf<T>, OptionQuery>;
+
+	/// Operator-facing round data.
+	#[pallet::storage]
+	#[pallet::getter(fn round_details)]
+	pub type Details<T: Config> = StorageDoubleMap<
+		_,
+		Twox64Concat,
+		T::FeedId,
+		Twox64Concat,
+		RoundId,
+		RoundDetailsOf<T>,
+		OptionQuery,
+	>;
+
+	/// Global oracle meta data including admin and withdrawable funds.
+	#[pallet::storage]
+	#[pallet::getter(fn oracle)]
+	pub type Oracles<T: Config> =
+		StorageMap<_, Blake2_128Concat, T::AccountId, OracleMetaOf<T>, OptionQuery>;
+
+	/// Feed local oracle status data.
+	#[pallet::storage]
+	#[pallet::getter(fn oracle_status)]
+	pub type OracleStatuses<T: Config> = StorageDoubleMap<
+		_,
+		Twox64Concat,
+		T::FeedId,
+		Blake2_128Concat,
+		T::AccountId,
+		OracleStatusOf<T>,
+		OptionQuery,
+	>;
+
+	/// Per-feed permissioning for starting new rounds.
+	#[pallet::storage]
+	#[pallet::getter(fn requester)]
+	pub type Requesters<T: Config> = StorageDoubleMap<
+		_,
+		Twox64Concat,
+		T::FeedId,
+		Blake2_128Concat,
+		T::AccountId,
+		Requester,
+		OptionQuery,
+	>;
+
+	/// Feeds that opted into automatic pruning and the next round to prune for each of them.
+	#[pallet::storage]
+	#[pallet::getter(fn auto_pruning)]
+	pub type AutoPruning<T: Config> = StorageMap<_, Twox64Concat, T::FeedId, RoundId, OptionQuery>;
+
+	/// The feed automatic pruning processed last, the next block continues with the feed after it.
+	#[pallet::storage]
+	pub type AutoPruningCursor<T: Config> = StorageValue<_, T::FeedId, OptionQuery>;
+
+	#[pallet::genesis_config]
+	pub struct GenesisConfig<T: Config> {
+		pub pallet_admin: T::AccountId,
+		// accounts configured at genesis to be allowed to create new feeds
+		pub feed_creators: Vec<T::AccountId>,
+		// feeds (including oracles and requesters) to create at genesis
+		pub feeds: Vec<GenesisFeedOf<T>>,
+	}
 
-		/// Per-feed permissioning for starting new rounds.
-		pub Requesters get(fn requester):
-			double_map hasher(twox_64_concat) T::FeedId,
-			           hasher(blake2_128_concat) T::AccountId => Option<Requester>;
+	#[cfg(feature = "std")]
+	impl<T: Config> Default for GenesisConfig<T> {
+		fn default() -> Self {
+			Self {
+				pallet_admin: Default::default(),
+				feed_creators: Default::default(),
+				feeds: Default::default(),
+			}
+		}
+	}
 
-	} add_extra_genesis {
-		// accounts configured at genesis to be allowed to create new feeds
-		config(feed_creators): Vec<T::AccountId>;
-		build(|config: &GenesisConfig<T>| {
-			for creator in &config.feed_creators {
+	#[pallet::genesis_build]
+	impl<T: Config> GenesisBuild<T> for GenesisConfig<T> {
+		fn build(&self) {
+			PalletAdmin::<T>::put(&self.pallet_admin);
+			for creator in &self.feed_creators {
 				FeedCreators::<T>::insert(creator, ());
 			}
-		})
+			for feed in &self.feeds {
+				// genesis is not built in a transactional layer, but it panics on errors anyway
+				let id = Pallet::<T>::create_feed_unchecked(
+					feed.owner.clone(),
+					feed.payment,
+					feed.timeout,
+					feed.submission_value_bounds,
+					feed.min_submissions,
+					feed.decimals,
+					feed.description.clone(),
+					feed.restart_delay,
+					feed.oracles.clone(),
+				)
+				.expect("genesis feeds should be valid");
+				for (requester, delay) in &feed.requesters {
+					Requesters::<T>::insert(id, requester, Requester {
+						delay: *delay,
+						last_started_round: None,
+					});
+				}
+				if let Some(answer) = feed.initial_answer {
+					let (min, max) = feed.submission_value_bounds;
+					assert!(
+						min <= answer && answer <= max,
+						"the initial answer {:?} of genesis feed {:?} is outside of its submission value bounds {:?}",
+						answer,
+						id,
+						feed.submission_value_bounds,
+					);
+					Feed::<T>::load_from(id)
+						.expect("feed was just created")
+						.seed_answer(answer);
+				}
+			}
+		}
 	}
-}
 
-/// Minimum and Maximum number of submissions allowed per round.
-pub type SubmissionBounds = (u32, u32);
-
-decl_event!(
-	pub enum Event<T>
-	where
-		AccountId = <T as frame_system::Trait>::AccountId,
-		Balance = BalanceOf<T>,
-		BlockNumber = <T as frame_system::Trait>::BlockNumber,
-		FeedId = <T as Trait>::FeedId,
-		Value = <T as Trait>::Value,
-	{
+	#[pallet::event]
+	#[pallet::generate_deposit(pub(super) fn deposit_event)]
+	#[pallet::metadata(
+		T::AccountId = "AccountId",
+		BalanceOf<T> = "Balance",
+		T::BlockNumber = "BlockNumber",
+		T::FeedId = "FeedId",
+		T::Value = "Value",
+		RoundTimeoutOf<T> = "RoundTimeout"
+	)]
+	pub enum Event<T: Config> {
 		/// A new oracle feed was created. \[feed_id, creator\]
-		FeedCreated(FeedId, AccountId),
+		FeedCreated(T::FeedId, T::AccountId),
 		/// A new round was started. \[new_round_id, initiator, started_at\]
-		NewRound(FeedId, RoundId, AccountId, BlockNumber),
+		NewRound(T::FeedId, RoundId, T::AccountId, T::BlockNumber),
 		/// A submission was recorded. \[feed_id, round_id, submission, oracle\]
-		SubmissionReceived(FeedId, RoundId, Value, AccountId),
+		SubmissionReceived(T::FeedId, RoundId, T::Value, T::AccountId),
+		/// A submission of a batch could not be recorded. \[feed_id, round_id, oracle, error\]
+		SubmissionFailed(T::FeedId, RoundId, T::AccountId, DispatchError),
 		/// The answer for the round was updated. \[feed_id, round_id, new_answer, updated_at_block\]
-		AnswerUpdated(FeedId, RoundId, Value, BlockNumber),
+		AnswerUpdated(T::FeedId, RoundId, T::Value, T::BlockNumber),
 		/// The round details were updated. \[feed_id, payment, submission_count_bounds, restart_delay, timeout\]
-		RoundDetailsUpdated(FeedId, Balance, SubmissionBounds, RoundId, BlockNumber),
+		RoundDetailsUpdated(T::FeedId, BalanceOf<T>, SubmissionBounds, RoundId, RoundTimeoutOf<T>),
+		/// Automatic pruning was enabled or disabled for the feed. \[feed, enabled\]
+		AutoPruningUpdated(T::FeedId, bool),
 		/// An admin change was requested for the given oracle. \[oracle, admin, pending_admin\]
-		OracleAdminUpdateRequested(AccountId, AccountId, AccountId),
+		OracleAdminUpdateRequested(T::AccountId, T::AccountId, T::AccountId),
 		/// The admin change was executed. \[oracle, new_admin\]
-		OracleAdminUpdated(AccountId, AccountId),
+		OracleAdminUpdated(T::AccountId, T::AccountId),
 		/// The submission permissions for the given feed and oralce have been updated. \[feed, oracle, enabled\]
-		OraclePermissionsUpdated(FeedId, AccountId, bool),
+		OraclePermissionsUpdated(T::FeedId, T::AccountId, bool),
 		/// The requester permissions have been updated (set or removed). \[feed, requester, authorized, delays\]
-		RequesterPermissionsSet(FeedId, AccountId, bool, RoundId),
+		RequesterPermissionsSet(T::FeedId, T::AccountId, bool, RoundId),
 		/// An owner change was requested for the given feed. \[feed, old_owner, new_owner\]
-		OwnerUpdateRequested(FeedId, AccountId, AccountId),
+		OwnerUpdateRequested(T::FeedId, T::AccountId, T::AccountId),
 		/// The owner change was executed. \[feed, new_owner\]
-		OwnerUpdated(FeedId, AccountId),
+		OwnerUpdated(T::FeedId, T::AccountId),
 		/// A pallet admin change was requested. \[old_pallet_admin, new_pallet_admin\]
-		PalletAdminUpdateRequested(AccountId, AccountId),
+		PalletAdminUpdateRequested(T::AccountId, T::AccountId),
 		/// The pallet admin change was executed. \[new_admin\]
-		PalletAdminUpdated(AccountId),
+		PalletAdminUpdated(T::AccountId),
 		/// The account is allowed to create feeds. \[new_creator\]
-		FeedCreator(AccountId),
+		FeedCreator(T::AccountId),
 		/// The account is no longer allowed to create feeds. \[previously_creator\]
-		FeedCreatorRemoved(AccountId),
+		FeedCreatorRemoved(T::AccountId),
 	}
-);
 
-decl_error! {
-	pub enum Error for Module<T: Trait> {
+	#[pallet::error]
+	pub enum Error<T> {
 		/// A math operation lead to an overflow.
 		Overflow,
 		/// Given account id is not an oracle
@@ -441,25 +759,41 @@ decl_error! {
 		InvalidRound,
 		/// The calling account is not allowed to create feeds.
 		NotFeedCreator,
+		/// More submissions than `MaxBatchSize` were passed to `submit_batch`.
+		BatchTooLarge,
 	}
-}
-
-decl_module! {
-	pub struct Module<T: Trait> for enum Call where origin: T::Origin {
-		fn deposit_event() = default;
 
+	#[pallet::extra_constants]
+	impl<T: Config> Pallet<T> {
 		/// The account used to pay oracles and manage the funds of this pallet.
-		const FundAccount: T::AccountId = T::ModuleId::get().into_account();
+		#[allow(non_snake_case)]
+		fn FundAccount() -> T::AccountId {
+			T::PalletId::get().into_account()
+		}
+	}
+
+	#[pallet::hooks]
+	impl<T: Config> Hooks<BlockNumberFor<T>> for Pallet<T> {
+		fn on_initialize(_n: BlockNumberFor<T>) -> Weight {
+			Self::auto_prune(T::PruningWeightLimit::get())
+		}
 
+		fn on_runtime_upgrade() -> Weight {
+			migrations::migrate::<T>()
+		}
+	}
+
+	#[pallet::call]
+	impl<T: Config> Pallet<T> {
 		// --- feed operations ---
 
 		/// Create a new oracle feed with the given config values.
 		/// Limited to feed creator accounts.
-		#[weight = T::WeightInfo::create_feed(oracles.len() as u32)]
+		#[pallet::weight(T::WeightInfo::create_feed(oracles.len() as u32))]
 		pub fn create_feed(
-			origin,
+			origin: OriginFor<T>,
 			payment: BalanceOf<T>,
-			timeout: T::BlockNumber,
+			timeout: RoundTimeoutOf<T>,
 			submission_value_bounds: (T::Value, T::Value),
 			min_submissions: u32,
 			decimals: u8,
@@ -469,54 +803,27 @@ decl_module! {
 		) -> DispatchResultWithPostInfo {
 			let owner = ensure_signed(origin)?;
 			ensure!(FeedCreators::<T>::contains_key(&owner), Error::<T>::NotFeedCreator);
-			ensure!(description.len() as u32 <= T::StringLimit::get(), Error::<T>::DescriptionTooLong);
-
-			let submission_count_bounds = (min_submissions, oracles.len() as u32);
-
-			with_transaction_result(|| -> DispatchResultWithPostInfo {
-				let id: T::FeedId = FeedCounter::<T>::get();
-				ensure!(id < T::FeedLimit::get(), Error::<T>::FeedLimitReached);
-				let new_id = id.checked_add(&One::one()).ok_or(Error::<T>::Overflow)?;
-				FeedCounter::<T>::put(new_id);
-
-				let new_config = FeedConfig {
-					owner: owner.clone(),
-					pending_owner: None,
-					payment,
-					timeout,
-					submission_value_bounds,
-					submission_count_bounds,
-					decimals,
-					description,
-					restart_delay,
-					latest_round: Zero::zero(),
-					reporting_round: Zero::zero(),
-					first_valid_round: None,
-					oracle_count: Zero::zero(),
-				};
-				let mut feed = Feed::<T>::new(id, new_config); // synced on drop
-				let started_at = frame_system::Module::<T>::block_number();
-				let updated_at = Some(started_at);
-				// Store a dummy value for round 0 because we will not get useful data for
-				// it, but need some seed data that future rounds can carry over.
-				Rounds::<T>::insert(id, RoundId::zero(), Round {
-					started_at,
-					answer: Some(Zero::zero()),
-					updated_at,
-					answered_in_round: Some(Zero::zero())
-				});
-				feed.add_oracles(oracles)?;
-				// validate the rounds config
-				feed.update_future_rounds(payment, submission_count_bounds, restart_delay, timeout)?;
-				Self::deposit_event(RawEvent::FeedCreated(id, owner));
-				Ok(().into())
-			})
+
+			let id = Self::do_create_feed(
+				owner.clone(),
+				payment,
+				timeout,
+				submission_value_bounds,
+				min_submissions,
+				decimals,
+				description,
+				restart_delay,
+				oracles,
+			)?;
+			Self::deposit_event(Event::FeedCreated(id, owner));
+
+			Ok(().into())
 		}
 
 		/// Initiate the transfer of the feed to `new_owner`.
-		#[weight = T::WeightInfo::transfer_ownership()]
+		#[pallet::weight(T::WeightInfo::transfer_ownership())]
 		pub fn transfer_ownership(
-			origin,
+			origin: OriginFor<T>,
 			feed_id: T::FeedId,
 			new_owner: T::AccountId,
 		) -> DispatchResultWithPostInfo {
@@ -527,15 +834,15 @@ decl_module! {
 			feed.pending_owner = Some(new_owner.clone());
 			Feeds::<T>::insert(feed_id, feed);
 
-			Self::deposit_event(RawEvent::OwnerUpdateRequested(feed_id, old_owner, new_owner));
+			Self::deposit_event(Event::OwnerUpdateRequested(feed_id, old_owner, new_owner));
 
 			Ok(().into())
 		}
 
 		/// Accept the transfer of feed ownership.
-		#[weight = T::WeightInfo::accept_ownership()]
+		#[pallet::weight(T::WeightInfo::accept_ownership())]
 		pub fn accept_ownership(
-			origin,
+			origin: OriginFor<T>,
 			feed_id: T::FeedId,
 		) -> DispatchResultWithPostInfo {
 			let new_owner = ensure_signed(origin)?;
@@ -550,7 +857,7 @@ decl_module! {
 			feed.owner = new_owner.clone();
 			Feeds::<T>::insert(feed_id, feed);
 
-			Self::deposit_event(RawEvent::OwnerUpdated(feed_id, new_owner));
+			Self::deposit_event(Event::OwnerUpdated(feed_id, new_owner));
 
 			Ok(().into())
 		}
@@ -565,137 +872,100 @@ decl_module! {
 		/// - Removes the details for the previous round if it was superseded.
 		///
 		/// Limited to the oracles of a feed.
-		#[weight = T::WeightInfo::submit_opening_round_answers().max(
-			T::WeightInfo::submit_closing_answer(T::OracleCountLimit::get())
-		)]
+		///
+		/// The weight is refunded down to the cost of the path actually taken
+		/// (opening a round, closing a timed out round, updating the answer or
+		/// a plain submission).
+		#[pallet::weight(Pallet::<T>::max_submit_weight())]
 		pub fn submit(
-			origin,
-			#[compact] feed_id: T::FeedId,
-			#[compact] round_id: RoundId,
-			#[compact] submission: T::Value,
+			origin: OriginFor<T>,
+			#[pallet::compact] feed_id: T::FeedId,
+			#[pallet::compact] round_id: RoundId,
+			#[pallet::compact] submission: T::Value,
 		) -> DispatchResultWithPostInfo {
 			let oracle = ensure_signed(origin)?;
 
-			with_transaction_result(|| -> DispatchResultWithPostInfo {
-				let mut feed = Feed::<T>::load_from(feed_id).ok_or(Error::<T>::FeedNotFound)?;
-				let mut oracle_status = Self::oracle_status(feed_id, &oracle)
-					.ok_or(Error::<T>::NotOracle)?;
-				feed.ensure_valid_round(&oracle, round_id)?;
-
-				let (min_val, max_val) = feed.config.submission_value_bounds;
-				ensure!(submission >= min_val, Error::<T>::SubmissionBelowMinimum);
-				ensure!(submission <= max_val, Error::<T>::SubmissionAboveMaximum);
-
-				let new_round_id = feed.reporting_round_id().saturating_add(One::one());
-				let next_eligible_round = oracle_status.last_started_round
-					.unwrap_or(Zero::zero())
-					.checked_add(feed.config.restart_delay).ok_or(Error::<T>::Overflow)?
-					.checked_add(One::one()).ok_or(Error::<T>::Overflow)?;
-				let eligible_to_start = round_id >= next_eligible_round
-					|| oracle_status.last_started_round.is_none();
-
-				// initialize the round if conditions are met
-				if round_id == new_round_id && eligible_to_start {
-					let started_at = feed.initialize_round(new_round_id)?;
+			let weight = Self::do_submit(oracle, feed_id, round_id, submission)?;
 
-					Self::deposit_event(
-						RawEvent::NewRound(feed_id, new_round_id, oracle.clone(), started_at));
+			Ok(Some(weight).into())
+		}
 
-					oracle_status.last_started_round = Some(new_round_id);
-				}
+		/// Submit values to multiple feeds at once.
+		///
+		/// Every entry is processed like a separate `submit` call in its own
+		/// storage transaction, so a failing entry does not revert the others.
+		/// Successful entries emit `SubmissionReceived`, failed entries emit
+		/// `SubmissionFailed` with the error.
+		///
+		/// Limited to the oracles of the respective feeds and to `MaxBatchSize`
+		/// submissions.
+		#[pallet::weight(
+			// oversized batches are charged for `MaxBatchSize` submissions so they are rejected
+			// by the call instead of exceeding the block weight
+			Pallet::<T>::max_submit_weight()
+				.saturating_mul(submissions.len().min(T::MaxBatchSize::get() as usize) as Weight)
+		)]
+		pub fn submit_batch(
+			origin: OriginFor<T>,
+			submissions: Vec<(T::FeedId, RoundId, T::Value)>,
+		) -> DispatchResultWithPostInfo {
+			let oracle = ensure_signed(origin)?;
+			ensure!(
+				submissions.len() <= T::MaxBatchSize::get() as usize,
+				Error::<T>::BatchTooLarge
+			);
 
-				// record submission
-				let mut details = Details::<T>::take(feed_id, round_id)
-					.ok_or(Error::<T>::NotAcceptingSubmissions)?;
-				details.submissions.push(submission);
-
-				oracle_status.last_reported_round = Some(round_id);
-				oracle_status.latest_submission = Some(submission);
-				OracleStatuses::<T>::insert(feed_id, &oracle, oracle_status);
-				Self::deposit_event(
-					RawEvent::SubmissionReceived(feed_id, round_id, submission, oracle.clone()));
-
-				// update round answer
-				let (min_count, max_count) = details.submission_count_bounds;
-				if details.submissions.len() >= min_count as usize {
-					let new_answer = median(&mut details.submissions);
-					let mut round = Self::round(feed_id, round_id).ok_or(Error::<T>::RoundNotFound)?;
-					round.answer = Some(new_answer);
-					let updated_at = frame_system::Module::<T>::block_number();
-					round.updated_at = Some(updated_at);
-					round.answered_in_round = Some(round_id);
-					Rounds::<T>::insert(feed_id, round_id, round);
-
-					feed.config.latest_round = round_id;
-					if feed.config.first_valid_round.is_none() {
-						feed.config.first_valid_round = Some(round_id);
+			let mut weight: Weight = 0;
+			for (feed_id, round_id, submission) in submissions {
+				match Self::do_submit(oracle.clone(), feed_id, round_id, submission) {
+					Ok(w) => weight = weight.saturating_add(w),
+					Err(e) => {
+						// we don't know how far the submission got, so we charge the maximum
+						weight = weight.saturating_add(Self::max_submit_weight());
+						Self::deposit_event(Event::SubmissionFailed(
+							feed_id,
+							round_id,
+							oracle.clone(),
+							e,
+						));
 					}
-					// the previous rounds is not eligible for answers any more, so we close it
-					let prev_round_id = round_id.saturating_sub(1);
-					if prev_round_id > 0 {
-						Details::<T>::remove(feed_id, prev_round_id);
-					}
-
-					Self::deposit_event(RawEvent::AnswerUpdated(
-						feed_id, round_id, new_answer, updated_at));
-				}
-
-				// update oracle rewards and try to reserve them
-				let payment = details.payment;
-				T::Currency::reserve(&T::ModuleId::get().into_account(), payment)
-					.or_else(|_| -> DispatchResult {
-						// track the debt in case we cannot reserve
-						Debt::<T>::try_mutate(|debt| {
-							*debt = debt.checked_add(&payment).ok_or(Error::<T>::Overflow)?;
-							Ok(())
-						})
-					})?;
-				let mut oracle_meta = Self::oracle(&oracle).ok_or(Error::<T>::OracleNotFound)?;
-				oracle_meta.withdrawable = oracle_meta.withdrawable
-					.checked_add(&payment).ok_or(Error::<T>::Overflow)?;
-				Oracles::<T>::insert(&oracle, oracle_meta);
-
-				// delete the details if the maximum count has been reached
-				if details.submissions.len() < max_count as usize {
-					Details::<T>::insert(feed_id, round_id, details);
 				}
+			}
 
-				Ok(().into())
-			})
+			Ok(Some(weight).into())
 		}
 
 		/// Disable and add oracles for the given feed.
 		/// Limited to the owner of a feed.
-		#[weight = T::WeightInfo::change_oracles(to_disable.len() as u32, to_add.len() as u32)]
+		#[pallet::weight(T::WeightInfo::change_oracles(to_disable.len() as u32, to_add.len() as u32))]
+		#[transactional]
 		pub fn change_oracles(
-			origin,
+			origin: OriginFor<T>,
 			feed_id: T::FeedId,
 			to_disable: Vec<T::AccountId>,
 			to_add: Vec<(T::AccountId, T::AccountId)>,
 		) -> DispatchResultWithPostInfo {
 			let owner = ensure_signed(origin)?;
 
-			with_transaction_result(|| -> DispatchResultWithPostInfo {
-				// synced on drop
-				let mut feed = Feed::<T>::load_from(feed_id).ok_or(Error::<T>::FeedNotFound)?;
-				feed.ensure_owner(&owner)?;
-				feed.disable_oracles(to_disable)?;
-				feed.add_oracles(to_add)?;
+			// synced on drop
+			let mut feed = Feed::<T>::load_from(feed_id).ok_or(Error::<T>::FeedNotFound)?;
+			feed.ensure_owner(&owner)?;
+			feed.disable_oracles(to_disable)?;
+			feed.add_oracles(to_add)?;
 
-				Ok(().into())
-			})
+			Ok(().into())
 		}
 
 		/// Update the configuration for future oracle rounds.
 		/// Limited to the owner of a feed.
-		#[weight = T::WeightInfo::update_future_rounds()]
+		#[pallet::weight(T::WeightInfo::update_future_rounds())]
 		pub fn update_future_rounds(
-			origin,
+			origin: OriginFor<T>,
 			feed_id: T::FeedId,
 			payment: BalanceOf<T>,
 			submission_count_bounds: (u32, u32),
 			restart_delay: RoundId,
-			timeout: T::BlockNumber,
+			timeout: RoundTimeoutOf<T>,
 		) -> DispatchResultWithPostInfo {
 			let owner = ensure_signed(origin)?;
 			// synced on drop
@@ -714,47 +984,74 @@ decl_module! {
 		/// of data available).
 		///
 		/// Limited to the owner of a feed.
-		#[weight = T::WeightInfo::prune(keep_round.saturating_sub(*first_to_prune))]
+		///
+		/// The weight is refunded down to the number of rounds actually pruned.
+		#[pallet::weight(T::WeightInfo::prune(keep_round.saturating_sub(*first_to_prune)))]
 		pub fn prune(
-			origin,
+			origin: OriginFor<T>,
 			feed_id: T::FeedId,
 			first_to_prune: RoundId,
 			keep_round: RoundId,
-		) -> DispatchResult {
+		) -> DispatchResultWithPostInfo {
 			let owner = ensure_signed(origin)?;
 			ensure!(first_to_prune > Zero::zero(), Error::<T>::CannotPruneRoundZero);
 			ensure!(keep_round > first_to_prune, Error::<T>::NothingToPrune);
-			let mut feed = Self::feed_config(feed_id).ok_or(Error::<T>::FeedNotFound)?;
-			ensure!(feed.owner == owner, Error::<T>::NotFeedOwner);
-			let first_valid_round = feed.first_valid_round.ok_or(Error::<T>::NoValidRoundYet)?;
+			// synced on drop
+			let mut feed = Feed::<T>::load_from(feed_id).ok_or(Error::<T>::FeedNotFound)?;
+			feed.ensure_owner(&owner)?;
+			let first_valid_round =
+				feed.config.first_valid_round.ok_or(Error::<T>::NoValidRoundYet)?;
 			ensure!(first_to_prune <= first_valid_round, Error::<T>::PruneContiguously);
 			let pruning_window = T::PruningWindow::get();
 			ensure!(
-				feed.latest_round.saturating_sub(first_to_prune) > pruning_window,
+				feed.config.latest_round.saturating_sub(first_to_prune) > pruning_window,
 				Error::<T>::NothingToPrune
 			);
 
-			let keep_round = feed.latest_round.saturating_sub(pruning_window).min(keep_round);
-			let mut round = first_to_prune;
-			while round < keep_round {
-				Rounds::<T>::remove(feed_id, round);
-				Details::<T>::remove(feed_id, round);
-				round += RoundId::one();
+			let keep_round =
+				feed.config.latest_round.saturating_sub(pruning_window).min(keep_round);
+			feed.prune_rounds(first_to_prune, keep_round);
+
+			Ok(Some(T::WeightInfo::prune(keep_round.saturating_sub(first_to_prune))).into())
+		}
+
+		/// Enable or disable automatic pruning for the feed.
+		///
+		/// Feeds with automatic pruning enabled are pruned down to the pruning window in
+		/// `on_initialize`, bounded by `PruningWeightLimit` per block.
+		///
+		/// Limited to the owner of a feed.
+		#[pallet::weight(T::WeightInfo::set_auto_pruning())]
+		pub fn set_auto_pruning(
+			origin: OriginFor<T>,
+			feed_id: T::FeedId,
+			enabled: bool,
+		) -> DispatchResultWithPostInfo {
+			let owner = ensure_signed(origin)?;
+			let feed = Self::feed_config(feed_id).ok_or(Error::<T>::FeedNotFound)?;
+			ensure!(feed.owner == owner, Error::<T>::NotFeedOwner);
+
+			if enabled {
+				// Keep the position if automatic pruning was already enabled.
+				if !AutoPruning::<T>::contains_key(feed_id) {
+					AutoPruning::<T>::insert(feed_id, RoundId::one());
+				}
+			} else {
+				AutoPruning::<T>::remove(feed_id);
 			}
-			feed.first_valid_round = Some(keep_round.max(first_valid_round));
 
-			Feeds::<T>::insert(feed_id, feed);
+			Self::deposit_event(Event::AutoPruningUpdated(feed_id, enabled));
 
-			Ok(())
+			Ok(().into())
 		}
 
 		// --- feed: round requests ---
 
 		/// Set requester permissions for `requester`.
 		/// Limited to the feed owner.
-		#[weight = T::WeightInfo::set_requester()]
+		#[pallet::weight(T::WeightInfo::set_requester())]
 		pub fn set_requester(
-			origin,
+			origin: OriginFor<T>,
 			feed_id: T::FeedId,
 			requester: T::AccountId,
 			delay: RoundId,
@@ -768,16 +1065,16 @@ decl_module! {
 			requester_meta.delay = delay;
 			Requesters::<T>::insert(feed_id, &requester, requester_meta);
 
-			Self::deposit_event(RawEvent::RequesterPermissionsSet(feed_id, requester, true, delay));
+			Self::deposit_event(Event::RequesterPermissionsSet(feed_id, requester, true, delay));
 
 			Ok(().into())
 		}
 
 		/// Remove requester permissions for `requester`.
 		/// Limited to the feed owner.
-		#[weight = T::WeightInfo::remove_requester()]
+		#[pallet::weight(T::WeightInfo::remove_requester())]
 		pub fn remove_requester(
-			origin,
+			origin: OriginFor<T>,
 			feed_id: T::FeedId,
 			requester: T::AccountId,
 		) -> DispatchResultWithPostInfo {
@@ -788,72 +1085,87 @@ decl_module! {
 			let requester_meta = Requesters::<T>::take(feed_id, &requester)
 				.ok_or(Error::<T>::RequesterNotFound)?;
 
-			Self::deposit_event(
-				RawEvent::RequesterPermissionsSet(feed_id, requester, false, requester_meta.delay)
-			);
+			Self::deposit_event(Event::RequesterPermissionsSet(
+				feed_id,
+				requester,
+				false,
+				requester_meta.delay,
+			));
 
 			Ok(().into())
 		}
 
 		/// Request the start of a new oracle round.
 		/// Limited to accounts with "requester" permission.
-		#[weight = T::WeightInfo::request_new_round()]
+		#[pallet::weight(T::WeightInfo::request_new_round())]
+		#[transactional]
 		pub fn request_new_round(
-			origin,
+			origin: OriginFor<T>,
 			feed_id: T::FeedId,
 		) -> DispatchResultWithPostInfo {
 			let sender = ensure_signed(origin)?;
-			let mut requester = Self::requester(feed_id, &sender)
-				.ok_or(Error::<T>::NotAuthorizedRequester)?;
+			let mut requester =
+				Self::requester(feed_id, &sender).ok_or(Error::<T>::NotAuthorizedRequester)?;
 
-			with_transaction_result(|| -> DispatchResultWithPostInfo {
-				let mut feed = Feed::<T>::load_from(feed_id).ok_or(Error::<T>::FeedNotFound)?;
+			let mut feed = Feed::<T>::load_from(feed_id).ok_or(Error::<T>::FeedNotFound)?;
 
-				let new_round = feed.reporting_round_id()
-					.checked_add(One::one()).ok_or(Error::<T>::Overflow)?;
-				let last_started = requester.last_started_round.unwrap_or(Zero::zero());
-				let next_allowed_round = last_started
-					.checked_add(requester.delay).ok_or(Error::<T>::Overflow)?;
-				ensure!(
-					requester.last_started_round.is_none() || new_round > next_allowed_round,
-					Error::<T>::CannotRequestRoundYet
-				);
+			let new_round = feed
+				.reporting_round_id()
+				.checked_add(One::one())
+				.ok_or(Error::<T>::Overflow)?;
+			let last_started = requester.last_started_round.unwrap_or(Zero::zero());
+			let next_allowed_round = last_started
+				.checked_add(requester.delay)
+				.ok_or(Error::<T>::Overflow)?;
+			ensure!(
+				requester.last_started_round.is_none() || new_round > next_allowed_round,
+				Error::<T>::CannotRequestRoundYet
+			);
 
-				requester.last_started_round = Some(new_round);
-				Requesters::<T>::insert(feed_id, &sender, requester);
+			requester.last_started_round = Some(new_round);
+			Requesters::<T>::insert(feed_id, &sender, requester);
 
-				feed.request_new_round(sender)?;
+			feed.request_new_round(sender)?;
 
-				Ok(().into())
-			})
+			Ok(().into())
 		}
 
 		// --- oracle operations ---
 
 		/// Withdraw `amount` payment of the given oracle to `recipient`.
 		/// Limited to the oracle admin.
-		#[weight = T::WeightInfo::withdraw_payment()]
-		pub fn withdraw_payment(origin,
+		#[pallet::weight(T::WeightInfo::withdraw_payment())]
+		pub fn withdraw_payment(
+			origin: OriginFor<T>,
 			oracle: T::AccountId,
 			recipient: T::AccountId,
 			amount: BalanceOf<T>,
-		) {
+		) -> DispatchResultWithPostInfo {
 			let admin = ensure_signed(origin)?;
 			let mut oracle_meta = Self::oracle(&oracle).ok_or(Error::<T>::OracleNotFound)?;
 			ensure!(oracle_meta.admin == admin, Error::<T>::NotAdmin);
 
-			oracle_meta.withdrawable = oracle_meta.withdrawable
-				.checked_sub(&amount).ok_or(Error::<T>::InsufficientFunds)?;
-
-			T::Currency::transfer(&T::ModuleId::get().into_account(), &recipient, amount, ExistenceRequirement::KeepAlive)?;
+			oracle_meta.withdrawable = oracle_meta
+				.withdrawable
+				.checked_sub(&amount)
+				.ok_or(Error::<T>::InsufficientFunds)?;
+
+			T::Currency::transfer(
+				&T::PalletId::get().into_account(),
+				&recipient,
+				amount,
+				ExistenceRequirement::KeepAlive,
+			)?;
 			Oracles::<T>::insert(&oracle, oracle_meta);
+
+			Ok(().into())
 		}
 
 		/// Initiate an admin transfer for the given oracle.
 		/// Limited to the oracle admin account.
-		#[weight = T::WeightInfo::transfer_admin()]
+		#[pallet::weight(T::WeightInfo::transfer_admin())]
 		pub fn transfer_admin(
-			origin,
+			origin: OriginFor<T>,
 			oracle: T::AccountId,
 			new_admin: T::AccountId,
 		) -> DispatchResultWithPostInfo {
@@ -865,16 +1177,16 @@ decl_module! {
 			oracle_meta.pending_admin = Some(new_admin.clone());
 			Oracles::<T>::insert(&oracle, oracle_meta);
 
-			Self::deposit_event(RawEvent::OracleAdminUpdateRequested(oracle, old_admin, new_admin));
+			Self::deposit_event(Event::OracleAdminUpdateRequested(oracle, old_admin, new_admin));
 
 			Ok(().into())
 		}
 
 		/// Complete an admin transfer for the given oracle.
 		/// Limited to the pending oracle admin account.
-		#[weight = T::WeightInfo::accept_admin()]
+		#[pallet::weight(T::WeightInfo::accept_admin())]
 		pub fn accept_admin(
-			origin,
+			origin: OriginFor<T>,
 			oracle: T::AccountId,
 		) -> DispatchResultWithPostInfo {
 			let new_admin = ensure_signed(origin)?;
@@ -889,7 +1201,7 @@ decl_module! {
 			oracle_meta.admin = new_admin.clone();
 			Oracles::<T>::insert(&oracle, oracle_meta);
 
-			Self::deposit_event(RawEvent::OracleAdminUpdated(oracle, new_admin));
+			Self::deposit_event(Event::OracleAdminUpdated(oracle, new_admin));
 
 			Ok(().into())
 		}
@@ -898,105 +1210,532 @@ decl_module! {
 
 		/// Withdraw `amount` funds to `recipient`.
 		/// Limited to the pallet admin.
-		#[weight = T::WeightInfo::withdraw_funds()]
-		pub fn withdraw_funds(origin,
+		#[pallet::weight(T::WeightInfo::withdraw_funds())]
+		pub fn withdraw_funds(
+			origin: OriginFor<T>,
 			recipient: T::AccountId,
 			amount: BalanceOf<T>,
-		) {
+		) -> DispatchResultWithPostInfo {
 			let sender = ensure_signed(origin)?;
 			ensure!(sender == Self::pallet_admin(), Error::<T>::NotPalletAdmin);
-			let fund = T::ModuleId::get().into_account();
+			let fund = T::PalletId::get().into_account();
 			let reserve = T::Currency::free_balance(&fund);
 			let new_reserve = reserve.checked_sub(&amount).ok_or(Error::<T>::InsufficientFunds)?;
 			ensure!(new_reserve >= T::MinimumReserve::get(), Error::<T>::InsufficientReserve);
 			T::Currency::transfer(&fund, &recipient, amount, ExistenceRequirement::KeepAlive)?;
+
+			Ok(().into())
 		}
 
 		/// Reduce the amount of debt in the pallet by moving funds from
 		/// the free balance to the reserved so oracles can be payed out.
 		/// Limited to the pallet admin.
-		#[weight = T::WeightInfo::reduce_debt()]
-		pub fn reduce_debt(origin, amount: BalanceOf<T>) -> DispatchResult {
+		#[pallet::weight(T::WeightInfo::reduce_debt())]
+		pub fn reduce_debt(origin: OriginFor<T>, amount: BalanceOf<T>) -> DispatchResultWithPostInfo {
 			let _sender = ensure_signed(origin)?;
-			Debt::<T>::try_mutate(|debt| {
+			Debt::<T>::try_mutate(|debt| -> DispatchResult {
 				let to_reserve = amount.min(*debt);
-				T::Currency::reserve(&T::ModuleId::get().into_account(), to_reserve)?;
+				T::Currency::reserve(&T::PalletId::get().into_account(), to_reserve)?;
 				// it's fine if we saturate to 0 debt
 				*debt = debt.saturating_sub(amount);
 				Ok(())
-			})
+			})?;
+
+			Ok(().into())
 		}
 
 		/// Initiate an admin transfer for the pallet.
 		/// Limited to the pallet admin account.
-		#[weight = T::WeightInfo::transfer_pallet_admin()]
+		#[pallet::weight(T::WeightInfo::transfer_pallet_admin())]
 		pub fn transfer_pallet_admin(
-			origin,
+			origin: OriginFor<T>,
 			new_pallet_admin: T::AccountId,
-		) -> DispatchResult {
+		) -> DispatchResultWithPostInfo {
 			let old_admin = ensure_signed(origin)?;
 
 			ensure!(Self::pallet_admin() == old_admin, Error::<T>::NotPalletAdmin);
 
 			PendingPalletAdmin::<T>::put(&new_pallet_admin);
 
-			Self::deposit_event(RawEvent::PalletAdminUpdateRequested(old_admin, new_pallet_admin));
+			Self::deposit_event(Event::PalletAdminUpdateRequested(old_admin, new_pallet_admin));
 
-			Ok(())
+			Ok(().into())
 		}
 
 		/// Complete an admin transfer for the pallet.
 		/// Limited to the pending pallet admin account.
-		#[weight = T::WeightInfo::accept_pallet_admin()]
-		pub fn accept_pallet_admin(origin) -> DispatchResult {
+		#[pallet::weight(T::WeightInfo::accept_pallet_admin())]
+		pub fn accept_pallet_admin(origin: OriginFor<T>) -> DispatchResultWithPostInfo {
 			let new_pallet_admin = ensure_signed(origin)?;
 
-			ensure!(PendingPalletAdmin::<T>::get().filter(|p| p == &new_pallet_admin).is_some(), Error::<T>::NotPendingPalletAdmin);
+			ensure!(
+				PendingPalletAdmin::<T>::get()
+					.filter(|p| p == &new_pallet_admin)
+					.is_some(),
+				Error::<T>::NotPendingPalletAdmin
+			);
 
 			PendingPalletAdmin::<T>::take();
 			PalletAdmin::<T>::put(&new_pallet_admin);
 
-			Self::deposit_event(RawEvent::PalletAdminUpdated(new_pallet_admin));
+			Self::deposit_event(Event::PalletAdminUpdated(new_pallet_admin));
 
-			Ok(())
+			Ok(().into())
 		}
 
 		/// Allow the given account to create oracle feeds.
 		/// Limited to the pallet admin account.
-		#[weight = T::WeightInfo::set_feed_creator()]
-		pub fn set_feed_creator(origin, new_creator: T::AccountId) {
+		#[pallet::weight(T::WeightInfo::set_feed_creator())]
+		pub fn set_feed_creator(
+			origin: OriginFor<T>,
+			new_creator: T::AccountId,
+		) -> DispatchResultWithPostInfo {
 			let admin = ensure_signed(origin)?;
 			ensure!(Self::pallet_admin() == admin, Error::<T>::NotPalletAdmin);
 
 			FeedCreators::<T>::insert(&new_creator, ());
 
-			Self::deposit_event(RawEvent::FeedCreator(new_creator));
+			Self::deposit_event(Event::FeedCreator(new_creator));
+
+			Ok(().into())
 		}
 
 		/// Disallow the given account to create oracle feeds.
 		/// Limited to the pallet admin account.
-		#[weight = T::WeightInfo::remove_feed_creator()]
-		pub fn remove_feed_creator(origin, creator: T::AccountId) {
+		#[pallet::weight(T::WeightInfo::remove_feed_creator())]
+		pub fn remove_feed_creator(
+			origin: OriginFor<T>,
+			creator: T::AccountId,
+		) -> DispatchResultWithPostInfo {
 			let admin = ensure_signed(origin)?;
 			ensure!(Self::pallet_admin() == admin, Error::<T>::NotPalletAdmin);
 
 			FeedCreators::<T>::remove(&creator);
 
-			Self::deposit_event(RawEvent::FeedCreatorRemoved(creator));
+			Self::deposit_event(Event::FeedCreatorRemoved(creator));
+
+			Ok(().into())
+		}
+	}
+}
+
+impl<T: Config> Pallet<T> {
+	/// Return the rounds `oracle` can currently submit to as `(feed_id, round_id)` pairs.
+	///
+	/// Contains the reporting round of a feed while it accepts submissions from the oracle or the
+	/// next round if the oracle is allowed to start it. Meant to be exposed via a runtime API
+	/// for off-chain oracle clients.
+	pub fn eligible_rounds(oracle: &T::AccountId) -> Vec<(T::FeedId, RoundId)> {
+		Feeds::<T>::iter_keys()
+			.filter_map(|feed_id| {
+				let feed = Feed::<T>::read_only_from(feed_id)?;
+				feed.eligible_round(oracle).map(|round_id| (feed_id, round_id))
+			})
+			.collect()
+	}
+
+	/// Return the ids of all feeds.
+	pub fn feed_ids() -> Vec<T::FeedId> {
+		Feeds::<T>::iter_keys().collect()
+	}
+
+	/// Return the health of the feed with the given id (`None` if it does not exist).
+	///
+	/// Meant to be exposed via a runtime API for node-side monitoring.
+	pub fn feed_health(feed_id: T::FeedId) -> Option<FeedHealthOf<T>> {
+		let feed = Feed::<T>::read_only_from(feed_id)?;
+		let latest_round = feed.config.latest_round;
+		let reporting_round = feed.reporting_round_id();
+		let pending_submissions = if reporting_round != latest_round {
+			feed.details(reporting_round).map_or(0, |d| d.submissions.len() as u32)
+		} else {
+			0
+		};
+		Some(FeedHealth {
+			latest_round,
+			updated_at: feed.round(latest_round).and_then(|r| r.updated_at),
+			reporting_round,
+			pending_submissions,
+			min_submissions: feed.config.submission_count_bounds.0,
+		})
+	}
+
+	/// Return the latest answered round of the feed with its data (`None` if the feed does not
+	/// exist or has not been answered, yet).
+	///
+	/// Meant to be exposed via a runtime API for off-chain feed consumers.
+	pub fn latest_round_data(feed_id: T::FeedId) -> Option<(RoundId, RoundDataOf<T>)> {
+		let feed = Feed::<T>::read_only_from(feed_id)?;
+		let latest_round = feed.latest_round();
+		feed.data_at(latest_round).map(|data| (latest_round, data))
+	}
+
+	/// Return the rounds of the given feed that were updated in block `updated_at` with their
+	/// data, oldest first. A block can close a timed out round and answer the next one, so there
+	/// may be more than one. Meant to be called on the state of block `updated_at`, later rounds
+	/// hide the rounds answered before them.
+	///
+	/// Meant to be exposed via a runtime API for off-chain feed consumers.
+	pub fn rounds_updated_at(
+		feed_id: T::FeedId,
+		updated_at: T::BlockNumber,
+	) -> Vec<(RoundId, RoundDataOf<T>)> {
+		let feed = match Feed::<T>::read_only_from(feed_id) {
+			Some(feed) => feed,
+			None => return Vec::new(),
+		};
+		let first_valid_round = match feed.first_valid_round() {
+			Some(round_id) => round_id,
+			None => return Vec::new(),
+		};
+		let mut rounds = Vec::new();
+		// rounds are answered in order, so walk back from the reporting round until the first
+		// round that was answered earlier (skipping rounds that are not answered, yet)
+		for round_id in (first_valid_round..=feed.reporting_round_id()).rev() {
+			match feed.data_at(round_id) {
+				Some(data) if data.updated_at == updated_at => rounds.push((round_id, data)),
+				Some(_) => break,
+				None => {}
+			}
+		}
+		rounds.reverse();
+		rounds
+	}
+
+	/// Return the free balance of the pallet fund and its debt towards the oracles.
+	pub fn fund_health() -> FundHealthOf<T> {
+		FundHealth {
+			free_balance: T::Currency::free_balance(&T::PalletId::get().into_account()),
+			debt: Debt::<T>::get(),
+		}
+	}
+
+	/// The current time in milliseconds as reported by `T::Time`.
+	fn timestamp() -> Timestamp {
+		T::Time::now().as_millis().saturated_into()
+	}
+
+	/// Create a new feed owned by `owner` and return its id.
+	#[transactional]
+	fn do_create_feed(
+		owner: T::AccountId,
+		payment: BalanceOf<T>,
+		timeout: RoundTimeoutOf<T>,
+		submission_value_bounds: (T::Value, T::Value),
+		min_submissions: u32,
+		decimals: u8,
+		description: Vec<u8>,
+		restart_delay: RoundId,
+		oracles: Vec<(T::AccountId, T::AccountId)>,
+	) -> Result<T::FeedId, DispatchError> {
+		Self::create_feed_unchecked(
+			owner,
+			payment,
+			timeout,
+			submission_value_bounds,
+			min_submissions,
+			decimals,
+			description,
+			restart_delay,
+			oracles,
+		)
+	}
+
+	/// Create a new feed owned by `owner` and return its id.
+	///
+	/// Shared by `create_feed` and the genesis config.
+	///
+	/// **Warning:** Fallible function that changes storage, does not require a transactional
+	/// layer so it can be used when building the genesis.
+	fn create_feed_unchecked(
+		owner: T::AccountId,
+		payment: BalanceOf<T>,
+		timeout: RoundTimeoutOf<T>,
+		submission_value_bounds: (T::Value, T::Value),
+		min_submissions: u32,
+		decimals: u8,
+		description: Vec<u8>,
+		restart_delay: RoundId,
+		oracles: Vec<(T::AccountId, T::AccountId)>,
+	) -> Result<T::FeedId, DispatchError> {
+		let description: BoundedVec<u8, T::StringLimit> =
+			description.try_into().map_err(|_| Error::<T>::DescriptionTooLong)?;
+
+		let submission_count_bounds = (min_submissions, oracles.len() as u32);
+
+		let id: T::FeedId = FeedCounter::<T>::get();
+		ensure!(id < T::FeedLimit::get(), Error::<T>::FeedLimitReached);
+		let new_id = id.checked_add(&One::one()).ok_or(Error::<T>::Overflow)?;
+		FeedCounter::<T>::put(new_id);
+
+		let new_config = FeedConfig {
+			owner,
+			pending_owner: None,
+			payment,
+			timeout,
+			submission_value_bounds,
+			submission_count_bounds,
+			decimals,
+			description,
+			restart_delay,
+			latest_round: Zero::zero(),
+			reporting_round: Zero::zero(),
+			first_valid_round: None,
+			oracle_count: Zero::zero(),
+		};
+		let mut feed = Feed::<T>::new(id, new_config); // synced on drop
+		let started_at = frame_system::Pallet::<T>::block_number();
+		let started_at_timestamp = Self::timestamp();
+		// Store a dummy value for round 0 because we will not get useful data for
+		// it, but need some seed data that future rounds can carry over.
+		Rounds::<T>::insert(
+			id,
+			RoundId::zero(),
+			Round {
+				started_at,
+				started_at_timestamp,
+				answer: Some(Zero::zero()),
+				updated_at: Some(started_at),
+				updated_at_timestamp: Some(started_at_timestamp),
+				answered_in_round: Some(Zero::zero()),
+			},
+		);
+		feed.add_oracles_unchecked(oracles)?;
+		// validate the rounds config
+		feed.update_future_rounds(payment, submission_count_bounds, restart_delay, timeout)?;
+
+		Ok(id)
+	}
+
+	/// The weight of the most expensive path through `submit`: opening a round (possibly
+	/// closing a timed out one) and answering it in the same submission.
+	fn max_submit_weight() -> Weight {
+		let opening = T::WeightInfo::submit_opening_round_answers()
+			.max(T::WeightInfo::submit_closing_timed_out_round());
+		let closing = T::WeightInfo::submit_closing_answer(T::OracleCountLimit::get());
+		Self::submit_weight(Some(opening), Some(closing))
+	}
+
+	/// The weight of a submission that took the given opening and closing paths.
+	///
+	/// Every `submit` benchmark measures a complete submission, so the paths taken add their
+	/// cost on top of a plain submission.
+	fn submit_weight(opening: Option<Weight>, closing: Option<Weight>) -> Weight {
+		let plain = T::WeightInfo::submit_plain();
+		let extra = |path: Option<Weight>| path.unwrap_or(plain).saturating_sub(plain);
+		plain.saturating_add(extra(opening)).saturating_add(extra(closing))
+	}
+
+	/// Prune the feeds that opted into automatic pruning down to the pruning window until
+	/// `limit` is used up.
+	///
+	/// Returns the weight consumed.
+	fn auto_prune(limit: Weight) -> Weight {
+		let db = T::DbWeight::get();
+		let pruning_window = T::PruningWindow::get();
+		// `prune` is linear in the number of rounds, so derive the cost of a single round from it
+		let base_weight = T::WeightInfo::prune(0);
+		let round_weight = T::WeightInfo::prune(1).saturating_sub(base_weight).max(1);
+		// collect first so the positions can be updated while going through the feeds
+		let feeds: Vec<(T::FeedId, RoundId)> = AutoPruning::<T>::iter().collect();
+		// iterating the positions (regardless of how many feeds are visited) and the cursor
+		let mut used: Weight = db.reads((feeds.len() as Weight).saturating_add(1));
+		// continue after the feed processed last, so a feed with a large backlog cannot starve
+		// the feeds after it
+		let start = AutoPruningCursor::<T>::get()
+			.and_then(|cursor| feeds.iter().position(|(feed_id, _)| *feed_id == cursor))
+			.map_or(0, |position| position + 1);
+		let mut last_processed = None;
+		for &(feed_id, first_to_prune) in feeds.iter().cycle().skip(start).take(feeds.len()) {
+			// reading the feed config
+			used = used.saturating_add(db.reads(1));
+			let remaining = limit.saturating_sub(used);
+			if remaining < base_weight.saturating_add(round_weight) {
+				break;
+			}
+			last_processed = Some(feed_id);
+			let config = match Self::feed_config(feed_id) {
+				Some(config) => config,
+				None => continue,
+			};
+			// same invariants as for manual pruning
+			match config.first_valid_round {
+				Some(first_valid_round) if first_to_prune <= first_valid_round => {}
+				_ => continue,
+			}
+			let keep_round = config.latest_round.saturating_sub(pruning_window);
+			if keep_round <= first_to_prune {
+				continue;
+			}
+
+			let max_rounds: RoundId =
+				((remaining - base_weight) / round_weight).saturated_into();
+			let keep_round = keep_round.min(first_to_prune.saturating_add(max_rounds));
+			// synced on drop
+			Feed::<T>::new(feed_id, config).prune_rounds(first_to_prune, keep_round);
+			AutoPruning::<T>::insert(feed_id, keep_round);
+			used = used
+				.saturating_add(T::WeightInfo::prune(keep_round - first_to_prune))
+				.saturating_add(db.writes(1));
+		}
+		if let Some(feed_id) = last_processed {
+			AutoPruningCursor::<T>::put(feed_id);
+			used = used.saturating_add(db.writes(1));
+		}
+		used
+	}
+
+	/// Record the `submission` of `oracle` for the given feed and round.
+	///
+	/// Returns the weight of the path taken through the function.
+	///
+	/// **Warning:** Fallible function that changes storage.
+	#[transactional]
+	fn do_submit(
+		oracle: T::AccountId,
+		feed_id: T::FeedId,
+		round_id: RoundId,
+		submission: T::Value,
+	) -> Result<Weight, DispatchError> {
+		let mut feed = Feed::<T>::load_from(feed_id).ok_or(Error::<T>::FeedNotFound)?;
+		let mut oracle_status =
+			Self::oracle_status(feed_id, &oracle).ok_or(Error::<T>::NotOracle)?;
+		feed.ensure_valid_round(&oracle, round_id)?;
+
+		let (min_val, max_val) = feed.config.submission_value_bounds;
+		ensure!(submission >= min_val, Error::<T>::SubmissionBelowMinimum);
+		ensure!(submission <= max_val, Error::<T>::SubmissionAboveMaximum);
+
+		let new_round_id = feed.reporting_round_id().saturating_add(One::one());
+		let next_eligible_round = oracle_status
+			.last_started_round
+			.unwrap_or(Zero::zero())
+			.checked_add(feed.config.restart_delay)
+			.ok_or(Error::<T>::Overflow)?
+			.checked_add(One::one())
+			.ok_or(Error::<T>::Overflow)?;
+		let eligible_to_start =
+			round_id >= next_eligible_round || oracle_status.last_started_round.is_none();
+
+		let (mut opening_weight, mut closing_weight) = (None, None);
+
+		// initialize the round if conditions are met
+		if round_id == new_round_id && eligible_to_start {
+			opening_weight = Some(if feed.is_timed_out(feed.reporting_round_id()) {
+				T::WeightInfo::submit_closing_timed_out_round()
+			} else {
+				T::WeightInfo::submit_opening_round_answers()
+			});
+			let started_at = feed.initialize_round(new_round_id)?;
+
+			Self::deposit_event(Event::NewRound(
+				feed_id,
+				new_round_id,
+				oracle.clone(),
+				started_at,
+			));
+
+			oracle_status.last_started_round = Some(new_round_id);
+		}
+
+		// record submission
+		let mut details =
+			Details::<T>::take(feed_id, round_id).ok_or(Error::<T>::NotAcceptingSubmissions)?;
+		details
+			.submissions
+			.try_push(submission)
+			.map_err(|_| Error::<T>::NotAcceptingSubmissions)?;
+
+		oracle_status.last_reported_round = Some(round_id);
+		oracle_status.latest_submission = Some(submission);
+		OracleStatuses::<T>::insert(feed_id, &oracle, oracle_status);
+		Self::deposit_event(Event::SubmissionReceived(
+			feed_id,
+			round_id,
+			submission,
+			oracle.clone(),
+		));
+
+		// update round answer
+		let (min_count, max_count) = details.submission_count_bounds;
+		if details.submissions.len() >= min_count as usize {
+			closing_weight = Some(T::WeightInfo::submit_closing_answer(
+				details.submissions.len() as u32,
+			));
+			let new_answer = median(&mut details.submissions.to_vec());
+			let mut round = Self::round(feed_id, round_id).ok_or(Error::<T>::RoundNotFound)?;
+			round.answer = Some(new_answer);
+			let updated_at = frame_system::Pallet::<T>::block_number();
+			round.updated_at = Some(updated_at);
+			round.updated_at_timestamp = Some(Self::timestamp());
+			round.answered_in_round = Some(round_id);
+			feed.index_round(round_id, &round);
+			Rounds::<T>::insert(feed_id, round_id, round);
+
+			feed.config.latest_round = round_id;
+			if feed.config.first_valid_round.is_none() {
+				feed.config.first_valid_round = Some(round_id);
+			}
+			// the previous rounds is not eligible for answers any more, so we close it
+			let prev_round_id = round_id.saturating_sub(1);
+			if prev_round_id > 0 {
+				Details::<T>::remove(feed_id, prev_round_id);
+			}
+
+			Self::deposit_event(Event::AnswerUpdated(
+				feed_id, round_id, new_answer, updated_at,
+			));
+		}
+
+		// update oracle rewards and try to reserve them
+		let payment = details.payment;
+		T::Currency::reserve(&T::PalletId::get().into_account(), payment)
+			.or_else(|_| -> DispatchResult {
+				// track the debt in case we cannot reserve
+				Debt::<T>::try_mutate(|debt| {
+					*debt = debt.checked_add(&payment).ok_or(Error::<T>::Overflow)?;
+					Ok(())
+				})
+			})?;
+		let mut oracle_meta = Self::oracle(&oracle).ok_or(Error::<T>::OracleNotFound)?;
+		oracle_meta.withdrawable = oracle_meta
+			.withdrawable
+			.checked_add(&payment)
+			.ok_or(Error::<T>::Overflow)?;
+		Oracles::<T>::insert(&oracle, oracle_meta);
+
+		// delete the details if the maximum count has been reached
+		if details.submissions.len() < max_count as usize {
+			Details::<T>::insert(feed_id, round_id, details);
 		}
+
+		Ok(Self::submit_weight(opening_weight, closing_weight))
+	}
+}
+
+/// A `UnixTime` implementation that always reports the unix epoch.
+///
+/// Can be used as `Config::Time` by runtimes that do not keep track of time.
+/// Rounds will record a timestamp of zero and only block based timeouts
+/// will take effect.
+pub struct NoTimestamp;
+
+impl UnixTime for NoTimestamp {
+	fn now() -> core::time::Duration {
+		Default::default()
 	}
 }
 
 /// Proxy used for interaction with a feed.
 /// `should_sync` flag determines whether the `config` is put into
 /// storage on `drop`.
-pub struct Feed<T: Trait> {
+pub struct Feed<T: Config> {
 	id: T::FeedId,
 	config: FeedConfigOf<T>,
 	should_sync: bool,
 }
 
-impl<T: Trait> Feed<T> {
+impl<T: Config> Feed<T> {
 	// --- constructors ---
 
 	/// Create a new feed with the given id and config.
@@ -1098,25 +1837,60 @@ impl<T: Trait> Feed<T> {
 		Ok(())
 	}
 
+	/// Return the round `oracle` can currently submit to (if any).
+	///
+	/// Prefers the reporting round while it accepts submissions and falls back to the next
+	/// round if the oracle is allowed to start it.
+	fn eligible_round(&self, oracle: &T::AccountId) -> Option<RoundId> {
+		let status = self.status(oracle)?;
+		let current_round = self.reporting_round_id();
+		if self.details(current_round).is_some()
+			&& self.ensure_valid_round(oracle, current_round).is_ok()
+		{
+			return Some(current_round);
+		}
+		let new_round = current_round.saturating_add(One::one());
+		let eligible_to_start = status
+			.last_started_round
+			.map(|last| {
+				new_round
+					>= last
+						.saturating_add(self.config.restart_delay)
+						.saturating_add(One::one())
+			})
+			.unwrap_or(true);
+		if eligible_to_start && self.ensure_valid_round(oracle, new_round).is_ok() {
+			Some(new_round)
+		} else {
+			None
+		}
+	}
+
 	/// Check whether a round is timed out.
+	/// Uses the clock (blocks or timestamps) the round timeout is expressed in.
 	/// Returns `false` for rounds not present in storage.
 	fn is_timed_out(&self, round: RoundId) -> bool {
 		// Assumption: returning false for non-existent rounds is fine.
-		let started_at = self
+		let (started_at, started_at_timestamp) = self
 			.round(round)
-			.map(|r| r.started_at)
-			.unwrap_or(Zero::zero());
-		let timeout = self
-			.details(round)
-			.map(|d| d.timeout)
-			.unwrap_or(Zero::zero());
-		let block_num = frame_system::Module::<T>::block_number();
-
-		started_at > Zero::zero()
-			&& timeout > Zero::zero()
-			&& started_at
-				.saturating_add(timeout)
-				< block_num
+			.map(|r| (r.started_at, r.started_at_timestamp))
+			.unwrap_or((Zero::zero(), Zero::zero()));
+		let timeout = self.details(round).map(|d| d.timeout).unwrap_or_default();
+
+		match timeout {
+			RoundTimeout::Blocks(timeout) => {
+				let block_num = frame_system::Pallet::<T>::block_number();
+				started_at > Zero::zero()
+					&& timeout > Zero::zero()
+					&& started_at.saturating_add(timeout) < block_num
+			}
+			RoundTimeout::Millis(timeout) => {
+				let now = Pallet::<T>::timestamp();
+				started_at_timestamp > Zero::zero()
+					&& timeout > Zero::zero()
+					&& started_at_timestamp.saturating_add(timeout) < now
+			}
+		}
 	}
 
 	/// Check whether a round has been updated.
@@ -1135,12 +1909,43 @@ impl<T: Trait> Feed<T> {
 
 	// --- mutators ---
 
+	/// Store `answer` as the answer of round 1 and mark it as the latest (valid) round.
+	///
+	/// Used to seed feeds created at genesis, so it expects a fresh feed.
+	fn seed_answer(&mut self, answer: T::Value) {
+		let round_id = RoundId::one();
+		let now = frame_system::Pallet::<T>::block_number();
+		let now_timestamp = Pallet::<T>::timestamp();
+		let round = Round {
+			started_at: now,
+			started_at_timestamp: now_timestamp,
+			answer: Some(answer),
+			updated_at: Some(now),
+			updated_at_timestamp: Some(now_timestamp),
+			answered_in_round: Some(round_id),
+		};
+		self.index_round(round_id, &round);
+		Rounds::<T>::insert(self.id, round_id, round);
+		self.config.reporting_round = round_id;
+		self.config.latest_round = round_id;
+		self.config.first_valid_round = Some(round_id);
+	}
+
 	/// Add the given oracles to the feed.
 	///
 	/// **Warning:** Fallible function that changes storage.
-	// TODO: use [require_transactional](https://github.com/paritytech/substrate/issues/7004)
-	// after migrating to Substrate v3 for this and others.
+	#[require_transactional]
 	fn add_oracles(&mut self, to_add: Vec<(T::AccountId, T::AccountId)>) -> DispatchResult {
+		self.add_oracles_unchecked(to_add)
+	}
+
+	/// Add the given oracles to the feed without requiring a transactional layer.
+	///
+	/// **Warning:** Fallible function that changes storage, only used by `create_feed_unchecked`.
+	fn add_oracles_unchecked(
+		&mut self,
+		to_add: Vec<(T::AccountId, T::AccountId)>,
+	) -> DispatchResult {
 		let new_count = self
 			.oracle_count()
 			// saturating is fine because we inforce a limit below
@@ -1182,7 +1987,7 @@ impl<T: Trait> Feed<T> {
 				}
 				Ok(())
 			})?;
-			Module::<T>::deposit_event(RawEvent::OraclePermissionsUpdated(self.id, oracle, true));
+			Pallet::<T>::deposit_event(Event::OraclePermissionsUpdated(self.id, oracle, true));
 		}
 
 		Ok(())
@@ -1191,6 +1996,7 @@ impl<T: Trait> Feed<T> {
 	/// Disable the given oracles.
 	///
 	/// **Warning:** Fallible function that changes storage.
+	#[require_transactional]
 	fn disable_oracles(&mut self, to_disable: Vec<T::AccountId>) -> DispatchResult {
 		let disabled_count = to_disable.len() as u32;
 		self.config.oracle_count = self.config.oracle_count
@@ -1201,7 +2007,7 @@ impl<T: Trait> Feed<T> {
 			ensure!(status.ending_round.is_none(), Error::<T>::OracleDisabled);
 			status.ending_round = Some(self.reporting_round_id());
 			OracleStatuses::<T>::insert(self.id, &d, status);
-			Module::<T>::deposit_event(RawEvent::OraclePermissionsUpdated(self.id, d, false));
+			Pallet::<T>::deposit_event(Event::OraclePermissionsUpdated(self.id, d, false));
 		}
 		Ok(())
 	}
@@ -1215,7 +2021,7 @@ impl<T: Trait> Feed<T> {
 		payment: BalanceOf<T>,
 		submission_count_bounds: (u32, u32),
 		restart_delay: RoundId,
-		timeout: T::BlockNumber,
+		timeout: RoundTimeoutOf<T>,
 	) -> DispatchResult {
 		let (min, max) = submission_count_bounds;
 		ensure!(max >= min, Error::<T>::WrongBounds);
@@ -1237,7 +2043,7 @@ impl<T: Trait> Feed<T> {
 		self.config.restart_delay = restart_delay;
 		self.config.timeout = timeout;
 
-		Module::<T>::deposit_event(RawEvent::RoundDetailsUpdated(
+		Pallet::<T>::deposit_event(Event::RoundDetailsUpdated(
 			self.id,
 			payment,
 			submission_count_bounds,
@@ -1263,18 +2069,36 @@ impl<T: Trait> Feed<T> {
 			self.id,
 			new_round_id,
 			RoundDetails {
-				submissions: Vec::new(),
+				submissions: Default::default(),
 				submission_count_bounds: self.config.submission_count_bounds,
 				payment: self.config.payment,
 				timeout: self.config.timeout,
 			},
 		);
-		let started_at = frame_system::Module::<T>::block_number();
-		Rounds::<T>::insert(self.id, new_round_id, Round::new(started_at));
+		let started_at = frame_system::Pallet::<T>::block_number();
+		Rounds::<T>::insert(
+			self.id,
+			new_round_id,
+			Round::new(started_at, Pallet::<T>::timestamp()),
+		);
 
 		Ok(started_at)
 	}
 
+	/// Remove the rounds in `[first_to_prune, keep_round)` and move the first valid round
+	/// up to `keep_round`.
+	///
+	/// **Warning:** Does not check the pruning invariants. Changes storage.
+	fn prune_rounds(&mut self, first_to_prune: RoundId, keep_round: RoundId) {
+		let mut round = first_to_prune;
+		while round < keep_round {
+			Rounds::<T>::remove(self.id, round);
+			Details::<T>::remove(self.id, round);
+			round += RoundId::one();
+		}
+		self.config.first_valid_round = self.config.first_valid_round.map(|r| r.max(keep_round));
+	}
+
 	/// Close a timed out round and remove its details.
 	///
 	/// **Warning:** Fallible function that changes storage.
@@ -1284,15 +2108,24 @@ impl<T: Trait> Feed<T> {
 		let mut timed_out_round = self.round(timed_out_id).ok_or(Error::<T>::RoundNotFound)?;
 		timed_out_round.answer = prev_round.answer;
 		timed_out_round.answered_in_round = prev_round.answered_in_round;
-		let updated_at = frame_system::Module::<T>::block_number();
+		let updated_at = frame_system::Pallet::<T>::block_number();
 		timed_out_round.updated_at = Some(updated_at);
+		timed_out_round.updated_at_timestamp = Some(Pallet::<T>::timestamp());
 
+		self.index_round(timed_out_id, &timed_out_round);
 		Rounds::<T>::insert(self.id, timed_out_id, timed_out_round);
 		Details::<T>::remove(self.id, timed_out_id);
 
 		Ok(())
 	}
 
+	/// Write the round to the offchain index if it has been answered.
+	fn index_round(&self, round_id: RoundId, round: &RoundOf<T>) {
+		if let Ok(data) = RoundDataOf::<T>::try_from(round.clone()) {
+			sp_io::offchain_index::set(&round_history_key(self.id, round_id), &data.encode());
+		}
+	}
+
 	/// Store the feed config in storage.
 	fn sync_to_storage(&mut self) {
 		Feeds::<T>::insert(self.id, sp_std::mem::take(&mut self.config));
@@ -1300,7 +2133,7 @@ impl<T: Trait> Feed<T> {
 }
 
 // We want the feed to sync automatically when going out of scope.
-impl<T: Trait> Drop for Feed<T> {
+impl<T: Config> Drop for Feed<T> {
 	fn drop(&mut self) {
 		if self.should_sync {
 			self.sync_to_storage();
@@ -1308,7 +2141,7 @@ impl<T: Trait> Drop for Feed<T> {
 	}
 }
 
-impl<T: Trait> FeedOracle<T> for Module<T> {
+impl<T: Config> FeedOracle<T> for Pallet<T> {
 	type FeedId = T::FeedId;
 	type Feed = Feed<T>;
 	type MutableFeed = Feed<T>;
@@ -1326,7 +2159,7 @@ impl<T: Trait> FeedOracle<T> for Module<T> {
 	}
 }
 
-impl<T: Trait> FeedInterface<T> for Feed<T> {
+impl<T: Config> FeedInterface<T> for Feed<T> {
 	type Value = T::Value;
 
 	/// Returns the id of the first round that contains non-default data.
@@ -1349,7 +2182,7 @@ impl<T: Trait> FeedInterface<T> for Feed<T> {
 		let latest_round = self.latest_round();
 		self.data_at(latest_round).unwrap_or_else(|| {
 			debug_assert!(false, "The latest round data should always be available.");
-			frame_support::debug::error!(
+			log::error!(
 				"Latest round is data missing which should never happen. (Latest round id: {:?})",
 				latest_round
 			);
@@ -1358,7 +2191,7 @@ impl<T: Trait> FeedInterface<T> for Feed<T> {
 	}
 }
 
-impl<T: Trait> MutableFeedInterface<T> for Feed<T> {
+impl<T: Config> MutableFeedInterface<T> for Feed<T> {
 	/// Requests that a new round be started for the feed.
 	///
 	/// Returns `Ok` on success and `Err` in case the round could not be started.
@@ -1375,7 +2208,7 @@ impl<T: Trait> MutableFeedInterface<T> for Feed<T> {
 		);
 		let started_at = self.initialize_round(new_round)?;
 
-		Module::<T>::deposit_event(RawEvent::NewRound(
+		Pallet::<T>::deposit_event(Event::NewRound(
 			self.id, new_round, requester, started_at,
 		));
 
@@ -1390,9 +2223,12 @@ pub trait WeightInfo {
 	fn accept_ownership() -> Weight;
 	fn submit_opening_round_answers() -> Weight;
 	fn submit_closing_answer(o: u32) -> Weight;
+	fn submit_closing_timed_out_round() -> Weight;
+	fn submit_plain() -> Weight;
 	fn change_oracles(d: u32, n: u32) -> Weight;
 	fn update_future_rounds() -> Weight;
 	fn prune(r: u32) -> Weight;
+	fn set_auto_pruning() -> Weight;
 	fn set_requester() -> Weight;
 	fn remove_requester() -> Weight;
 	fn request_new_round() -> Weight;
@@ -0,0 +1,252 @@
+//! Storage migrations for the chainlink feed pallet.
+
+use super::*;
+use frame_support::traits::GetStorageVersion;
+
+/// Migrate the storage from the on-chain storage version to the current one.
+///
+/// Called from `on_runtime_upgrade`, so it needs to be a no-op once the storage is up to date.
+pub fn migrate<T: Config>() -> Weight {
+	let on_chain_version = Pallet::<T>::on_chain_storage_version();
+	let mut weight = T::DbWeight::get().reads(1);
+
+	if on_chain_version < 1 {
+		// the `decl_storage` based pallet tracked its layout in a storage value of its own
+		if v1::take_legacy_version() == v1::Releases::V0 {
+			// Version 0 is translated into the current (bounded) types directly.
+			weight = weight.saturating_add(v1::migrate::<T>());
+		} else {
+			weight = weight.saturating_add(v2::migrate::<T>());
+		}
+		weight = weight.saturating_add(T::DbWeight::get().reads_writes(1, 1));
+	} else if on_chain_version < 2 {
+		weight = weight.saturating_add(v2::migrate::<T>());
+	}
+
+	if on_chain_version < STORAGE_VERSION {
+		STORAGE_VERSION.put::<Pallet<T>>();
+		weight = weight.saturating_add(T::DbWeight::get().writes(1));
+	}
+
+	weight
+}
+
+/// Convert `values` into a bounded vector, dropping the values exceeding the bound.
+fn truncate_to_bound<V, S: Get<u32>>(mut values: Vec<V>) -> BoundedVec<V, S> {
+	let bound = S::get() as usize;
+	if values.len() > bound {
+		log::warn!(
+			"Truncating {} values to the bound of {} during the storage migration.",
+			values.len(),
+			bound
+		);
+		values.truncate(bound);
+	}
+	values.try_into().unwrap_or_default()
+}
+
+/// Migration from the `decl_storage` based pallet (version 0).
+///
+/// The `decl_storage` based pallet tracked its layout in its own `StorageVersion` value.
+/// Storage without that value (`Releases::V0`) stored timeouts as plain block numbers and did not
+/// record round timestamps. `Feeds`, `Rounds` and `Details` are translated to the current format
+/// (with timestamps set to zero), while `Oracles`, `OracleStatuses` and `Requesters` kept their
+/// layout and encoding. Storage at `Releases::V1` already has the version 1 layout and is
+/// migrated by `v2`.
+pub mod v1 {
+	use super::*;
+	use frame_support::storage::migration::take_storage_value;
+
+	/// The storage layout versions of the `decl_storage` based pallet.
+	#[derive(Encode, Decode, Eq, PartialEq, RuntimeDebug)]
+	pub(crate) enum Releases {
+		/// Timeouts are plain block numbers and rounds have no timestamps.
+		V0,
+		/// Timeouts are `RoundTimeout`s and rounds record their timestamps.
+		V1,
+	}
+
+	/// Remove the storage version of the `decl_storage` based pallet and return it.
+	///
+	/// `decl_storage` used the fixed `ChainlinkFeed` prefix for the storage of the pallet.
+	pub(crate) fn take_legacy_version() -> Releases {
+		take_storage_value::<Releases>(b"ChainlinkFeed", b"StorageVersion", &[])
+			.unwrap_or(Releases::V0)
+	}
+
+	#[derive(Encode, Decode)]
+	pub(crate) struct OldFeedConfig<AccountId, Balance, BlockNumber, Value> {
+		pub(crate) owner: AccountId,
+		pub(crate) pending_owner: Option<AccountId>,
+		pub(crate) submission_value_bounds: (Value, Value),
+		pub(crate) submission_count_bounds: (u32, u32),
+		pub(crate) payment: Balance,
+		pub(crate) timeout: BlockNumber,
+		pub(crate) decimals: u8,
+		pub(crate) description: Vec<u8>,
+		pub(crate) restart_delay: RoundId,
+		pub(crate) reporting_round: RoundId,
+		pub(crate) latest_round: RoundId,
+		pub(crate) first_valid_round: Option<RoundId>,
+		pub(crate) oracle_count: u32,
+	}
+	type OldFeedConfigOf<T> = OldFeedConfig<
+		<T as frame_system::Config>::AccountId,
+		BalanceOf<T>,
+		<T as frame_system::Config>::BlockNumber,
+		<T as Config>::Value,
+	>;
+
+	#[derive(Encode, Decode)]
+	pub(crate) struct OldRound<BlockNumber, Value> {
+		pub(crate) started_at: BlockNumber,
+		pub(crate) answer: Option<Value>,
+		pub(crate) updated_at: Option<BlockNumber>,
+		pub(crate) answered_in_round: Option<RoundId>,
+	}
+	type OldRoundOf<T> =
+		OldRound<<T as frame_system::Config>::BlockNumber, <T as Config>::Value>;
+
+	#[derive(Encode, Decode)]
+	pub(crate) struct OldRoundDetails<Balance, BlockNumber, Value> {
+		pub(crate) submissions: Vec<Value>,
+		pub(crate) submission_count_bounds: (u32, u32),
+		pub(crate) payment: Balance,
+		pub(crate) timeout: BlockNumber,
+	}
+	type OldRoundDetailsOf<T> = OldRoundDetails<
+		BalanceOf<T>,
+		<T as frame_system::Config>::BlockNumber,
+		<T as Config>::Value,
+	>;
+
+	/// Translate `Feeds`, `Rounds` and `Details` to the version 1 format.
+	///
+	/// Returns the weight consumed.
+	pub fn migrate<T: Config>() -> Weight {
+		let mut translated: Weight = 0;
+
+		Feeds::<T>::translate::<OldFeedConfigOf<T>, _>(|_, old| {
+			translated = translated.saturating_add(1);
+			Some(FeedConfig {
+				owner: old.owner,
+				pending_owner: old.pending_owner,
+				submission_value_bounds: old.submission_value_bounds,
+				submission_count_bounds: old.submission_count_bounds,
+				payment: old.payment,
+				timeout: RoundTimeout::Blocks(old.timeout),
+				decimals: old.decimals,
+				description: truncate_to_bound(old.description),
+				restart_delay: old.restart_delay,
+				reporting_round: old.reporting_round,
+				latest_round: old.latest_round,
+				first_valid_round: old.first_valid_round,
+				oracle_count: old.oracle_count,
+			})
+		});
+		Rounds::<T>::translate::<OldRoundOf<T>, _>(|_, _, old| {
+			translated = translated.saturating_add(1);
+			Some(Round {
+				started_at: old.started_at,
+				started_at_timestamp: Zero::zero(),
+				answer: old.answer,
+				updated_at_timestamp: old.updated_at.map(|_| Zero::zero()),
+				updated_at: old.updated_at,
+				answered_in_round: old.answered_in_round,
+			})
+		});
+		Details::<T>::translate::<OldRoundDetailsOf<T>, _>(|_, _, old| {
+			translated = translated.saturating_add(1);
+			Some(RoundDetails {
+				submissions: truncate_to_bound(old.submissions),
+				submission_count_bounds: old.submission_count_bounds,
+				payment: old.payment,
+				timeout: RoundTimeout::Blocks(old.timeout),
+			})
+		});
+
+		T::DbWeight::get().reads_writes(translated, translated)
+	}
+}
+
+/// Migration from plain vectors to bounded vectors (version 1 to version 2).
+///
+/// `FeedConfig.description` and `RoundDetails.submissions` became bounded by `StringLimit` and
+/// `OracleCountLimit` respectively. The encoding of the bounded vectors is the same, so this only
+/// truncates values that exceed the limits (and would thus fail to decode).
+pub mod v2 {
+	use super::*;
+
+	#[derive(Encode, Decode)]
+	pub(crate) struct OldFeedConfig<AccountId, Balance, BlockNumber, Value> {
+		pub(crate) owner: AccountId,
+		pub(crate) pending_owner: Option<AccountId>,
+		pub(crate) submission_value_bounds: (Value, Value),
+		pub(crate) submission_count_bounds: (u32, u32),
+		pub(crate) payment: Balance,
+		pub(crate) timeout: RoundTimeout<BlockNumber>,
+		pub(crate) decimals: u8,
+		pub(crate) description: Vec<u8>,
+		pub(crate) restart_delay: RoundId,
+		pub(crate) reporting_round: RoundId,
+		pub(crate) latest_round: RoundId,
+		pub(crate) first_valid_round: Option<RoundId>,
+		pub(crate) oracle_count: u32,
+	}
+	type OldFeedConfigOf<T> = OldFeedConfig<
+		<T as frame_system::Config>::AccountId,
+		BalanceOf<T>,
+		<T as frame_system::Config>::BlockNumber,
+		<T as Config>::Value,
+	>;
+
+	#[derive(Encode, Decode)]
+	pub(crate) struct OldRoundDetails<Balance, BlockNumber, Value> {
+		pub(crate) submissions: Vec<Value>,
+		pub(crate) submission_count_bounds: (u32, u32),
+		pub(crate) payment: Balance,
+		pub(crate) timeout: RoundTimeout<BlockNumber>,
+	}
+	type OldRoundDetailsOf<T> = OldRoundDetails<
+		BalanceOf<T>,
+		<T as frame_system::Config>::BlockNumber,
+		<T as Config>::Value,
+	>;
+
+	/// Translate `Feeds` and `Details` to the version 2 format.
+	///
+	/// Returns the weight consumed.
+	pub fn migrate<T: Config>() -> Weight {
+		let mut translated: Weight = 0;
+
+		Feeds::<T>::translate::<OldFeedConfigOf<T>, _>(|_, old| {
+			translated = translated.saturating_add(1);
+			Some(FeedConfig {
+				owner: old.owner,
+				pending_owner: old.pending_owner,
+				submission_value_bounds: old.submission_value_bounds,
+				submission_count_bounds: old.submission_count_bounds,
+				payment: old.payment,
+				timeout: old.timeout,
+				decimals: old.decimals,
+				description: truncate_to_bound(old.description),
+				restart_delay: old.restart_delay,
+				reporting_round: old.reporting_round,
+				latest_round: old.latest_round,
+				first_valid_round: old.first_valid_round,
+				oracle_count: old.oracle_count,
+			})
+		});
+		Details::<T>::translate::<OldRoundDetailsOf<T>, _>(|_, _, old| {
+			translated = translated.saturating_add(1);
+			Some(RoundDetails {
+				submissions: truncate_to_bound(old.submissions),
+				submission_count_bounds: old.submission_count_bounds,
+				payment: old.payment,
+				timeout: old.timeout,
+			})
+		});
+
+		T::DbWeight::get().reads_writes(translated, translated)
+	}
+}
@@ -1,37 +1,49 @@
 use super::*;
 
-use frame_support::weights::Weight;
-use frame_support::{assert_noop, assert_ok, impl_outer_origin, parameter_types};
+use crate as pallet_chainlink_feed;
+use frame_support::traits::{
+	GenesisBuild, GetStorageVersion, OnInitialize, OnRuntimeUpgrade, StorageVersion,
+};
+use frame_support::weights::{GetDispatchInfo, Weight};
+use frame_support::{assert_noop, assert_ok, parameter_types, PalletId};
 use sp_core::H256;
 
-use frame_system as system;
 use sp_runtime::{
 	testing::Header,
 	traits::{BlakeTwo256, IdentityLookup},
-	Perbill,
 };
 
-impl_outer_origin! {
-	pub enum Origin for Test {}
-}
+type UncheckedExtrinsic = frame_system::mocking::MockUncheckedExtrinsic<Test>;
+type Block = frame_system::mocking::MockBlock<Test>;
 
 // Configure a mock runtime to test the pallet.
+frame_support::construct_runtime!(
+	pub enum Test where
+		Block = Block,
+		NodeBlock = Block,
+		UncheckedExtrinsic = UncheckedExtrinsic,
+	{
+		System: frame_system::{Pallet, Call, Config, Storage, Event<T>},
+		Timestamp: pallet_timestamp::{Pallet, Call, Storage, Inherent},
+		Balances: pallet_balances::{Pallet, Call, Storage, Config<T>, Event<T>},
+		ChainlinkFeed: pallet_chainlink_feed::{Pallet, Call, Storage, Event<T>, Config<T>},
+	}
+);
 
-#[derive(Clone, Eq, PartialEq)]
-pub struct Test;
 parameter_types! {
 	pub const BlockHashCount: u64 = 250;
-	pub const MaximumBlockWeight: Weight = 1024;
-	pub const MaximumBlockLength: u32 = 2 * 1024;
-	pub const AvailableBlockRatio: Perbill = Perbill::from_percent(75);
+	pub const SS58Prefix: u8 = 42;
 }
 
 type AccountId = u64;
 type BlockNumber = u64;
-impl system::Trait for Test {
-	type BaseCallFilter = ();
+impl frame_system::Config for Test {
+	type BaseCallFilter = frame_support::traits::Everything;
+	type BlockWeights = ();
+	type BlockLength = ();
+	type DbWeight = ();
 	type Origin = Origin;
-	type Call = ();
+	type Call = Call;
 	type Index = u64;
 	type BlockNumber = BlockNumber;
 	type Hash = H256;
@@ -39,74 +51,84 @@ impl system::Trait for Test {
 	type AccountId = AccountId;
 	type Lookup = IdentityLookup<Self::AccountId>;
 	type Header = Header;
-	type Event = ();
+	type Event = Event;
 	type BlockHashCount = BlockHashCount;
-	type MaximumBlockWeight = MaximumBlockWeight;
-	type DbWeight = ();
-	type BlockExecutionWeight = ();
-	type ExtrinsicBaseWeight = ();
-	type MaximumExtrinsicWeight = MaximumBlockWeight;
-	type MaximumBlockLength = MaximumBlockLength;
-	type AvailableBlockRatio = AvailableBlockRatio;
 	type Version = ();
-	type PalletInfo = ();
+	type PalletInfo = PalletInfo;
 	type AccountData = pallet_balances::AccountData<u64>;
 	type OnNewAccount = ();
 	type OnKilledAccount = ();
 	type SystemWeightInfo = ();
+	type SS58Prefix = SS58Prefix;
+	type OnSetCode = ();
+}
+
+parameter_types! {
+	pub const MinimumPeriod: u64 = 1;
+}
+
+impl pallet_timestamp::Config for Test {
+	type Moment = u64;
+	type OnTimestampSet = ();
+	type MinimumPeriod = MinimumPeriod;
+	type WeightInfo = ();
 }
-type System = frame_system::Module<Test>;
 
 parameter_types! {
 	pub const ExistentialDeposit: u64 = 1;
 }
 
 type Balance = u64;
-impl pallet_balances::Trait for Test {
+impl pallet_balances::Config for Test {
 	type MaxLocks = ();
+	type MaxReserves = ();
+	type ReserveIdentifier = [u8; 8];
 	type Balance = Balance;
-	type Event = ();
+	type Event = Event;
 	type DustRemoval = ();
 	type ExistentialDeposit = ExistentialDeposit;
 	type AccountStore = System;
 	type WeightInfo = ();
 }
-type Balances = pallet_balances::Module<Test>;
 
 const MIN_RESERVE: u64 = 100;
 
 parameter_types! {
-	pub const FeedModuleId: ModuleId = ModuleId(*b"linkfeed");
+	pub const FeedPalletId: PalletId = PalletId(*b"linkfeed");
 	pub const MinimumReserve: u64 = MIN_RESERVE;
 	pub const StringLimit: u32 = 15;
 	pub const OracleLimit: u32 = 10;
 	pub const FeedLimit: u16 = 10;
+	pub const MaxBatchSize: u32 = 4;
 	pub const PruningWindow: u32 = 3;
+	pub const PruningWeightLimit: Weight = 700_000_000;
 }
 
 type FeedId = u16;
 type Value = u64;
 
-impl Trait for Test {
-	type Event = ();
+impl Config for Test {
+	type Event = Event;
 	type FeedId = FeedId;
 	type Value = Value;
 	type Currency = Balances;
-	type ModuleId = FeedModuleId;
+	type PalletId = FeedPalletId;
 	type MinimumReserve = MinimumReserve;
 	type StringLimit = StringLimit;
 	type OracleCountLimit = OracleLimit;
 	type FeedLimit = FeedLimit;
+	type MaxBatchSize = MaxBatchSize;
 	type PruningWindow = PruningWindow;
+	type PruningWeightLimit = PruningWeightLimit;
+	type Time = Timestamp;
 	type WeightInfo = ();
 }
-type ChainlinkFeed = crate::Module<Test>;
 
 #[derive(Debug, Default)]
 struct FeedBuilder {
 	owner: Option<AccountId>,
 	payment: Option<Balance>,
-	timeout: Option<BlockNumber>,
+	timeout: Option<RoundTimeout<BlockNumber>>,
 	value_bounds: Option<(Value, Value)>,
 	min_submissions: Option<u32>,
 	description: Option<Vec<u8>>,
@@ -130,7 +152,12 @@ impl FeedBuilder {
 	}
 
 	fn timeout(mut self, t: BlockNumber) -> Self {
-		self.timeout = Some(t);
+		self.timeout = Some(RoundTimeout::Blocks(t));
+		self
+	}
+
+	fn timeout_millis(mut self, t: u64) -> Self {
+		self.timeout = Some(RoundTimeout::Millis(t));
 		self
 	}
 
@@ -162,7 +189,7 @@ impl FeedBuilder {
 	fn build_and_store(self) -> DispatchResultWithPostInfo {
 		let owner = Origin::signed(self.owner.unwrap_or(1));
 		let payment = self.payment.unwrap_or(20);
-		let timeout = self.timeout.unwrap_or(1);
+		let timeout = self.timeout.unwrap_or(RoundTimeout::Blocks(1));
 		let value_bounds = self.value_bounds.unwrap_or((1, 1_000));
 		let min_submissions = self.min_submissions.unwrap_or(2);
 		let decimals = 5;
@@ -190,7 +217,7 @@ pub(crate) fn new_test_ext() -> sp_io::TestExternalities {
 		.build_storage::<Test>()
 		.unwrap();
 
-	let module_account: AccountId = FeedModuleId::get().into_account();
+	let module_account: AccountId = FeedPalletId::get().into_account();
 	pallet_balances::GenesisConfig::<Test> {
 		balances: vec![(module_account, 100 * MIN_RESERVE)],
 	}
@@ -200,6 +227,7 @@ pub(crate) fn new_test_ext() -> sp_io::TestExternalities {
 	crate::GenesisConfig::<Test> {
 		pallet_admin: module_account,
 		feed_creators: vec![1],
+		feeds: vec![],
 	}
 	.assimilate_storage(&mut t)
 	.unwrap();
@@ -207,13 +235,116 @@ pub(crate) fn new_test_ext() -> sp_io::TestExternalities {
 	t.into()
 }
 
+#[test]
+fn genesis_feeds_should_work() {
+	let mut t = frame_system::GenesisConfig::default()
+		.build_storage::<Test>()
+		.unwrap();
+	let module_account: AccountId = FeedPalletId::get().into_account();
+	pallet_balances::GenesisConfig::<Test> {
+		balances: vec![(module_account, 100 * MIN_RESERVE)],
+	}
+	.assimilate_storage(&mut t)
+	.unwrap();
+	crate::GenesisConfig::<Test> {
+		pallet_admin: module_account,
+		feed_creators: vec![1],
+		feeds: vec![
+			GenesisFeed {
+				owner: 1,
+				payment: 20,
+				timeout: RoundTimeout::Blocks(10),
+				submission_value_bounds: (1, 1_000),
+				min_submissions: 2,
+				decimals: 5,
+				description: b"desc".to_vec(),
+				restart_delay: 0,
+				oracles: vec![(2, 4), (3, 4)],
+				requesters: vec![(5, 1)],
+				initial_answer: Some(42),
+			},
+			GenesisFeed {
+				owner: 1,
+				payment: 20,
+				timeout: RoundTimeout::Blocks(10),
+				submission_value_bounds: (1, 1_000),
+				min_submissions: 1,
+				decimals: 5,
+				description: b"no answer".to_vec(),
+				restart_delay: 0,
+				oracles: vec![(2, 4)],
+				requesters: vec![],
+				initial_answer: None,
+			},
+		],
+	}
+	.assimilate_storage(&mut t)
+	.unwrap();
+	let mut ext: sp_io::TestExternalities = t.into();
+
+	ext.execute_with(|| {
+		System::set_block_number(1);
+		assert_eq!(ChainlinkFeed::feed_counter(), 2);
+
+		let feed = ChainlinkFeed::feed(0).expect("genesis feed should exist");
+		assert_eq!(feed.first_valid_round(), Some(1));
+		assert_eq!(feed.latest_round(), 1);
+		assert_eq!(feed.latest_data().answer, 42);
+		let config = ChainlinkFeed::feed_config(0).expect("feed config should exist");
+		assert_eq!(config.owner, 1);
+		assert_eq!(config.oracle_count, 2);
+		assert_eq!(config.description, b"desc".to_vec());
+		assert!(ChainlinkFeed::oracle_status(0, 2).is_some());
+		assert_eq!(ChainlinkFeed::oracle(3).map(|o| o.admin), Some(4));
+		assert_eq!(ChainlinkFeed::requester(0, 5).map(|r| r.delay), Some(1));
+
+		// the oracles can report for the next round right away
+		assert_ok!(ChainlinkFeed::submit(Origin::signed(2), 0, 2, 50));
+		assert_ok!(ChainlinkFeed::submit(Origin::signed(3), 0, 2, 60));
+		assert_eq!(ChainlinkFeed::feed(0).unwrap().latest_data().answer, 55);
+
+		let feed = ChainlinkFeed::feed(1).expect("genesis feed should exist");
+		assert_eq!(feed.first_valid_round(), None);
+		assert_eq!(feed.latest_round(), 0);
+		assert_ok!(ChainlinkFeed::submit(Origin::signed(2), 1, 1, 50));
+		assert_eq!(ChainlinkFeed::feed(1).unwrap().first_valid_round(), Some(1));
+	});
+}
+
+#[test]
+#[should_panic(expected = "outside of its submission value bounds")]
+fn genesis_answer_outside_of_bounds_should_panic() {
+	let mut t = frame_system::GenesisConfig::default()
+		.build_storage::<Test>()
+		.unwrap();
+	crate::GenesisConfig::<Test> {
+		pallet_admin: FeedPalletId::get().into_account(),
+		feed_creators: vec![1],
+		feeds: vec![GenesisFeed {
+			owner: 1,
+			payment: 20,
+			timeout: RoundTimeout::Blocks(10),
+			submission_value_bounds: (1, 1_000),
+			min_submissions: 1,
+			decimals: 5,
+			description: b"desc".to_vec(),
+			restart_delay: 0,
+			oracles: vec![(2, 4)],
+			requesters: vec![],
+			initial_answer: Some(1_001),
+		}],
+	}
+	.assimilate_storage(&mut t)
+	.unwrap();
+}
+
 #[test]
 fn feed_creation_should_work() {
 	new_test_ext().execute_with(|| {
 		assert_ok!(ChainlinkFeed::create_feed(
 			Origin::signed(1),
 			20,
-			10,
+			RoundTimeout::Blocks(10),
 			(10, 1_000),
 			3,
 			5,
@@ -315,8 +446,10 @@ fn submit_should_work() {
 			round,
 			Round {
 				started_at: 0,
+				started_at_timestamp: 0,
 				answer: Some(submission),
 				updated_at: Some(0),
+				updated_at_timestamp: Some(0),
 				answered_in_round: Some(1),
 			}
 		);
@@ -325,10 +458,10 @@ fn submit_should_work() {
 		assert_eq!(
 			details,
 			RoundDetails {
-				submissions: vec![submission, submission],
+				submissions: vec![submission, submission].try_into().unwrap(),
 				submission_count_bounds,
 				payment,
-				timeout,
+				timeout: RoundTimeout::Blocks(timeout),
 			}
 		);
 		let oracle_status =
@@ -450,6 +583,105 @@ fn submit_failure_cases() {
 	});
 }
 
+#[test]
+fn submit_charges_the_path_taken() {
+	new_test_ext().execute_with(|| {
+		let feed_id = 0;
+		let round_id = 1;
+		let submission = 42;
+		assert_ok!(FeedBuilder::new()
+			.min_submissions(3)
+			.oracles(vec![(2, 4), (3, 4), (4, 4)])
+			.build_and_store());
+		let weight_of = |oracle| {
+			ChainlinkFeed::submit(Origin::signed(oracle), feed_id, round_id, submission)
+				.expect("submission should work")
+				.actual_weight
+		};
+
+		assert_eq!(
+			weight_of(2),
+			Some(<() as WeightInfo>::submit_opening_round_answers())
+		);
+		assert_eq!(weight_of(3), Some(<() as WeightInfo>::submit_plain()));
+		assert_eq!(
+			weight_of(4),
+			Some(<() as WeightInfo>::submit_closing_answer(3))
+		);
+	});
+}
+
+#[test]
+fn submit_charges_opening_and_closing_in_one_submission() {
+	new_test_ext().execute_with(|| {
+		assert_ok!(FeedBuilder::new().min_submissions(1).build_and_store());
+
+		let oracle = 2;
+		let weight = ChainlinkFeed::submit(Origin::signed(oracle), 0, 1, 42)
+			.expect("submission should work")
+			.actual_weight;
+		// the submission opens the round and answers it
+		let plain = <() as WeightInfo>::submit_plain();
+		assert_eq!(
+			weight,
+			Some(
+				<() as WeightInfo>::submit_opening_round_answers()
+					+ <() as WeightInfo>::submit_closing_answer(1)
+					- plain
+			)
+		);
+		assert!(weight.unwrap() <= ChainlinkFeed::max_submit_weight());
+	});
+}
+
+#[test]
+fn submit_batch_should_work() {
+	new_test_ext().execute_with(|| {
+		let payment = 20;
+		assert_ok!(FeedBuilder::new().payment(payment).build_and_store());
+		assert_ok!(FeedBuilder::new().payment(payment).build_and_store());
+
+		let oracle = 2;
+		let no_feed = 1234;
+		let too_high = 1337;
+		assert_ok!(ChainlinkFeed::submit_batch(
+			Origin::signed(oracle),
+			vec![(0, 1, 42), (no_feed, 1, 42), (1, 1, too_high), (1, 1, 21)],
+		));
+		// the valid entries are recorded ...
+		let status = ChainlinkFeed::oracle_status(0, oracle).expect("oracle status should be present");
+		assert_eq!(status.latest_submission, Some(42));
+		let status = ChainlinkFeed::oracle_status(1, oracle).expect("oracle status should be present");
+		assert_eq!(status.latest_submission, Some(21));
+		let details = ChainlinkFeed::round_details(1, 1).expect("details should be present");
+		assert_eq!(details.submissions, vec![21]);
+		// ... while the failed ones leave no trace
+		assert_eq!(ChainlinkFeed::round_details(no_feed, 1), None);
+		let meta = ChainlinkFeed::oracle(oracle).expect("oracle should be present");
+		assert_eq!(meta.withdrawable, 2 * payment);
+	});
+}
+
+#[test]
+fn submit_batch_should_reject_oversized_batches() {
+	new_test_ext().execute_with(|| {
+		assert_ok!(FeedBuilder::new().build_and_store());
+
+		let oracle = 2;
+		let too_many = vec![(0, 1, 42); MaxBatchSize::get() as usize + 1];
+		// the declared weight is capped at `MaxBatchSize` submissions
+		let call = crate::Call::<Test>::submit_batch(too_many.clone());
+		assert_eq!(
+			call.get_dispatch_info().weight,
+			ChainlinkFeed::max_submit_weight() * MaxBatchSize::get() as Weight
+		);
+		assert_noop!(
+			ChainlinkFeed::submit_batch(Origin::signed(oracle), too_many),
+			Error::<Test>::BatchTooLarge
+		);
+	});
+}
+
 #[test]
 fn change_oracles_should_work() {
 	new_test_ext().execute_with(|| {
@@ -652,7 +884,7 @@ fn update_future_rounds_should_work() {
 		let new_min = 3;
 		let new_max = 3;
 		let new_delay = 1;
-		let new_timeout = 5;
+		let new_timeout = RoundTimeout::Blocks(5);
 		// failure cases
 		assert_noop!(
 			ChainlinkFeed::update_future_rounds(
@@ -734,6 +966,7 @@ fn update_future_rounds_should_work() {
 		let feed_id = 0;
 		let feed = ChainlinkFeed::feed_config(feed_id).expect("feed should be there");
 		assert_eq!(feed.payment, new_payment);
+		assert_eq!(feed.timeout, new_timeout);
 	});
 }
 
@@ -784,6 +1017,125 @@ fn admin_transfer_should_work() {
 	});
 }
 
+#[test]
+fn eligible_rounds_should_work() {
+	new_test_ext().execute_with(|| {
+		// oracles 2, 3 and 4 with a restart delay of 2
+		assert_ok!(FeedBuilder::new().timeout(10).build_and_store());
+		let feed_id = 0;
+
+		// every oracle can start the first round
+		assert_eq!(ChainlinkFeed::eligible_rounds(&2), vec![(feed_id, 1)]);
+		assert_eq!(ChainlinkFeed::eligible_rounds(&5), vec![]);
+
+		assert_ok!(ChainlinkFeed::submit(Origin::signed(2), feed_id, 1, 42));
+		// oracle 2 already reported and round 1 is not supersedable, yet
+		assert_eq!(ChainlinkFeed::eligible_rounds(&2), vec![]);
+		assert_eq!(ChainlinkFeed::eligible_rounds(&3), vec![(feed_id, 1)]);
+
+		assert_ok!(ChainlinkFeed::submit(Origin::signed(3), feed_id, 1, 42));
+		// round 1 is answered but still accepts the submission of oracle 4
+		assert_eq!(ChainlinkFeed::eligible_rounds(&4), vec![(feed_id, 1)]);
+		// oracle 3 may start round 2, but oracle 2 has to respect the restart delay
+		assert_eq!(ChainlinkFeed::eligible_rounds(&3), vec![(feed_id, 2)]);
+		assert_eq!(ChainlinkFeed::eligible_rounds(&2), vec![]);
+	});
+}
+
+#[test]
+fn feed_health_should_work() {
+	new_test_ext().execute_with(|| {
+		assert_ok!(FeedBuilder::new().min_submissions(2).build_and_store());
+		let feed_id = 0;
+		assert_eq!(ChainlinkFeed::feed_ids(), vec![feed_id]);
+		assert_eq!(ChainlinkFeed::feed_health(1), None);
+		assert_eq!(
+			ChainlinkFeed::feed_health(feed_id),
+			Some(FeedHealth {
+				latest_round: 0,
+				updated_at: None,
+				reporting_round: 0,
+				pending_submissions: 0,
+				min_submissions: 2,
+			})
+		);
+
+		System::set_block_number(3);
+		assert_ok!(ChainlinkFeed::submit(Origin::signed(2), feed_id, 1, 42));
+		let health = ChainlinkFeed::feed_health(feed_id).unwrap();
+		assert_eq!(health.reporting_round, 1);
+		assert_eq!(health.pending_submissions, 1);
+
+		assert_ok!(ChainlinkFeed::submit(Origin::signed(3), feed_id, 1, 42));
+		let health = ChainlinkFeed::feed_health(feed_id).unwrap();
+		assert_eq!(health.latest_round, 1);
+		assert_eq!(health.updated_at, Some(3));
+		assert_eq!(health.pending_submissions, 0);
+
+		let fund: AccountId = FeedPalletId::get().into_account();
+		assert_eq!(
+			ChainlinkFeed::fund_health(),
+			FundHealth {
+				free_balance: Balances::free_balance(&fund),
+				debt: ChainlinkFeed::debt(),
+			}
+		);
+	});
+}
+
+#[test]
+fn latest_round_data_should_work() {
+	new_test_ext().execute_with(|| {
+		assert_ok!(FeedBuilder::new().build_and_store());
+		let feed_id = 0;
+		assert_eq!(ChainlinkFeed::latest_round_data(feed_id), None);
+		assert_eq!(ChainlinkFeed::latest_round_data(1), None);
+
+		System::set_block_number(2);
+		assert_ok!(ChainlinkFeed::submit(Origin::signed(2), feed_id, 1, 42));
+		assert_ok!(ChainlinkFeed::submit(Origin::signed(3), feed_id, 1, 44));
+		let (round_id, data) =
+			ChainlinkFeed::latest_round_data(feed_id).expect("round 1 should be answered");
+		assert_eq!(round_id, 1);
+		assert_eq!(data.answer, 43);
+		assert_eq!(data.updated_at, 2);
+	});
+}
+
+#[test]
+fn rounds_updated_at_should_work() {
+	new_test_ext().execute_with(|| {
+		assert_ok!(FeedBuilder::new().restart_delay(0).build_and_store());
+		let feed_id = 0;
+		assert_eq!(ChainlinkFeed::rounds_updated_at(feed_id, 1), vec![]);
+		assert_eq!(ChainlinkFeed::rounds_updated_at(1, 1), vec![]);
+
+		System::set_block_number(2);
+		assert_ok!(ChainlinkFeed::submit(Origin::signed(2), feed_id, 1, 42));
+		assert_ok!(ChainlinkFeed::submit(Origin::signed(3), feed_id, 1, 44));
+		let rounds = ChainlinkFeed::rounds_updated_at(feed_id, 2);
+		assert_eq!(
+			rounds.iter().map(|(id, data)| (*id, data.answer)).collect::<Vec<_>>(),
+			vec![(1, 43)]
+		);
+
+		// round 2 is opened but never answered
+		System::set_block_number(3);
+		assert_ok!(ChainlinkFeed::submit(Origin::signed(4), feed_id, 2, 50));
+		assert_eq!(ChainlinkFeed::rounds_updated_at(feed_id, 3), vec![]);
+
+		// opening round 3 closes the timed out round 2, both are updated in the same block
+		System::set_block_number(5);
+		assert_ok!(ChainlinkFeed::submit(Origin::signed(2), feed_id, 3, 60));
+		assert_ok!(ChainlinkFeed::submit(Origin::signed(3), feed_id, 3, 62));
+		let rounds = ChainlinkFeed::rounds_updated_at(feed_id, 5);
+		assert_eq!(
+			rounds.iter().map(|(id, data)| (*id, data.answer)).collect::<Vec<_>>(),
+			vec![(2, 43), (3, 61)]
+		);
+	});
+}
+
 #[test]
 fn request_new_round_should_work() {
 	new_test_ext().execute_with(|| {
@@ -868,10 +1220,10 @@ fn request_new_round_should_work() {
 		assert_eq!(
 			details,
 			RoundDetails {
-				submissions: Vec::new(),
+				submissions: Default::default(),
 				submission_count_bounds,
 				payment,
-				timeout,
+				timeout: RoundTimeout::Blocks(timeout),
 			}
 		);
 		let requester_meta =
@@ -1028,7 +1380,9 @@ fn feed_oracle_trait_should_work() {
 				RoundData {
 					answer: 42,
 					started_at: 0,
+					started_at_timestamp: 0,
 					updated_at: 0,
+					updated_at_timestamp: 0,
 					answered_in_round: 1,
 				}
 			);
@@ -1234,28 +1588,175 @@ fn prune_should_work() {
 		);
 
 		// do the successful prune
-		assert_ok!(ChainlinkFeed::prune(
-			Origin::signed(owner),
-			feed_id,
-			first_to_prune,
-			keep_round
-		));
+		let pruned = ChainlinkFeed::prune(Origin::signed(owner), feed_id, first_to_prune, keep_round)
+			.expect("pruning should work");
 		// we try to prune until 5, but limits are set up in a way that we can
-		// only prune until 4
+		// only prune until 4 (and are only charged for that)
+		assert_eq!(pruned.actual_weight, Some(<() as WeightInfo>::prune(3)));
 		assert_eq!(ChainlinkFeed::round(feed_id, 3), None);
 		let round = ChainlinkFeed::round(feed_id, 4).expect("fourth round should be present");
 		assert_eq!(
 			round,
 			Round {
 				started_at: 5,
+				started_at_timestamp: 0,
 				answer: Some(submission),
 				updated_at: Some(5),
+				updated_at_timestamp: Some(0),
 				answered_in_round: Some(4),
 			}
 		);
 	});
 }
 
+#[test]
+fn auto_pruning_should_work() {
+	new_test_ext().execute_with(|| {
+		let feed_id = 0;
+		let owner = 1;
+		let oracle = 2;
+		let submission = 42;
+		assert_ok!(FeedBuilder::new()
+			.owner(owner)
+			.min_submissions(1)
+			.restart_delay(0)
+			.oracles(vec![(oracle, 4)])
+			.build_and_store());
+		for r in 1..=8 {
+			assert_ok!(ChainlinkFeed::submit(
+				Origin::signed(oracle),
+				feed_id,
+				r,
+				submission
+			));
+		}
+
+		// nothing happens for feeds that did not opt in
+		assert_eq!(ChainlinkFeed::on_initialize(1), 0);
+		assert!(ChainlinkFeed::round(feed_id, 1).is_some());
+
+		assert_noop!(
+			ChainlinkFeed::set_auto_pruning(Origin::signed(owner), 23, true),
+			Error::<Test>::FeedNotFound
+		);
+		assert_noop!(
+			ChainlinkFeed::set_auto_pruning(Origin::signed(23), feed_id, true),
+			Error::<Test>::NotFeedOwner
+		);
+		assert_ok!(ChainlinkFeed::set_auto_pruning(Origin::signed(owner), feed_id, true));
+		assert_eq!(ChainlinkFeed::auto_pruning(feed_id), Some(1));
+
+		// the weight limit only allows for pruning two rounds per block
+		let weight = ChainlinkFeed::on_initialize(2);
+		assert!(weight <= PruningWeightLimit::get());
+		assert_eq!(ChainlinkFeed::round(feed_id, 1), None);
+		assert_eq!(ChainlinkFeed::round(feed_id, 2), None);
+		assert!(ChainlinkFeed::round(feed_id, 3).is_some());
+		assert_eq!(ChainlinkFeed::auto_pruning(feed_id), Some(3));
+		assert_eq!(ChainlinkFeed::feed(feed_id).unwrap().first_valid_round(), Some(3));
+
+		// pruning stops at the pruning window
+		ChainlinkFeed::on_initialize(3);
+		assert_eq!(ChainlinkFeed::round(feed_id, 4), None);
+		assert!(ChainlinkFeed::round(feed_id, 5).is_some());
+		assert_eq!(ChainlinkFeed::feed(feed_id).unwrap().first_valid_round(), Some(5));
+		ChainlinkFeed::on_initialize(4);
+		assert!(ChainlinkFeed::round(feed_id, 5).is_some());
+		assert_eq!(ChainlinkFeed::auto_pruning(feed_id), Some(5));
+
+		assert_ok!(ChainlinkFeed::set_auto_pruning(Origin::signed(owner), feed_id, false));
+		assert_eq!(ChainlinkFeed::auto_pruning(feed_id), None);
+	});
+}
+
+#[test]
+fn auto_pruning_should_round_robin() {
+	new_test_ext().execute_with(|| {
+		let owner = 1;
+		let oracle = 2;
+		let feeds = [0, 1];
+		for feed_id in feeds.iter().copied() {
+			assert_ok!(FeedBuilder::new()
+				.owner(owner)
+				.min_submissions(1)
+				.restart_delay(0)
+				.oracles(vec![(oracle, 4)])
+				.build_and_store());
+			for r in 1..=8 {
+				assert_ok!(ChainlinkFeed::submit(Origin::signed(oracle), feed_id, r, 42));
+			}
+			assert_ok!(ChainlinkFeed::set_auto_pruning(Origin::signed(owner), feed_id, true));
+		}
+
+		// the weight limit only allows for pruning two rounds per block, so each block prunes a
+		// single feed and the next block continues with the other one although the first still
+		// has rounds to prune
+		ChainlinkFeed::on_initialize(2);
+		let first = AutoPruningCursor::<Test>::get().expect("a feed should have been pruned");
+		assert_eq!(ChainlinkFeed::auto_pruning(first), Some(3));
+		ChainlinkFeed::on_initialize(3);
+		for feed_id in feeds.iter().copied() {
+			assert_eq!(ChainlinkFeed::auto_pruning(feed_id), Some(3));
+		}
+		assert_ne!(AutoPruningCursor::<Test>::get(), Some(first));
+	});
+}
+
+#[test]
+fn timestamp_timeout_should_work() {
+	new_test_ext().execute_with(|| {
+		let feed_id = 0;
+		let oracle_a = 2;
+		let oracle_b = 3;
+		let submission = 42;
+		assert_ok!(FeedBuilder::new()
+			.timeout_millis(1_000)
+			.min_submissions(2)
+			.restart_delay(0)
+			.oracles(vec![(oracle_a, 4), (oracle_b, 4)])
+			.build_and_store());
+
+		System::set_block_number(1);
+		Timestamp::set_timestamp(1_000);
+		assert_ok!(ChainlinkFeed::submit(
+			Origin::signed(oracle_a),
+			feed_id,
+			1,
+			submission
+		));
+		// plenty of blocks pass but the round is not timed out in wall clock time
+		System::set_block_number(100);
+		Timestamp::set_timestamp(2_000);
+		assert_noop!(
+			ChainlinkFeed::submit(Origin::signed(oracle_b), feed_id, 2, submission),
+			Error::<Test>::NotSupersedable
+		);
+		Timestamp::set_timestamp(2_001);
+		assert_ok!(ChainlinkFeed::submit(
+			Origin::signed(oracle_b),
+			feed_id,
+			2,
+			submission
+		));
+		// the timed out round carries over the previous answer
+		assert_eq!(
+			ChainlinkFeed::round(feed_id, 1),
+			Some(Round {
+				started_at: 1,
+				started_at_timestamp: 1_000,
+				answer: Some(0),
+				updated_at: Some(100),
+				updated_at_timestamp: Some(2_001),
+				answered_in_round: Some(0),
+			})
+		);
+		assert_eq!(
+			ChainlinkFeed::round(feed_id, 2),
+			Some(Round::new(100, 2_001))
+		);
+	});
+}
+
 #[test]
 fn feed_creation_permissioning() {
 	new_test_ext().execute_with(|| {
@@ -1332,11 +1833,11 @@ fn feed_life_cylce() {
 		let id = 0;
 		let owner = 1;
 		let payment = 33;
-		let timeout = 10;
+		let timeout = RoundTimeout::Blocks(10);
 		let submission_value_bounds = (1, 1_000);
 		let submission_count_bounds = (1, 3);
 		let decimals = 5;
-		let description = b"desc".to_vec();
+		let description = b"desc".to_vec().try_into().unwrap();
 		let restart_delay = 1;
 		let new_config = FeedConfig {
 			owner,
@@ -1365,7 +1866,7 @@ fn feed_life_cylce() {
 		};
 		// config should be stored on drop
 		assert_eq!(ChainlinkFeed::feed_config(id), Some(new_config.clone()));
-		let new_timeout = 5;
+		let new_timeout = RoundTimeout::Blocks(5);
 		{
 			let mut feed = Feed::<Test>::load_from(id).expect("feed should be there");
 			feed.config.timeout = new_timeout;
@@ -1376,7 +1877,7 @@ fn feed_life_cylce() {
 		};
 		// modified config should be stored on drop
 		assert_eq!(ChainlinkFeed::feed_config(id), Some(modified_config.clone()));
-		let ignored_timeout = 23;
+		let ignored_timeout = RoundTimeout::Blocks(23);
 		{
 			let mut feed = Feed::<Test>::read_only_from(id).expect("feed should be there");
 			feed.config.timeout = ignored_timeout;
@@ -1390,3 +1891,154 @@ fn feed_life_cylce() {
 		assert_eq!(ChainlinkFeed::feed_config(id).unwrap().reporting_round, 1);
 	});
 }
+
+#[test]
+fn migration_to_v1_should_work() {
+	new_test_ext().execute_with(|| {
+		use frame_support::storage::unhashed;
+		use migrations::v1::{OldFeedConfig, OldRound, OldRoundDetails};
+
+		let feed_id: FeedId = 0;
+		let round_id: RoundId = 1;
+		// put the data in the old format into storage
+		let old_config = OldFeedConfig {
+			owner: 1,
+			pending_owner: None,
+			submission_value_bounds: (1, 1_000),
+			submission_count_bounds: (1, 2),
+			payment: 20,
+			timeout: 10,
+			decimals: 5,
+			description: b"desc".to_vec(),
+			restart_delay: 0,
+			reporting_round: round_id,
+			latest_round: round_id,
+			first_valid_round: Some(round_id),
+			oracle_count: 2,
+		};
+		unhashed::put(&Feeds::<Test>::hashed_key_for(feed_id), &old_config);
+		let old_round = OldRound {
+			started_at: 2,
+			answer: Some(42),
+			updated_at: Some(3),
+			answered_in_round: Some(round_id),
+		};
+		unhashed::put(&Rounds::<Test>::hashed_key_for(feed_id, round_id), &old_round);
+		let old_details = OldRoundDetails {
+			submissions: vec![42],
+			submission_count_bounds: (1, 2),
+			payment: 20,
+			timeout: 10,
+		};
+		unhashed::put(&Details::<Test>::hashed_key_for(feed_id, round_id), &old_details);
+		StorageVersion::new(0).put::<ChainlinkFeed>();
+
+		ChainlinkFeed::on_runtime_upgrade();
+
+		assert_eq!(ChainlinkFeed::on_chain_storage_version(), STORAGE_VERSION);
+		let config = ChainlinkFeed::feed_config(feed_id).expect("feed should be migrated");
+		assert_eq!(config.owner, 1);
+		assert_eq!(config.timeout, RoundTimeout::Blocks(10));
+		assert_eq!(config.description, b"desc".to_vec());
+		assert_eq!(config.first_valid_round, Some(round_id));
+		let expected_round = Round {
+			started_at: 2,
+			started_at_timestamp: 0,
+			answer: Some(42),
+			updated_at: Some(3),
+			updated_at_timestamp: Some(0),
+			answered_in_round: Some(round_id),
+		};
+		assert_eq!(ChainlinkFeed::round(feed_id, round_id), Some(expected_round.clone()));
+		let details =
+			ChainlinkFeed::round_details(feed_id, round_id).expect("details should be migrated");
+		assert_eq!(details.submissions, vec![42]);
+		assert_eq!(details.timeout, RoundTimeout::Blocks(10));
+
+		// the migration only runs once
+		ChainlinkFeed::on_runtime_upgrade();
+		assert_eq!(ChainlinkFeed::round(feed_id, round_id), Some(expected_round));
+	});
+}
+
+#[test]
+fn migration_to_v1_should_keep_translated_storage() {
+	new_test_ext().execute_with(|| {
+		use frame_support::storage::migration::{get_storage_value, put_storage_value};
+		use migrations::v1::Releases;
+
+		let feed_id: FeedId = 0;
+		let round_id: RoundId = 1;
+		let round = Round {
+			started_at: 2,
+			started_at_timestamp: 1_000,
+			answer: Some(42),
+			updated_at: Some(3),
+			updated_at_timestamp: Some(2_000),
+			answered_in_round: Some(round_id),
+		};
+		Rounds::<Test>::insert(feed_id, round_id, round.clone());
+		// storage of the `decl_storage` based pallet that already uses the current layout
+		put_storage_value(b"ChainlinkFeed", b"StorageVersion", &[], Releases::V1);
+		StorageVersion::new(0).put::<ChainlinkFeed>();
+
+		ChainlinkFeed::on_runtime_upgrade();
+
+		assert_eq!(ChainlinkFeed::on_chain_storage_version(), STORAGE_VERSION);
+		assert_eq!(ChainlinkFeed::round(feed_id, round_id), Some(round));
+		assert_eq!(
+			get_storage_value::<Releases>(b"ChainlinkFeed", b"StorageVersion", &[]),
+			None
+		);
+	});
+}
+
+#[test]
+fn migration_to_v2_should_work() {
+	new_test_ext().execute_with(|| {
+		use frame_support::storage::unhashed;
+		use migrations::v2::{OldFeedConfig, OldRoundDetails};
+
+		let feed_id: FeedId = 0;
+		let round_id: RoundId = 1;
+		let string_limit = <Test as Config>::StringLimit::get() as usize;
+		// put the data with unbounded vectors into storage
+		let old_config = OldFeedConfig {
+			owner: 1,
+			pending_owner: None,
+			submission_value_bounds: (1, 1_000),
+			submission_count_bounds: (1, 2),
+			payment: 20,
+			timeout: RoundTimeout::Blocks(10),
+			decimals: 5,
+			description: vec![1; string_limit + 5],
+			restart_delay: 0,
+			reporting_round: round_id,
+			latest_round: round_id,
+			first_valid_round: Some(round_id),
+			oracle_count: 2,
+		};
+		unhashed::put(&Feeds::<Test>::hashed_key_for(feed_id), &old_config);
+		let old_details = OldRoundDetails {
+			submissions: vec![42, 43],
+			submission_count_bounds: (1, 2),
+			payment: 20,
+			timeout: RoundTimeout::Blocks(10),
+		};
+		unhashed::put(&Details::<Test>::hashed_key_for(feed_id, round_id), &old_details);
+		StorageVersion::new(1).put::<ChainlinkFeed>();
+
+		ChainlinkFeed::on_runtime_upgrade();
+
+		assert_eq!(ChainlinkFeed::on_chain_storage_version(), STORAGE_VERSION);
+		let config = ChainlinkFeed::feed_config(feed_id).expect("feed should be migrated");
+		// the description is truncated to the limit
+		assert_eq!(config.description, vec![1; string_limit]);
+		assert_eq!(config.timeout, RoundTimeout::Blocks(10));
+		assert_eq!(config.oracle_count, 2);
+		let details =
+			ChainlinkFeed::round_details(feed_id, round_id).expect("details should be migrated");
+		assert_eq!(details.submissions, vec![42, 43]);
+		assert_eq!(details.payment, 20);
+	});
+}
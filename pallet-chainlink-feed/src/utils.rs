@@ -1,25 +1,5 @@
-use frame_support::storage::{with_transaction, TransactionOutcome};
 use sp_arithmetic::traits::BaseArithmetic;
 
-/// Execute the supplied function in a new storage transaction.
-///
-/// All changes to storage performed by the supplied function are discarded if
-/// the returned outcome is `Result::Err`.
-///
-/// Transactions can be nested to any depth. Commits happen to the parent
-/// transaction.
-// TODO: remove after move to Substrate v3 (once the semantics of #[transactional] work as intended)
-pub(crate) fn with_transaction_result<R, E>(f: impl FnOnce() -> Result<R, E>) -> Result<R, E> {
-	with_transaction(|| {
-		let res = f();
-		if res.is_ok() {
-			TransactionOutcome::Commit(res)
-		} else {
-			TransactionOutcome::Rollback(res)
-		}
-	})
-}
-
 /// Determine the median of a slice of values.
 ///
 /// **Warning:** Will panic if passed an empty slice.
@@ -34,6 +14,27 @@ pub(crate) fn median<T: Copy + BaseArithmetic>(numbers: &mut [T]) -> T {
 	}
 }
 
+/// Serialize a bounded byte string as a (lossily converted) UTF-8 string.
+#[cfg(feature = "std")]
+pub(crate) fn serialize_lossy_str<B, S>(bytes: &B, serializer: S) -> Result<S::Ok, S::Error>
+where
+	B: sp_std::ops::Deref<Target = Vec<u8>>,
+	S: serde::Serializer,
+{
+	serializer.serialize_str(&String::from_utf8_lossy(bytes))
+}
+
+/// Serialize a bounded collection as its inner collection.
+#[cfg(feature = "std")]
+pub(crate) fn serialize_deref<D, S>(value: &D, serializer: S) -> Result<S::Ok, S::Error>
+where
+	D: sp_std::ops::Deref,
+	D::Target: serde::Serialize,
+	S: serde::Serializer,
+{
+	serde::Serialize::serialize(&**value, serializer)
+}
+
 #[test]
 fn median_works() {
 	let mut values = vec![4u32, 6, 2, 7];
@@ -0,0 +1,264 @@
+//! Verifier of storage proofs of chainlink price feed answers.
+//!
+//! Checks the proofs served by the `chainlinkFeed_latestRoundProof` RPC against the state root of
+//! a block, so bridges and light clients can consume the answers of a feed without trusting the
+//! node that served them. The proof covers the config of the feed (which determines its latest
+//! round) and the latest round itself, both located via their `twox_64_concat` hashed keys.
+
+#![cfg_attr(not(feature = "std"), no_std)]
+
+use codec::{Decode, Encode};
+use frame_support::Parameter;
+use hash_db::Hasher;
+use pallet_chainlink_feed::{FeedConfig, Round, RoundData, RoundId};
+use sp_core::RuntimeDebug;
+use sp_std::{convert::TryInto, prelude::*};
+use sp_trie::{read_trie_value, Layout, StorageProof};
+
+/// The name the feed pallet is declared with in `construct_runtime!`.
+pub const DEFAULT_PALLET_NAME: &[u8] = b"ChainlinkFeed";
+
+/// Possible errors when verifying a proof.
+#[derive(Clone, Eq, PartialEq, RuntimeDebug)]
+pub enum VerificationError {
+	/// The proof does not match the state root or lacks nodes needed for the lookup.
+	InvalidProof,
+	/// The feed does not exist in the proven state.
+	UnknownFeed,
+	/// The latest round of the feed does not exist in the proven state.
+	UnknownRound,
+	/// A proven value could not be decoded as the expected type.
+	Decode,
+	/// The latest round of the feed has not been answered, yet.
+	NotAnswered,
+}
+
+/// The storage key of the config of `feed_id` (`Feeds` map).
+pub fn feed_config_key<FeedId: Encode>(pallet_name: &[u8], feed_id: &FeedId) -> Vec<u8> {
+	storage_key(pallet_name, b"Feeds", &[&twox_64_concat(&feed_id.encode())])
+}
+
+/// The storage key of the round `round_id` of `feed_id` (`Rounds` double map).
+pub fn round_key<FeedId: Encode>(
+	pallet_name: &[u8],
+	feed_id: &FeedId,
+	round_id: RoundId,
+) -> Vec<u8> {
+	storage_key(
+		pallet_name,
+		b"Rounds",
+		&[&twox_64_concat(&feed_id.encode()), &twox_64_concat(&round_id.encode())],
+	)
+}
+
+/// Verify the latest answered round of `feed_id` against `state_root` using the `proof` nodes.
+///
+/// The type parameters have to match the configuration of the feed pallet in the runtime the
+/// proof is from: `H` is the hasher of the state trie, the others are the types of the pallet
+/// config. Returns the round id with its data on success.
+pub fn verify_latest_round<H, AccountId, Balance, BlockNumber, Value, FeedId>(
+	state_root: &H::Out,
+	proof: Vec<Vec<u8>>,
+	pallet_name: &[u8],
+	feed_id: &FeedId,
+) -> Result<(RoundId, RoundData<BlockNumber, Value>), VerificationError>
+where
+	H: Hasher,
+	AccountId: Parameter,
+	Balance: Parameter,
+	BlockNumber: Parameter,
+	Value: Parameter,
+	FeedId: Encode,
+{
+	let db = StorageProof::new(proof).into_memory_db::<H>();
+	let read = |key: &[u8]| {
+		read_trie_value::<Layout<H>, _>(&db, state_root, key)
+			.map_err(|_| VerificationError::InvalidProof)
+	};
+
+	// bounded strings are encoded like vectors, so the limit of the description does not matter
+	let config = read(&feed_config_key(pallet_name, feed_id))?
+		.ok_or(VerificationError::UnknownFeed)?;
+	let config = FeedConfig::<AccountId, Balance, BlockNumber, Value, Vec<u8>>::decode(
+		&mut &config[..],
+	)
+	.map_err(|_| VerificationError::Decode)?;
+
+	let round_id = config.latest_round();
+	let round = read(&round_key(pallet_name, feed_id, round_id))?
+		.ok_or(VerificationError::UnknownRound)?;
+	let round = Round::<BlockNumber, Value>::decode(&mut &round[..])
+		.map_err(|_| VerificationError::Decode)?;
+	let data = round.try_into().map_err(|_| VerificationError::NotAnswered)?;
+	Ok((round_id, data))
+}
+
+/// The key of a storage map entry with the given hashed keys.
+fn storage_key(pallet_name: &[u8], storage_name: &[u8], hashed_keys: &[&[u8]]) -> Vec<u8> {
+	let mut key = sp_io::hashing::twox_128(pallet_name).to_vec();
+	key.extend_from_slice(&sp_io::hashing::twox_128(storage_name));
+	for hashed_key in hashed_keys {
+		key.extend_from_slice(hashed_key);
+	}
+	key
+}
+
+fn twox_64_concat(data: &[u8]) -> Vec<u8> {
+	let mut hashed = sp_io::hashing::twox_64(data).to_vec();
+	hashed.extend_from_slice(data);
+	hashed
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use pallet_chainlink_feed::RoundTimeout;
+	use sp_runtime::traits::BlakeTwo256;
+
+	type AccountId = u64;
+	type Balance = u64;
+	type BlockNumber = u64;
+	type Value = u64;
+	type FeedId = u32;
+
+	const FEED_ID: FeedId = 3;
+	const LATEST_ROUND: RoundId = 7;
+
+	/// Encode a feed config with the given latest round (mirrors the field order of
+	/// `FeedConfig`, whose fields are private).
+	fn encoded_config(latest_round: RoundId) -> Vec<u8> {
+		(
+			1 as AccountId,
+			None::<AccountId>,
+			(1 as Value, 100 as Value),
+			(2u32, 3u32),
+			10 as Balance,
+			RoundTimeout::Blocks(10 as BlockNumber),
+			8u8,
+			b"LINK/USD".to_vec(),
+			0 as RoundId,
+			latest_round,
+			latest_round,
+			Some(1 as RoundId),
+			3u32,
+		)
+			.encode()
+	}
+
+	/// Encode a round (mirrors the field order of `Round`).
+	fn encoded_round(answer: Option<Value>) -> Vec<u8> {
+		let answered = answer.map(|_| ());
+		(
+			5 as BlockNumber,
+			1_000u64,
+			answer,
+			answered.map(|_| 6 as BlockNumber),
+			answered.map(|_| 1_006u64),
+			answered.map(|_| LATEST_ROUND),
+		)
+			.encode()
+	}
+
+	/// Build a state with the given feed entries and prove reading `keys` from it.
+	fn prove(
+		entries: Vec<(Vec<u8>, Vec<u8>)>,
+		keys: &[Vec<u8>],
+	) -> (<BlakeTwo256 as Hasher>::Out, Vec<Vec<u8>>) {
+		let mut ext = sp_io::TestExternalities::default();
+		for (key, value) in entries {
+			ext.insert(key, value);
+		}
+		let backend = ext.as_backend();
+		let root = *backend.root();
+		let proof = sp_state_machine::prove_read(backend, keys).expect("keys are provable");
+		(root, proof.iter_nodes().collect())
+	}
+
+	fn verify(
+		root: &<BlakeTwo256 as Hasher>::Out,
+		proof: Vec<Vec<u8>>,
+	) -> Result<(RoundId, RoundData<BlockNumber, Value>), VerificationError> {
+		verify_latest_round::<BlakeTwo256, AccountId, Balance, BlockNumber, Value, _>(
+			root,
+			proof,
+			DEFAULT_PALLET_NAME,
+			&FEED_ID,
+		)
+	}
+
+	#[test]
+	fn verifying_the_latest_round_should_work() {
+		let config_key = feed_config_key(DEFAULT_PALLET_NAME, &FEED_ID);
+		let round_key = round_key(DEFAULT_PALLET_NAME, &FEED_ID, LATEST_ROUND);
+		let (root, proof) = prove(
+			vec![
+				(config_key.clone(), encoded_config(LATEST_ROUND)),
+				(round_key.clone(), encoded_round(Some(42))),
+			],
+			&[config_key, round_key],
+		);
+
+		assert_eq!(
+			verify(&root, proof),
+			Ok((
+				LATEST_ROUND,
+				RoundData {
+					started_at: 5,
+					started_at_timestamp: 1_000,
+					answer: 42,
+					updated_at: 6,
+					updated_at_timestamp: 1_006,
+					answered_in_round: LATEST_ROUND,
+				}
+			))
+		);
+	}
+
+	#[test]
+	fn invalid_proofs_should_be_rejected() {
+		let config_key = feed_config_key(DEFAULT_PALLET_NAME, &FEED_ID);
+		let round_key = round_key(DEFAULT_PALLET_NAME, &FEED_ID, LATEST_ROUND);
+		let entries = vec![
+			(config_key.clone(), encoded_config(LATEST_ROUND)),
+			(round_key.clone(), encoded_round(Some(42))),
+		];
+
+		// wrong state root
+		let (_, proof) = prove(entries.clone(), &[config_key.clone(), round_key.clone()]);
+		assert_eq!(verify(&Default::default(), proof), Err(VerificationError::InvalidProof));
+
+		// the round is not covered by the proof
+		let (root, proof) = prove(entries.clone(), &[config_key.clone()]);
+		assert_eq!(verify(&root, proof), Err(VerificationError::InvalidProof));
+
+		// the proof of a stale round does not verify the latest round
+		let stale_key = super::round_key(DEFAULT_PALLET_NAME, &FEED_ID, LATEST_ROUND - 1);
+		let (root, proof) = prove(
+			vec![
+				(config_key.clone(), encoded_config(LATEST_ROUND)),
+				(stale_key.clone(), encoded_round(Some(40))),
+			],
+			&[config_key.clone(), stale_key],
+		);
+		assert_eq!(verify(&root, proof), Err(VerificationError::UnknownRound));
+	}
+
+	#[test]
+	fn unknown_feeds_and_unanswered_rounds_should_be_rejected() {
+		let config_key = feed_config_key(DEFAULT_PALLET_NAME, &FEED_ID);
+		let round_key = round_key(DEFAULT_PALLET_NAME, &FEED_ID, LATEST_ROUND);
+
+		let (root, proof) =
+			prove(vec![(round_key.clone(), encoded_round(Some(42)))], &[config_key.clone()]);
+		assert_eq!(verify(&root, proof), Err(VerificationError::UnknownFeed));
+
+		let (root, proof) = prove(
+			vec![
+				(config_key.clone(), encoded_config(LATEST_ROUND)),
+				(round_key.clone(), encoded_round(None)),
+			],
+			&[config_key, round_key],
+		);
+		assert_eq!(verify(&root, proof), Err(VerificationError::NotAnswered));
+	}
+}
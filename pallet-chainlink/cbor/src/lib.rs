@@ -0,0 +1,478 @@
+//! Chainlink CBOR request parameters for the chainlink pallet.
+//!
+//! Chainlink jobs read the parameters of a request (`get`, `path`, `times`, ...) from a CBOR map.
+//! [`Request`] builds the `data` of `initiate_request` (with
+//! `pallet_chainlink::encoding::CHAINLINK_CBOR_DATA_VERSION`) the way Solidity's
+//! `Chainlink.Request` does: the keys and values of the map, without its enclosing delimiters.
+//! [`decode`] reads them back for operator-side tooling.
+
+#![cfg_attr(not(feature = "std"), no_std)]
+
+use sp_std::{convert::TryFrom, prelude::*, str};
+
+const MAJOR_TYPE_INT: u8 = 0;
+const MAJOR_TYPE_NEGATIVE_INT: u8 = 1;
+const MAJOR_TYPE_BYTES: u8 = 2;
+const MAJOR_TYPE_STRING: u8 = 3;
+const MAJOR_TYPE_ARRAY: u8 = 4;
+const MAJOR_TYPE_MAP: u8 = 5;
+const MAJOR_TYPE_TAG: u8 = 6;
+
+const TAG_TYPE_BIGNUM: u64 = 2;
+const TAG_TYPE_NEGATIVE_BIGNUM: u64 = 3;
+
+/// The additional information of indefinite lengths.
+const INDEFINITE_LENGTH: u8 = 31;
+/// Ends indefinite-length arrays and maps.
+const BREAK: u8 = 0xff;
+
+/// The parameters of a Chainlink request, encoded as `Chainlink.Request` encodes them.
+///
+/// ```
+/// let data = pallet_chainlink_cbor::Request::new()
+///     .add("get", "https://min-api.cryptocompare.com/data/price?fsym=ETH&tsyms=USD")
+///     .add("path", "USD")
+///     .add_int("times", 100)
+///     .into_data();
+/// ```
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct Request {
+	buf: Vec<u8>,
+}
+
+impl Request {
+	pub fn new() -> Self {
+		Self::default()
+	}
+
+	/// Add a string parameter (`Chainlink.Request.add`).
+	pub fn add(mut self, key: &str, value: &str) -> Self {
+		encode_string(&mut self.buf, key);
+		encode_string(&mut self.buf, value);
+		self
+	}
+
+	/// Add a bytes parameter (`Chainlink.Request.addBytes`).
+	pub fn add_bytes(mut self, key: &str, value: &[u8]) -> Self {
+		encode_string(&mut self.buf, key);
+		encode_bytes(&mut self.buf, value);
+		self
+	}
+
+	/// Add a signed integer parameter (`Chainlink.Request.addInt`).
+	pub fn add_int(mut self, key: &str, value: i128) -> Self {
+		encode_string(&mut self.buf, key);
+		encode_int(&mut self.buf, value);
+		self
+	}
+
+	/// Add an unsigned integer parameter (`Chainlink.Request.addUint`).
+	pub fn add_uint(mut self, key: &str, value: u128) -> Self {
+		encode_string(&mut self.buf, key);
+		encode_uint(&mut self.buf, value);
+		self
+	}
+
+	/// Add an array of strings parameter (`Chainlink.Request.addStringArray`).
+	pub fn add_string_array(mut self, key: &str, values: &[&str]) -> Self {
+		encode_string(&mut self.buf, key);
+		self.buf.push(MAJOR_TYPE_ARRAY << 5 | INDEFINITE_LENGTH);
+		for value in values {
+			encode_string(&mut self.buf, value);
+		}
+		self.buf.push(BREAK);
+		self
+	}
+
+	/// The `data` of the request.
+	pub fn into_data(self) -> Vec<u8> {
+		self.buf
+	}
+}
+
+fn encode_type(buf: &mut Vec<u8>, major: u8, value: u64) {
+	if value <= 23 {
+		buf.push(major << 5 | value as u8);
+	} else if value <= 0xff {
+		buf.push(major << 5 | 24);
+		buf.push(value as u8);
+	} else if value <= 0xffff {
+		buf.push(major << 5 | 25);
+		buf.extend_from_slice(&(value as u16).to_be_bytes());
+	} else if value <= 0xffff_ffff {
+		buf.push(major << 5 | 26);
+		buf.extend_from_slice(&(value as u32).to_be_bytes());
+	} else {
+		buf.push(major << 5 | 27);
+		buf.extend_from_slice(&value.to_be_bytes());
+	}
+}
+
+fn encode_string(buf: &mut Vec<u8>, value: &str) {
+	encode_type(buf, MAJOR_TYPE_STRING, value.len() as u64);
+	buf.extend_from_slice(value.as_bytes());
+}
+
+fn encode_bytes(buf: &mut Vec<u8>, value: &[u8]) {
+	encode_type(buf, MAJOR_TYPE_BYTES, value.len() as u64);
+	buf.extend_from_slice(value);
+}
+
+/// Values beyond 64 bits are encoded as bignums of 32 bytes, like `abi.encode(uint256)` in
+/// Solidity.
+fn encode_bignum(buf: &mut Vec<u8>, tag: u64, value: u128) {
+	encode_type(buf, MAJOR_TYPE_TAG, tag);
+	let mut bytes = [0u8; 32];
+	bytes[16..].copy_from_slice(&value.to_be_bytes());
+	encode_bytes(buf, &bytes);
+}
+
+fn encode_uint(buf: &mut Vec<u8>, value: u128) {
+	match u64::try_from(value) {
+		Ok(value) => encode_type(buf, MAJOR_TYPE_INT, value),
+		Err(_) => encode_bignum(buf, TAG_TYPE_BIGNUM, value),
+	}
+}
+
+fn encode_int(buf: &mut Vec<u8>, value: i128) {
+	if value >= 0 {
+		return encode_uint(buf, value as u128);
+	}
+	// Negative integers are encoded as `-1 - value`, which always fits in a `u128`
+	let encoded = (-1 - value) as u128;
+	match u64::try_from(encoded) {
+		Ok(encoded) => encode_type(buf, MAJOR_TYPE_NEGATIVE_INT, encoded),
+		Err(_) => encode_bignum(buf, TAG_TYPE_NEGATIVE_BIGNUM, encoded),
+	}
+}
+
+/// A decoded parameter value.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Value {
+	/// A UTF-8 string.
+	String(Vec<u8>),
+	Bytes(Vec<u8>),
+	/// A non-negative integer.
+	UInt(u128),
+	/// A negative integer.
+	Int(i128),
+	Array(Vec<Value>),
+}
+
+impl Value {
+	pub fn as_str(&self) -> Option<&str> {
+		match self {
+			Value::String(value) => str::from_utf8(value).ok(),
+			_ => None,
+		}
+	}
+
+	pub fn as_bytes(&self) -> Option<&[u8]> {
+		match self {
+			Value::Bytes(value) => Some(value),
+			_ => None,
+		}
+	}
+
+	/// The value of an integer that fits in an `i128`.
+	pub fn as_int(&self) -> Option<i128> {
+		match self {
+			Value::UInt(value) => i128::try_from(*value).ok(),
+			Value::Int(value) => Some(*value),
+			_ => None,
+		}
+	}
+
+	pub fn as_uint(&self) -> Option<u128> {
+		match self {
+			Value::UInt(value) => Some(*value),
+			_ => None,
+		}
+	}
+
+	pub fn as_array(&self) -> Option<&[Value]> {
+		match self {
+			Value::Array(values) => Some(values),
+			_ => None,
+		}
+	}
+}
+
+/// The decoded parameters of a request, in their encoding order.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct Parameters(pub Vec<(Vec<u8>, Value)>);
+
+impl Parameters {
+	/// The value of the first parameter named `key`.
+	pub fn get(&self, key: &str) -> Option<&Value> {
+		self.0.iter().find(|(k, _)| k == key.as_bytes()).map(|(_, value)| value)
+	}
+
+	pub fn iter(&self) -> impl Iterator<Item = (&[u8], &Value)> {
+		self.0.iter().map(|(key, value)| (&key[..], value))
+	}
+}
+
+/// Why parameters could not be decoded.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Error {
+	/// The data ended within a parameter.
+	UnexpectedEnd,
+	/// A key is not a UTF-8 string.
+	InvalidKey,
+	/// A string is not valid UTF-8.
+	InvalidUtf8,
+	/// An integer does not fit in 128 bits.
+	Overflow,
+	/// A CBOR item Chainlink parameters don't use (maps, floats, other tags, ...) or nested deeper
+	/// than `MAX_DEPTH`.
+	Unsupported,
+	/// Bytes are left after the enclosing map of the parameters.
+	TrailingBytes,
+}
+
+/// Decode the `data` of a request built by [`Request`] (or `Chainlink.Request`).
+///
+/// The parameters may also be enclosed in an indefinite-length map, as Chainlink nodes read
+/// them.
+pub fn decode(data: &[u8]) -> Result<Parameters, Error> {
+	let mut decoder = Decoder { data, position: 0 };
+	let enclosed = data.first() == Some(&(MAJOR_TYPE_MAP << 5 | INDEFINITE_LENGTH));
+	if enclosed {
+		decoder.position = 1;
+	}
+	let mut parameters = Vec::new();
+	loop {
+		match decoder.peek() {
+			None if !enclosed => break,
+			Some(BREAK) if enclosed => {
+				decoder.position += 1;
+				if decoder.peek().is_some() {
+					return Err(Error::TrailingBytes)
+				}
+				break
+			}
+			_ => {}
+		}
+		let key = match decoder.value(0)? {
+			Value::String(key) => key,
+			_ => return Err(Error::InvalidKey),
+		};
+		parameters.push((key, decoder.value(0)?));
+	}
+	Ok(Parameters(parameters))
+}
+
+/// The maximum nesting depth of the arrays (and tags) of a parameter value.
+pub const MAX_DEPTH: usize = 16;
+
+struct Decoder<'a> {
+	data: &'a [u8],
+	position: usize,
+}
+
+impl<'a> Decoder<'a> {
+	fn peek(&self) -> Option<u8> {
+		self.data.get(self.position).copied()
+	}
+
+	fn take(&mut self, len: usize) -> Result<&'a [u8], Error> {
+		let end = self.position.checked_add(len).ok_or(Error::UnexpectedEnd)?;
+		let bytes = self.data.get(self.position..end).ok_or(Error::UnexpectedEnd)?;
+		self.position = end;
+		Ok(bytes)
+	}
+
+	/// The major type and the additional information (or the length) of the next item.
+	/// Indefinite lengths are returned as `None`.
+	fn header(&mut self) -> Result<(u8, Option<u64>), Error> {
+		let initial = self.take(1)?[0];
+		let (major, info) = (initial >> 5, initial & 0x1f);
+		let value = match info {
+			0..=23 => info as u64,
+			24 => self.take(1)?[0] as u64,
+			25 => {
+				let mut bytes = [0u8; 2];
+				bytes.copy_from_slice(self.take(2)?);
+				u16::from_be_bytes(bytes) as u64
+			}
+			26 => {
+				let mut bytes = [0u8; 4];
+				bytes.copy_from_slice(self.take(4)?);
+				u32::from_be_bytes(bytes) as u64
+			}
+			27 => {
+				let mut bytes = [0u8; 8];
+				bytes.copy_from_slice(self.take(8)?);
+				u64::from_be_bytes(bytes)
+			}
+			INDEFINITE_LENGTH => return Ok((major, None)),
+			_ => return Err(Error::Unsupported),
+		};
+		Ok((major, Some(value)))
+	}
+
+	/// The length of a byte string, text string or array.
+	fn length(len: u64) -> Result<usize, Error> {
+		usize::try_from(len).map_err(|_| Error::Overflow)
+	}
+
+	/// The next item, nested in `depth` arrays (or tags).
+	fn value(&mut self, depth: usize) -> Result<Value, Error> {
+		if depth > MAX_DEPTH {
+			return Err(Error::Unsupported)
+		}
+		match self.header()? {
+			(MAJOR_TYPE_INT, Some(value)) => Ok(Value::UInt(value as u128)),
+			(MAJOR_TYPE_NEGATIVE_INT, Some(value)) => Ok(Value::Int(-1 - value as i128)),
+			(MAJOR_TYPE_BYTES, Some(len)) => Ok(Value::Bytes(self.take(Self::length(len)?)?.to_vec())),
+			(MAJOR_TYPE_STRING, Some(len)) => {
+				let value = self.take(Self::length(len)?)?;
+				str::from_utf8(value).map_err(|_| Error::InvalidUtf8)?;
+				Ok(Value::String(value.to_vec()))
+			}
+			(MAJOR_TYPE_ARRAY, Some(len)) => {
+				// no capacity up front, the length is not checked against the data yet
+				let mut values = Vec::new();
+				for _ in 0..Self::length(len)? {
+					values.push(self.value(depth + 1)?);
+				}
+				Ok(Value::Array(values))
+			}
+			(MAJOR_TYPE_ARRAY, None) => {
+				let mut values = Vec::new();
+				while self.peek().ok_or(Error::UnexpectedEnd)? != BREAK {
+					values.push(self.value(depth + 1)?);
+				}
+				self.position += 1;
+				Ok(Value::Array(values))
+			}
+			(MAJOR_TYPE_TAG, Some(tag)) if tag == TAG_TYPE_BIGNUM || tag == TAG_TYPE_NEGATIVE_BIGNUM => {
+				let magnitude = match self.value(depth + 1)? {
+					Value::Bytes(bytes) => bignum(&bytes)?,
+					_ => return Err(Error::Unsupported),
+				};
+				if tag == TAG_TYPE_BIGNUM {
+					Ok(Value::UInt(magnitude))
+				} else {
+					// `-1 - magnitude` must fit in an `i128`
+					let magnitude = i128::try_from(magnitude).map_err(|_| Error::Overflow)?;
+					Ok(Value::Int(-1 - magnitude))
+				}
+			}
+			// Maps, other tags, floats and simple values are not used by Chainlink parameters
+			_ => Err(Error::Unsupported),
+		}
+	}
+}
+
+/// The big-endian unsigned integer `bytes`.
+fn bignum(bytes: &[u8]) -> Result<u128, Error> {
+	let leading_zeros = bytes.iter().take_while(|byte| **byte == 0).count();
+	let significant = &bytes[leading_zeros..];
+	if significant.len() > 16 {
+		return Err(Error::Overflow)
+	}
+	let mut buf = [0u8; 16];
+	buf[16 - significant.len()..].copy_from_slice(significant);
+	Ok(u128::from_be_bytes(buf))
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn encodes_like_chainlink_request() {
+		let data = Request::new()
+			.add("get", "https://x")
+			.add_int("times", 100)
+			.add_int("neg", -100)
+			.add_uint("big", 1_000_000)
+			.add_bytes("b", &[1, 2])
+			.add_string_array("path", &["RAW", "ETH"])
+			.into_data();
+		let expected = [
+			&[0x63][..], b"get", &[0x69], b"https://x",
+			&[0x65], b"times", &[0x18, 0x64],
+			&[0x63], b"neg", &[0x38, 0x63],
+			&[0x63], b"big", &[0x1a, 0x00, 0x0f, 0x42, 0x40],
+			&[0x61], b"b", &[0x42, 0x01, 0x02],
+			&[0x64], b"path", &[0x9f, 0x63], b"RAW", &[0x63], b"ETH", &[0xff],
+		]
+		.concat();
+		assert_eq!(data, expected);
+	}
+
+	#[test]
+	fn encodes_bignums_like_abi_encode() {
+		let data = Request::new().add_uint("v", u128::max_value()).into_data();
+		let expected = [&[0x61, b'v', 0xc2, 0x58, 0x20][..], &[0; 16], &[0xff; 16]].concat();
+		assert_eq!(data, expected);
+
+		let data = Request::new().add_int("v", i128::min_value()).into_data();
+		let magnitude = (i128::max_value() as u128).to_be_bytes();
+		let expected = [&[0x61, b'v', 0xc3, 0x58, 0x20][..], &[0; 16], &magnitude].concat();
+		assert_eq!(data, expected);
+	}
+
+	#[test]
+	fn decodes_built_requests() {
+		let data = Request::new()
+			.add("get", "https://x")
+			.add_int("times", -100)
+			.add_uint("max", u128::max_value())
+			.add_int("min", i128::min_value())
+			.add_bytes("b", &[1, 2])
+			.add_string_array("path", &["RAW", "ETH"])
+			.into_data();
+		let parameters = decode(&data).unwrap();
+		assert_eq!(parameters.get("get").and_then(Value::as_str), Some("https://x"));
+		assert_eq!(parameters.get("times").and_then(Value::as_int), Some(-100));
+		assert_eq!(parameters.get("max").and_then(Value::as_uint), Some(u128::max_value()));
+		assert_eq!(parameters.get("max").and_then(Value::as_int), None);
+		assert_eq!(parameters.get("min").and_then(Value::as_int), Some(i128::min_value()));
+		assert_eq!(parameters.get("b").and_then(Value::as_bytes), Some(&[1u8, 2][..]));
+		let path: Vec<_> = parameters
+			.get("path")
+			.and_then(Value::as_array)
+			.unwrap()
+			.iter()
+			.filter_map(Value::as_str)
+			.collect();
+		assert_eq!(path, vec!["RAW", "ETH"]);
+		assert_eq!(parameters.iter().count(), 6);
+		assert!(parameters.get("post").is_none());
+
+		// As read by Chainlink nodes
+		let enclosed = [&[0xbf][..], &data, &[0xff]].concat();
+		assert_eq!(decode(&enclosed), Ok(parameters));
+		assert_eq!(decode(&Request::new().into_data()), Ok(Parameters::default()));
+	}
+
+	#[test]
+	fn rejects_invalid_data() {
+		let data = Request::new().add("get", "https://x").into_data();
+		assert_eq!(decode(&data[..data.len() - 1]), Err(Error::UnexpectedEnd));
+		assert_eq!(decode(&[0x01, 0x61, b'a']), Err(Error::InvalidKey));
+		assert_eq!(decode(&[0x61, b'a', 0x61, 0xff]), Err(Error::InvalidUtf8));
+		assert_eq!(decode(&[0x61, b'a', 0xa0]), Err(Error::Unsupported));
+		assert_eq!(decode(&[0xbf, 0xff, 0x00]), Err(Error::TrailingBytes));
+		assert_eq!(decode(&[0xbf, 0x61, b'a', 0x01]), Err(Error::UnexpectedEnd));
+		let too_big = [&[0x61, b'a', 0xc2, 0x51, 0x01][..], &[0; 16]].concat();
+		assert_eq!(decode(&too_big), Err(Error::Overflow));
+		// lengths beyond the data are rejected without allocating for them
+		assert_eq!(decode(&[0x61, b'a', 0x9b, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff]), Err(Error::UnexpectedEnd));
+		assert_eq!(decode(&[0x61, b'a', 0x5b, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff]), Err(Error::UnexpectedEnd));
+	}
+
+	#[test]
+	fn limits_nesting() {
+		let nested = |depth: usize| [&[0x61, b'a'][..], &vec![0x81; depth], &[0x01]].concat();
+		assert!(decode(&nested(MAX_DEPTH)).is_ok());
+		assert_eq!(decode(&nested(MAX_DEPTH + 1)), Err(Error::Unsupported));
+		// indefinite-length arrays are limited too
+		let indefinite = [&[0x61, b'a'][..], &[0x9f; MAX_DEPTH + 1], &[0x01], &[0xff; MAX_DEPTH + 1]].concat();
+		assert_eq!(decode(&indefinite), Err(Error::Unsupported));
+	}
+}
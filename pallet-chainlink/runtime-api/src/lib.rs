@@ -0,0 +1,23 @@
+//! Runtime API definition for the chainlink pallet.
+
+#![cfg_attr(not(feature = "std"), no_std)]
+
+use codec::Codec;
+use pallet_chainlink::{Request, RequestIdentifier};
+use sp_std::prelude::*;
+
+sp_api::decl_runtime_apis! {
+	/// Queries of operators against the chainlink pallet.
+	pub trait ChainlinkApi<AccountId, Callback, BlockNumber, Balance> where
+		AccountId: Codec,
+		Callback: Codec,
+		BlockNumber: Codec,
+		Balance: Codec,
+	{
+		/// Return the requests waiting for a result of `operator`, so operators that missed an
+		/// `OracleRequest` event can recover them from state.
+		fn pending_requests(
+			operator: AccountId,
+		) -> Vec<(RequestIdentifier, Request<AccountId, Callback, BlockNumber, Balance>)>;
+	}
+}
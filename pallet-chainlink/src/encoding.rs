@@ -0,0 +1,148 @@
+// Formats of the data of requests and of the results provided by Operators
+//
+// `data_version` tells Operators how the `data` of a request is encoded, and consumers how the results
+// they receive are encoded. The pallet itself treats both as opaque bytes, except for aggregated requests:
+// Operators answer them with SCALE encoded `NumericResult`s, and the median is passed to the callback as a
+// `TypedResult::Int` for the formats with typed results (a plain `NumericResult` otherwise).
+
+use codec::{Decode, Encode};
+use sp_runtime::RuntimeDebug;
+use sp_std::{convert::TryFrom, prelude::*};
+
+use crate::DataVersion;
+
+// `data` and results are opaque, their format is agreed on by requesters and Operators
+pub const OPAQUE_DATA_VERSION: DataVersion = 0;
+// `data` holds Chainlink CBOR encoded parameters (the `dataVersion` of Chainlink oracle requests, see the `pallet-chainlink-cbor` crate), results are `TypedResult`s
+pub const CHAINLINK_CBOR_DATA_VERSION: DataVersion = 1;
+// `data` holds SCALE encoded parameters, results are `TypedResult`s
+pub const SCALE_DATA_VERSION: DataVersion = 2;
+
+// The known formats of request data, identified by their `DataVersion`
+#[derive(Clone, Copy, Encode, Decode, PartialEq, Eq, RuntimeDebug)]
+pub enum DataFormat {
+	// Opaque bytes, results are opaque too
+	Opaque,
+	// The parameters of a Chainlink job as a CBOR map without its enclosing delimiters, like the `data` of
+	// Chainlink oracle requests. Keys are text strings, values are text strings, integers, byte strings or arrays of them
+	ChainlinkCbor,
+	// SCALE encoded parameters
+	Scale,
+}
+
+impl DataFormat {
+	// The format of `version`, if known
+	pub fn from_version(version: DataVersion) -> Option<Self> {
+		match version {
+			OPAQUE_DATA_VERSION => Some(DataFormat::Opaque),
+			CHAINLINK_CBOR_DATA_VERSION => Some(DataFormat::ChainlinkCbor),
+			SCALE_DATA_VERSION => Some(DataFormat::Scale),
+			_ => None,
+		}
+	}
+
+	pub fn version(&self) -> DataVersion {
+		match self {
+			DataFormat::Opaque => OPAQUE_DATA_VERSION,
+			DataFormat::ChainlinkCbor => CHAINLINK_CBOR_DATA_VERSION,
+			DataFormat::Scale => SCALE_DATA_VERSION,
+		}
+	}
+
+	// Whether the results of requests in this format are SCALE encoded `TypedResult`s
+	pub fn has_typed_results(&self) -> bool {
+		*self != DataFormat::Opaque
+	}
+}
+
+// A fixed-point number: `value / 10^decimals`
+#[derive(Clone, Copy, Encode, Decode, PartialEq, Eq, RuntimeDebug)]
+pub struct FixedPoint {
+	pub value: i128,
+	pub decimals: u8,
+}
+
+// The result of a request whose format has typed results, provided SCALE encoded by the Operator
+#[derive(Clone, Encode, Decode, PartialEq, Eq, RuntimeDebug)]
+pub enum TypedResult {
+	Int(i128),
+	UInt(u128),
+	Fixed(FixedPoint),
+	Bytes(Vec<u8>),
+	Bool(bool),
+}
+
+// A type a `TypedResult` can be converted to, used as the parameter of typed callbacks
+pub trait FromResult: Sized {
+	fn from_result(result: TypedResult) -> Option<Self>;
+}
+
+impl FromResult for TypedResult {
+	fn from_result(result: TypedResult) -> Option<Self> {
+		Some(result)
+	}
+}
+
+// Integers are converted from any integer result they can hold
+macro_rules! impl_from_result_for_integer {
+	($($integer:ty),*) => {
+		$(
+			impl FromResult for $integer {
+				fn from_result(result: TypedResult) -> Option<Self> {
+					match result {
+						TypedResult::Int(value) => <$integer>::try_from(value).ok(),
+						TypedResult::UInt(value) => <$integer>::try_from(value).ok(),
+						_ => None,
+					}
+				}
+			}
+		)*
+	}
+}
+
+impl_from_result_for_integer!(i128, u128, i64, u64, u32);
+
+impl FromResult for FixedPoint {
+	fn from_result(result: TypedResult) -> Option<Self> {
+		match result {
+			TypedResult::Fixed(value) => Some(value),
+			TypedResult::Int(value) => Some(FixedPoint { value, decimals: 0 }),
+			TypedResult::UInt(value) => i128::try_from(value).ok().map(|value| FixedPoint { value, decimals: 0 }),
+			_ => None,
+		}
+	}
+}
+
+impl FromResult for Vec<u8> {
+	fn from_result(result: TypedResult) -> Option<Self> {
+		match result {
+			TypedResult::Bytes(bytes) => Some(bytes),
+			_ => None,
+		}
+	}
+}
+
+impl FromResult for bool {
+	fn from_result(result: TypedResult) -> Option<Self> {
+		match result {
+			TypedResult::Bool(value) => Some(value),
+			_ => None,
+		}
+	}
+}
+
+// Decode a SCALE encoded `TypedResult` into `R`, rejecting trailing bytes
+pub fn decode_result<R: FromResult>(mut result: &[u8]) -> Option<R> {
+	let typed = TypedResult::decode(&mut result).ok()?;
+	if !result.is_empty() {
+		return None;
+	}
+	R::from_result(typed)
+}
+
+// A helper for `CallbackWithParameter` implementations, building the callback `call` from a typed result:
+// `Call::callback(_) => typed_callback(&result, Call::callback)` for a `callback(origin, result: i128)`.
+// Results that can't be decoded or converted are rejected with `UnknownCallback`, the Operator can answer again.
+pub fn typed_callback<R: FromResult, C>(result: &[u8], call: impl FnOnce(R) -> C) -> Option<C> {
+	decode_result(result).map(call)
+}
@@ -7,7 +7,18 @@
 //!
 //! To initiate a request, users call `initiate_request` with the relevant details, the `operator` AccountId and the `fee` they agree to spend to get the result.
 //!
-//! To be valid, an operator must register its AccountId first hand via `register_operator`.
+//! To be valid, an operator must register its AccountId first hand via `register_operator`, then announce the specifications it supports
+//! (with their minimum fee), its data version and its endpoint via `set_operator_info`. Requests not matching them are rejected.
+//!
+//! `initiate_aggregated_request` sends the same request to several operators. Their numeric results are aggregated by median
+//! once a quorum of them answered, and the median is dispatched to the callback once.
+//!
+//! Requests that don't receive a result within `ValidityPeriod` expire: they are removed in `on_finalize` and their fee is refunded
+//! to the requester. Requesters can give up on a request with `cancel_request` once its validity period passed.
+//!
+//! Operators reserve `OperatorDeposit` when registering. `MissedRequestPenalty` is slashed from it for each request they let expire.
+//! Unregistering is delayed by `UnregistrationDelay`: the deposit is returned by `withdraw_deposit` once the delay passed and all
+//! the pending requests of the operator were answered or expired.
 //!
 //! \## Terminology
 //! Operator: a member of chainlink that provides result to requests, in exchange of a fee payment
@@ -17,16 +28,24 @@
 #![cfg_attr(not(feature = "std"), no_std)]
 
 #[warn(unused_imports)]
-use codec::Codec;
-use frame_support::{decl_error, decl_event, decl_module, decl_storage, ensure, Parameter, dispatch::DispatchResult};
-use frame_support::traits::{Get, ReservableCurrency, Currency, BalanceStatus, UnfilteredDispatchable};
+use codec::{Codec, Decode, Encode};
+use frame_support::{decl_error, decl_event, decl_module, decl_storage, ensure, Parameter, dispatch::{DispatchResult, DispatchResultWithPostInfo}};
+use frame_support::storage::with_transaction;
+use frame_support::traits::{EnsureOrigin, Get, ReservableCurrency, Currency, BalanceStatus, OnUnbalanced, UnfilteredDispatchable};
+use frame_support::weights::{GetDispatchInfo, Weight};
 use sp_std::prelude::*;
 use frame_system::ensure_signed;
+use sp_runtime::{traits::{BadOrigin, Saturating, Zero}, DispatchError, RuntimeDebug, TransactionOutcome};
+use sp_std::marker::PhantomData;
 
+pub mod encoding;
+use encoding::{DataFormat, TypedResult};
+mod migration;
 mod tests;
 
 
 // A trait allowing to inject Operator results back into the specified Call
+// `encoding::typed_callback` builds Calls taking typed results instead of raw bytes
 pub trait CallbackWithParameter {
 	fn with_result(&self, result: Vec<u8>) -> Option<Self> where Self: core::marker::Sized;
 }
@@ -35,56 +54,273 @@ pub trait Trait: frame_system::Trait {
 	type Event: From<Event<Self>> + Into<<Self as frame_system::Trait>::Event>;
 	type Currency: ReservableCurrency<Self::AccountId>;
 
+	// The outer origin type, callbacks are dispatched with the `ChainlinkResponse` origin
+	type Origin: From<RawOrigin<Self::AccountId>>;
+
 	// A reference to an Extrinsic that can have a result injected. Used as Chainlink callback
-	type Callback: Parameter + UnfilteredDispatchable<Origin = Self::Origin> + Codec + Eq + CallbackWithParameter;
+	type Callback: Parameter + UnfilteredDispatchable<Origin = <Self as Trait>::Origin> + Codec + Eq + CallbackWithParameter + GetDispatchInfo;
 
 	// Period during which a request is valid
 	type ValidityPeriod: Get<Self::BlockNumber>;
+
+	// The maximum weight of a callback, requests with heavier callbacks are rejected
+	type MaxCallbackWeight: Get<Weight>;
+
+	// Who gets the fee of a request whose callback fails
+	type CallbackFailurePolicy: Get<CallbackFailurePolicy>;
+
+	// The maximum number of Operators of an aggregated request
+	type MaxOperatorsPerRequest: Get<u32>;
+
+	// The deposit reserved from an Operator when registering
+	type OperatorDeposit: Get<BalanceOf<Self>>;
+
+	// The amount slashed from the deposit of an Operator for each of its requests expiring without its result
+	type MissedRequestPenalty: Get<BalanceOf<Self>>;
+
+	// Handler for the slashed deposits
+	type Slash: OnUnbalanced<NegativeImbalanceOf<Self>>;
+
+	// The number of blocks between unregistering and withdrawing the deposit
+	type UnregistrationDelay: Get<Self::BlockNumber>;
+
+	// The maximum number of specifications an Operator can announce
+	type MaxOperatorSpecs: Get<u32>;
+
+	// The maximum length of the endpoint and of each specification an Operator announces
+	type MaxOperatorInfoLength: Get<u32>;
+}
+
+// Who gets the fee of a request whose callback fails
+#[derive(Clone, Copy, Encode, Decode, PartialEq, Eq, RuntimeDebug)]
+pub enum CallbackFailurePolicy {
+	// The Operator provided a result, it is paid as if the callback succeeded
+	PayOperator,
+	// The result could not be used, the fee is refunded to the requester
+	RefundRequester,
+}
+
+// The origin of the pallet
+#[derive(Clone, Encode, Decode, PartialEq, Eq, RuntimeDebug)]
+pub enum RawOrigin<AccountId> {
+	// The result of a request, provided by its Operator. Callbacks are dispatched with this origin
+	ChainlinkResponse {
+		request_id: RequestIdentifier,
+		operator: AccountId,
+		requester: AccountId,
+	},
+}
+
+// The origin of the pallet
+pub type Origin<T> = RawOrigin<<T as frame_system::Trait>::AccountId>;
+
+// Ensure that the origin `o` is the result of a request and return `(request_id, operator, requester)`
+pub fn ensure_chainlink_response<OuterOrigin, AccountId>(o: OuterOrigin) -> Result<(RequestIdentifier, AccountId, AccountId), BadOrigin>
+	where OuterOrigin: Into<Result<RawOrigin<AccountId>, OuterOrigin>>
+{
+	match o.into() {
+		Ok(RawOrigin::ChainlinkResponse { request_id, operator, requester }) => Ok((request_id, operator, requester)),
+		Err(_) => Err(BadOrigin),
+	}
+}
+
+// An `EnsureOrigin` implementation for consumer callbacks, succeeding with `(request_id, operator, requester)`
+// Consumers should check that `request_id` is one of their own requests
+pub struct EnsureChainlinkResponse<AccountId>(PhantomData<AccountId>);
+
+impl<O, AccountId> EnsureOrigin<O> for EnsureChainlinkResponse<AccountId>
+	where O: Into<Result<RawOrigin<AccountId>, O>> + From<RawOrigin<AccountId>>, AccountId: Default
+{
+	type Success = (RequestIdentifier, AccountId, AccountId);
+
+	fn try_origin(o: O) -> Result<Self::Success, O> {
+		o.into().map(|o| match o {
+			RawOrigin::ChainlinkResponse { request_id, operator, requester } => (request_id, operator, requester),
+		})
+	}
+
+	// The response of a dummy operator to a request of a dummy requester, used to benchmark consumer callbacks
+	#[cfg(feature = "runtime-benchmarks")]
+	fn successful_origin() -> O {
+		O::from(RawOrigin::ChainlinkResponse { request_id: 0, operator: AccountId::default(), requester: AccountId::default() })
+	}
 }
 
+// The weight of `callback` without the weight of the dispatched consumer callback
+pub const CALLBACK_BASE_WEIGHT: Weight = 10_000;
+
 // REVIEW: Use this for transfering currency.
 pub type BalanceOf<T> = <<T as Trait>::Currency as Currency<<T as frame_system::Trait>::AccountId>>::Balance;
+pub type NegativeImbalanceOf<T> = <<T as Trait>::Currency as Currency<<T as frame_system::Trait>::AccountId>>::NegativeImbalance;
 
 // Uniquely identify a request's specification understood by an Operator
 pub type SpecIndex = Vec<u8>;
 // Uniquely identify a request for a considered Operator
 pub type RequestIdentifier = u64;
-// The version of the serialized data format
+// The version of the serialized data format, see `encoding::DataFormat` for the known versions
 pub type DataVersion = u64;
 
+// The metadata an Operator announces, requests must match it
+#[derive(Clone, Default, Encode, Decode, PartialEq, Eq, RuntimeDebug)]
+pub struct OperatorInfo<Balance> {
+	// The supported specifications with the minimum fee of a request for each
+	pub specs: Vec<(SpecIndex, Balance)>,
+	// The supported version of the format of request data
+	pub data_version: DataVersion,
+	// A description of the endpoint of the Operator
+	pub endpoint: Vec<u8>,
+}
+
+pub type OperatorInfoOf<T> = OperatorInfo<BalanceOf<T>>;
+
+// A request waiting for the result of an Operator
+#[derive(Clone, Encode, Decode, PartialEq, Eq, RuntimeDebug)]
+pub struct Request<AccountId, Callback, BlockNumber, Balance> {
+	// The Operator expected to provide the result
+	pub operator: AccountId,
+	// The account that made the request and reserved the fee
+	pub requester: AccountId,
+	// The specification of the request understood by the Operator
+	pub spec_index: SpecIndex,
+	// The version of the format of `data`
+	pub data_version: DataVersion,
+	// The SCALE encoded parameters of the request
+	pub data: Vec<u8>,
+	// The Call the result is injected in
+	pub callback: Callback,
+	// The block the request was made in
+	pub requested_at: BlockNumber,
+	// The fee reserved from the requester, paid to the Operator with the result
+	pub fee: Balance,
+}
+
+pub type RequestOf<T> = Request<<T as frame_system::Trait>::AccountId, <T as Trait>::Callback, <T as frame_system::Trait>::BlockNumber, BalanceOf<T>>;
+
+// The result of an aggregated request: Operators answer with SCALE encoded values, the callback receives their encoded median
+pub type NumericResult = i128;
+
+// A request sent to several Operators, whose results are aggregated once a quorum of them answered
+#[derive(Clone, Encode, Decode, PartialEq, Eq, RuntimeDebug)]
+pub struct AggregatedRequest<AccountId, Callback, BlockNumber, Balance> {
+	// The Operators expected to provide a result
+	pub operators: Vec<AccountId>,
+	// The number of results needed to aggregate them
+	pub quorum: u32,
+	// The account that made the request and reserved the fees
+	pub requester: AccountId,
+	// The specification of the request understood by the Operators
+	pub spec_index: SpecIndex,
+	// The version of the format of `data`
+	pub data_version: DataVersion,
+	// The SCALE encoded parameters of the request
+	pub data: Vec<u8>,
+	// The Call the aggregated result is injected in
+	pub callback: Callback,
+	// The block the request was made in
+	pub requested_at: BlockNumber,
+	// The fee reserved from the requester for each Operator, paid to the Operators providing a result
+	pub fee: Balance,
+	// The results received so far
+	pub results: Vec<(AccountId, NumericResult)>,
+}
+
+pub type AggregatedRequestOf<T> = AggregatedRequest<<T as frame_system::Trait>::AccountId, <T as Trait>::Callback, <T as frame_system::Trait>::BlockNumber, BalanceOf<T>>;
+
+// The layout of the storage, used to migrate it on runtime upgrades
+#[derive(Clone, Copy, Encode, Decode, PartialEq, Eq, RuntimeDebug)]
+pub enum Releases {
+	// `Requests` stores tuples
+	V1,
+	// `Requests` stores `Request`s
+	V2,
+	// Operators have deposits and their pending requests are counted
+	V3,
+}
+
+impl Default for Releases {
+	fn default() -> Self {
+		Releases::V1
+	}
+}
+
 decl_storage! {
     trait Store for Module<T: Trait> as Chainlink {
 		// A set of all registered Operator
 		// TODO migrate to 'natural' hasher once migrated to 2.0
 		pub Operators get(fn operator): map hasher(blake2_128_concat) T::AccountId => bool;
 
+		// The metadata of the registered Operators that announced it
+		pub OperatorInfos get(fn operator_info): map hasher(blake2_128_concat) T::AccountId => Option<OperatorInfoOf<T>>;
+
+		// The deposit left of each Operator, reserved until it withdraws it after unregistering
+		pub OperatorDeposits get(fn operator_deposit): map hasher(blake2_128_concat) T::AccountId => BalanceOf<T>;
+
+		// The number of requests (of any kind) waiting for the result of each Operator
+		pub PendingRequestCounts get(fn pending_request_count): map hasher(blake2_128_concat) T::AccountId => u32;
+
+		// The Operators that unregistered, with the block from which they can withdraw their deposit
+		pub Unregistrations get(fn unregistration): map hasher(blake2_128_concat) T::AccountId => Option<T::BlockNumber>;
+
 		// A running counter used internally to identify the next request
 		pub NextRequestIdentifier get(fn request_identifier): RequestIdentifier;
 
 		// A map of details of each running request
 		// TODO migrate to 'natural' hasher once migrated to 2.0
-		// REVIEW: Consider using a struct for the Requests instead of a tuple to increase
-		//         readability.
-		pub Requests get(fn request): map hasher(blake2_128_concat) RequestIdentifier => (T::AccountId, Vec<T::Callback>, T::BlockNumber, BalanceOf<T>);
+		pub Requests get(fn request): map hasher(blake2_128_concat) RequestIdentifier => Option<RequestOf<T>>;
+
+		// A map of details of each running aggregated request, identified like the other requests
+		pub AggregatedRequests get(fn aggregated_request): map hasher(blake2_128_concat) RequestIdentifier => Option<AggregatedRequestOf<T>>;
+
+		// The requests expiring in a block, so that a block only touches the requests expiring in it.
+		// Answered and cancelled requests are not removed from here, they are skipped on expiry.
+		pub Expiries get(fn expiries): map hasher(twox_64_concat) T::BlockNumber => Vec<RequestIdentifier>;
+
+		// The layout of the storage. New chains start with the current layout, chains started before the
+		// layout was tracked are at `V1` until migrated
+		pub StorageVersion get(fn storage_version) build(|_| Releases::V3): Releases;
     }
 }
 
 decl_event!(
-	pub enum Event<T> where AccountId = <T as frame_system::Trait>::AccountId, Balance = BalanceOf<T> {
+	pub enum Event<T> where AccountId = <T as frame_system::Trait>::AccountId, Balance = BalanceOf<T>, BlockNumber = <T as frame_system::Trait>::BlockNumber {
 		// A request has been accepted. Corresponding fee paiement is reserved
 		OracleRequest(AccountId, SpecIndex, RequestIdentifier, AccountId, DataVersion, Vec<u8>, Vec<u8>, Balance),
 
 		// A request has been answered. Corresponding fee paiement is transfered
 		OracleAnswer(AccountId, RequestIdentifier, AccountId, Vec<u8>, Balance),
 
-		// A new operator has been registered
+		// A new operator has been registered. Its deposit is reserved
 		OperatorRegistered(AccountId),
 
-		// An existing operator has been unregistered
+		// An operator withdrew its deposit and is completely unregistered
 		OperatorUnregistered(AccountId),
 
-		// A request didn't receive any result in time
+		// A request didn't receive any result in time. Followed by `RequestRefunded`
 		KillRequest(RequestIdentifier),
+
+		// An expired request has been cancelled by its requester. The reserved fee has been refunded
+		CancelRequest(RequestIdentifier, AccountId, Balance),
+
+		// The fee reserved for a request that didn't receive any result in time has been refunded to the requester
+		RequestRefunded(RequestIdentifier, AccountId, Balance),
+
+		// The consumer callback of an answered request failed. Its changes have been reverted
+		CallbackFailed(RequestIdentifier, DispatchError),
+
+		// An Operator provided its result to an aggregated request
+		OraclePartialAnswer(AccountId, RequestIdentifier, NumericResult),
+
+		// An aggregated request reached its quorum. The median has been dispatched and the fees of the answering Operators transferred
+		OracleAggregatedAnswer(RequestIdentifier, NumericResult, Balance),
+
+		// An operator updated its metadata
+		OperatorInfoUpdated(AccountId),
+
+		// An operator unregistered, it can withdraw its deposit from the given block on
+		OperatorUnregistering(AccountId, BlockNumber),
+
+		// Part of the deposit of an operator has been slashed because a request expired without its result
+		OperatorPenalized(AccountId, RequestIdentifier, Balance),
 	}
 );
 
@@ -101,25 +337,64 @@ decl_error! {
 		OperatorAlreadyRegistered,
 		// Callback cannot be deserialized
 		UnknownCallback,
-		// Fee provided does not match minimum required fee
-		InsufficientFee,
+		// The weight of the callback exceeds `MaxCallbackWeight`
+		CallbackTooHeavy,
+		// The operator does not support the specification (or did not announce any)
+		UnsupportedSpec,
+		// The operator does not support the data version
+		UnsupportedDataVersion,
+		// The fee is below the minimum fee of the operator for the specification
+		FeeBelowMinimum,
+		// Not the requester of the request
+		WrongRequester,
+		// The validity period of the request did not pass yet
+		CancellationTooEarly,
+		// More Operators than `MaxOperatorsPerRequest`
+		TooManyOperators,
+		// An Operator is given more than once
+		DuplicateOperator,
+		// The quorum is zero or exceeds the number of Operators
+		InvalidQuorum,
+		// The Operator already provided its result
+		AlreadyAnswered,
+		// The result of an aggregated request is not a SCALE encoded `NumericResult`
+		NonNumericResult,
+		// The operator is unregistering and has not withdrawn its deposit yet
+		OperatorUnregistering,
+		// The operator did not unregister
+		NotUnregistering,
+		// The unregistration delay has not passed yet
+		UnregistrationDelayed,
+		// Requests are still waiting for the result of the operator
+		PendingRequests,
+		// The data version is not one of the formats of the `encoding` module
+		UnknownDataVersion,
+		// More specifications than `MaxOperatorSpecs`
+		TooManySpecs,
+		// The endpoint or a specification is longer than `MaxOperatorInfoLength`
+		OperatorInfoTooLong,
 	}
 }
 
 decl_module! {
-	pub struct Module<T: Trait> for enum Call where origin: T::Origin {
+	pub struct Module<T: Trait> for enum Call where origin: <T as frame_system::Trait>::Origin {
 
 		fn deposit_event() = default;
 
 		// REVIEW: Use `///` instead of `//` to make these doc comments that are part of the crate documentation.
-		// Register a new Operator.
-		// Fails with `OperatorAlreadyRegistered` if this Operator (identified by `origin`) has already been registered.
+		// Register a new Operator, reserving `OperatorDeposit`.
+		// Fails with `OperatorAlreadyRegistered` if this Operator (identified by `origin`) has already been registered,
+		// and with `OperatorUnregistering` if it did not withdraw its deposit since unregistering.
 		#[weight = 10_000]
 		pub fn register_operator(origin) -> DispatchResult {
 			let who : <T as frame_system::Trait>::AccountId = ensure_signed(origin)?;
 
 			ensure!(!<Operators<T>>::get(&who), Error::<T>::OperatorAlreadyRegistered);
+			ensure!(!<Unregistrations<T>>::contains_key(&who), Error::<T>::OperatorUnregistering);
 
+			let deposit = T::OperatorDeposit::get();
+			T::Currency::reserve(&who, deposit)?;
+			OperatorDeposits::<T>::insert(&who, deposit);
 			Operators::<T>::insert(&who, true);
 
 			Self::deposit_event(RawEvent::OperatorRegistered(who));
@@ -127,14 +402,15 @@ decl_module! {
 			Ok(())
 		}
 
-		// Unregisters an existing Operator
+		// Unregisters an existing Operator. It stops receiving requests but still has to answer its pending requests.
+		// Its deposit can be withdrawn with `withdraw_deposit` after `UnregistrationDelay` blocks.
 		// TODO check weight
 		#[weight = 10_000]
 		pub fn unregister_operator(origin) -> DispatchResult {
 			let who : <T as frame_system::Trait>::AccountId = ensure_signed(origin)?;
 
 			if Operators::<T>::take(who.clone()) {
-				Self::deposit_event(RawEvent::OperatorUnregistered(who));
+				Self::start_unregistration(who);
 				Ok(())
 			} else {
 				Err(Error::<T>::UnknownOperator.into())
@@ -154,25 +430,29 @@ decl_module! {
 			let who : <T as frame_system::Trait>::AccountId = ensure_signed(origin.clone())?;
 
 			ensure!(<Operators<T>>::get(&operator), Error::<T>::UnknownOperator);
-			// REVIEW: Should probably be at least `ExistentialDeposit`
-			ensure!(fee > BalanceOf::<T>::from(0), Error::<T>::InsufficientFee);
+			ensure!(DataFormat::from_version(data_version).is_some(), Error::<T>::UnknownDataVersion);
+			Self::check_operator_info(&operator, &spec_index, data_version, fee)?;
+			ensure!(callback.get_dispatch_info().weight <= T::MaxCallbackWeight::get(), Error::<T>::CallbackTooHeavy);
 
 			T::Currency::reserve(&who, fee.into())?;
 
-			let request_id = NextRequestIdentifier::get();
-			// REVIEW: This can overflow. You can make a maximum of `u64::max_value()` requests.
-			//         Default behavior for `u64` is to wrap around to 0, but you might want to
-			//         make this explicit.
-			//         I think using `wrapping_add` could be fine here, because it should be fine to
-			//         start at 0 when you reach `u64::max_value()`.
-			NextRequestIdentifier::put(request_id + 1);
+			let request_id = Self::next_request_identifier();
 
-			// REVIEW: Is it intentional that requests are only valid during the current block?
 			let now = frame_system::Module::<T>::block_number();
 			// REVIEW: You might want to think about and document that your requests can be overwritten
 			//         as soon as the request id wraps around.
-			// REVIEW: Is the `Vec` intended for forward compatibility? It seems superfluous here.
-			Requests::<T>::insert(request_id.clone(), (operator.clone(), vec![callback], now, fee));
+			Requests::<T>::insert(request_id, Request {
+				operator: operator.clone(),
+				requester: who.clone(),
+				spec_index: spec_index.clone(),
+				data_version,
+				data: data.clone(),
+				callback,
+				requested_at: now,
+				fee,
+			});
+			Expiries::<T>::append(Self::expiry(now), request_id);
+			PendingRequestCounts::<T>::mutate(&operator, |count| *count = count.saturating_add(1));
 
 			Self::deposit_event(RawEvent::OracleRequest(operator, spec_index, request_id, who, data_version, data, "Chainlink.callback".into(), fee));
 
@@ -181,48 +461,399 @@ decl_module! {
 
 		// The callback used to be notified of all Operators results.
 		// Only the Operator responsible for an identified request can notify back the result.
-		// Result is then dispatched back to the originator's callback.
+		// Results to aggregated requests are collected until their quorum is reached, see `initiate_aggregated_request`.
+		// Result is then dispatched back to the originator's callback with the `ChainlinkResponse` origin.
 		// The fee reserved during `initiate_request` is transferred as soon as this callback is called.
-		// TODO check weight
-		#[weight = 10_000]
-        fn callback(origin, request_id: RequestIdentifier, result: Vec<u8>) -> DispatchResult {
+		// If the result can't be dispatched, the changes of the callback are reverted, `CallbackFailed` is emitted and the fee goes to whoever `CallbackFailurePolicy` specifies.
+		// The weight is charged up to `MaxCallbackWeight` and refunded down to the actual weight of the dispatched callback.
+		#[weight = CALLBACK_BASE_WEIGHT.saturating_add(T::MaxCallbackWeight::get())]
+        fn callback(origin, request_id: RequestIdentifier, result: Vec<u8>) -> DispatchResultWithPostInfo {
 			let who : <T as frame_system::Trait>::AccountId = ensure_signed(origin.clone())?;
 
-			ensure!(<Requests<T>>::contains_key(&request_id), Error::<T>::UnknownRequest);
-			let (operator, callback, _, fee) = <Requests<T>>::get(&request_id);
+			let Request { operator, requester, callback, fee, .. } = match <Requests<T>>::get(&request_id) {
+				Some(request) => request,
+				None => return Self::answer_aggregated(who, request_id, result),
+			};
 			ensure!(operator == who, Error::<T>::WrongOperator);
+			let callback = callback.with_result(result.clone()).ok_or(Error::<T>::UnknownCallback)?;
 
-			// REVIEW: This does not make sure that the fee is payed. `repatriate_reserved` removes
-			//         *up to* the amount passed. [See here](https://substrate.dev/rustdocs/master/frame_support/traits/trait.ReservableCurrency.html#tymethod.repatriate_reserved)
-			//         Check `reserved_balance()` to make sure that the fee is payable via this method.
-			//         Maybe use a different payment method and check `total_balance()`. I don't know
-			//         Substrate's Currency module well enough to tell.
-			// REVIEW: This happens *after* the request is `take`n from storage. Is that intended?
-			//         See ["verify first, write last"](https://substrate.dev/recipes/2-appetizers/1-hello-substrate.html#inside-a-dispatchable-call) motto.
-			// TODO chec whether to use BalanceStatus::Reserved or Free?
-			T::Currency::repatriate_reserved(&who, &operator, fee.into(), BalanceStatus::Free)?;
+			// The request is answered, it must neither be answered again nor expire
+			Requests::<T>::remove(request_id);
+			Self::release_operator(&operator);
 
-			// Dispatch the result to the original callback registered by the caller
-			// TODO fix the "?" - not sure how to proceed there
-			callback[0].with_result(result.clone()).ok_or(Error::<T>::UnknownCallback)?.dispatch_bypass_filter(frame_system::RawOrigin::Root.into()).ok();
-			// callback[0].with_result(result.clone()).ok_or(Error::<T>::UnknownCallback)?.dispatch(frame_system::RawOrigin::Root.into())?;
+			let (succeeded, callback_weight) = Self::dispatch_callback(request_id, &operator, &requester, callback);
+			// The request is already removed, so the payment must not fail the call: whatever can't be paid is refunded
+			let paid_fee = if Self::pays_operators(succeeded) {
+				Self::pay(&requester, &operator, fee)
+			} else {
+				BalanceOf::<T>::zero()
+			};
+			Self::refund(&requester, fee.saturating_sub(paid_fee));
 
-			Self::deposit_event(RawEvent::OracleAnswer(operator, request_id, who, result, fee));
+			Self::deposit_event(RawEvent::OracleAnswer(operator, request_id, who, result, paid_fee));
 
-            Ok(())
+			Ok(Some(CALLBACK_BASE_WEIGHT.saturating_add(callback_weight)).into())
 		}
 
-		// Identify requests that are considered dead and remove them
-		fn on_finalize(n: T::BlockNumber) {
-			for (request_identifier, (_account_id, _data, block_number, _fee)) in Requests::<T>::iter() {
-				if n > block_number + T::ValidityPeriod::get() {
-					// No result has been received in time
-					Requests::<T>::remove(request_identifier);
+		// Cancel a request that didn't receive a result within `ValidityPeriod` and refund its fee.
+		// Only the requester of an identified request can cancel it.
+		// Expired requests are also removed (and refunded) in `on_finalize`, this allows requesters to reclaim their fee without waiting for it.
+		// The Operators are only penalized if the request already expired.
+		// The Operators that already answered an aggregated request are paid like for expired requests.
+		#[weight = 10_000]
+		pub fn cancel_request(origin, request_id: RequestIdentifier) -> DispatchResult {
+			let who : <T as frame_system::Trait>::AccountId = ensure_signed(origin)?;
+
+			let (requester, requested_at) = match <Requests<T>>::get(&request_id) {
+				Some(request) => (request.requester, request.requested_at),
+				None => {
+					let request = <AggregatedRequests<T>>::get(&request_id).ok_or(Error::<T>::UnknownRequest)?;
+					(request.requester, request.requested_at)
+				}
+			};
+			ensure!(requester == who, Error::<T>::WrongRequester);
+			let now = frame_system::Module::<T>::block_number();
+			ensure!(now >= Self::cancellable_from(requested_at), Error::<T>::CancellationTooEarly);
+
+			if let Some((requester, refunded)) = Self::remove_request(request_id, Self::is_expired(requested_at, now)) {
+				Self::deposit_event(RawEvent::CancelRequest(request_id, requester, refunded));
+			}
+
+			Ok(())
+		}
+
+		// Send the same request to several Operators, reserving `fee` for each of them.
+		// Each Operator receives an `OracleRequest` event and answers with `callback` like for other requests. Results must be SCALE encoded `NumericResult`s.
+		// Once `quorum` Operators answered, the median of their results is dispatched once to `callback`.
+		// The answering Operators are paid according to `CallbackFailurePolicy`, and the fees of the other Operators are refunded.
+		// Expired requests pay the Operators that answered and refund the other fees.
+		#[weight = 10_000u64.saturating_mul(operators.len().max(1) as Weight)]
+		pub fn initiate_aggregated_request(origin, operators: Vec<T::AccountId>, quorum: u32, spec_index: SpecIndex, data_version: DataVersion, data: Vec<u8>, fee: BalanceOf<T>, callback: <T as Trait>::Callback) -> DispatchResult {
+			let who : <T as frame_system::Trait>::AccountId = ensure_signed(origin)?;
+
+			ensure!(operators.len() <= T::MaxOperatorsPerRequest::get() as usize, Error::<T>::TooManyOperators);
+			ensure!(quorum > 0 && quorum as usize <= operators.len(), Error::<T>::InvalidQuorum);
+			ensure!(DataFormat::from_version(data_version).is_some(), Error::<T>::UnknownDataVersion);
+			for (i, operator) in operators.iter().enumerate() {
+				ensure!(<Operators<T>>::get(operator), Error::<T>::UnknownOperator);
+				ensure!(!operators[..i].contains(operator), Error::<T>::DuplicateOperator);
+				Self::check_operator_info(operator, &spec_index, data_version, fee)?;
+			}
+			ensure!(callback.get_dispatch_info().weight <= T::MaxCallbackWeight::get(), Error::<T>::CallbackTooHeavy);
+
+			T::Currency::reserve(&who, Self::total_fee(fee, operators.len()))?;
+
+			let request_id = Self::next_request_identifier();
+			let now = frame_system::Module::<T>::block_number();
+			for operator in &operators {
+				PendingRequestCounts::<T>::mutate(operator, |count| *count = count.saturating_add(1));
+				Self::deposit_event(RawEvent::OracleRequest(operator.clone(), spec_index.clone(), request_id, who.clone(), data_version, data.clone(), "Chainlink.callback".into(), fee));
+			}
+			AggregatedRequests::<T>::insert(request_id, AggregatedRequest {
+				operators,
+				quorum,
+				requester: who,
+				spec_index,
+				data_version,
+				data,
+				callback,
+				requested_at: now,
+				fee,
+				results: Vec::new(),
+			});
+			Expiries::<T>::append(Self::expiry(now), request_id);
+
+			Ok(())
+		}
 
+		// Announce the specifications supported by a registered Operator with their minimum fee, its data version and endpoint.
+		// Replaces the previous metadata, requests made before are not affected.
+		// The number of specifications is bounded by `MaxOperatorSpecs`, the endpoint and each specification by `MaxOperatorInfoLength`.
+		#[weight = 10_000u64.saturating_add(100u64.saturating_mul(info.using_encoded(|encoded| encoded.len()) as Weight))]
+		pub fn set_operator_info(origin, info: OperatorInfoOf<T>) -> DispatchResult {
+			let who : <T as frame_system::Trait>::AccountId = ensure_signed(origin)?;
+
+			ensure!(<Operators<T>>::get(&who), Error::<T>::UnknownOperator);
+			ensure!(info.specs.len() <= T::MaxOperatorSpecs::get() as usize, Error::<T>::TooManySpecs);
+			let max_length = T::MaxOperatorInfoLength::get() as usize;
+			ensure!(info.endpoint.len() <= max_length, Error::<T>::OperatorInfoTooLong);
+			ensure!(info.specs.iter().all(|(spec_index, _)| spec_index.len() <= max_length), Error::<T>::OperatorInfoTooLong);
+
+			OperatorInfos::<T>::insert(&who, info);
+
+			Self::deposit_event(RawEvent::OperatorInfoUpdated(who));
+
+			Ok(())
+		}
+
+		// Return the deposit of an unregistered Operator (less its penalties) and complete its unregistration.
+		// Fails with `UnregistrationDelayed` before `UnregistrationDelay` passed and with `PendingRequests` until all its requests were answered or expired.
+		// TODO check weight
+		#[weight = 10_000]
+		pub fn withdraw_deposit(origin) -> DispatchResult {
+			let who : <T as frame_system::Trait>::AccountId = ensure_signed(origin)?;
+
+			let withdrawable_at = <Unregistrations<T>>::get(&who).ok_or(Error::<T>::NotUnregistering)?;
+			ensure!(frame_system::Module::<T>::block_number() >= withdrawable_at, Error::<T>::UnregistrationDelayed);
+			ensure!(<PendingRequestCounts<T>>::get(&who) == 0, Error::<T>::PendingRequests);
+
+			T::Currency::unreserve(&who, OperatorDeposits::<T>::take(&who));
+			PendingRequestCounts::<T>::remove(&who);
+			Unregistrations::<T>::remove(&who);
+
+			Self::deposit_event(RawEvent::OperatorUnregistered(who));
+
+			Ok(())
+		}
+
+		fn on_runtime_upgrade() -> Weight {
+			let mut weight = T::DbWeight::get().reads(1);
+			if StorageVersion::get() == Releases::V1 {
+				weight = weight.saturating_add(migration::migrate_to_v2::<T>());
+			}
+			if StorageVersion::get() == Releases::V2 {
+				weight = weight.saturating_add(migration::migrate_to_v3::<T>());
+			}
+			weight
+		}
+
+		// Account for the requests expiring in this block, they are removed in `on_finalize`
+		fn on_initialize(n: T::BlockNumber) -> Weight {
+			let expiring = Expiries::<T>::decode_len(n).unwrap_or(0) as Weight;
+			let operators = T::MaxOperatorsPerRequest::get().max(1) as Weight;
+			// Reading the length, taking the queue, then per request reading both request maps,
+			// removing it, unreserving the fees of the requester, paying up to all its operators and
+			// updating the pending requests and deposits of the operators
+			T::DbWeight::get().reads_writes(2 + (2 + 3 * operators) * expiring, 1 + (2 + 3 * operators) * expiring)
+		}
+
+		// Remove the requests that are considered dead in this block and refund their fee
+		fn on_finalize(n: T::BlockNumber) {
+			for request_identifier in Expiries::<T>::take(n) {
+				// Skip the requests that have been answered or cancelled
+				if let Some((requester, refunded)) = Self::remove_request(request_identifier, true) {
+					// No (or not enough) result has been received in time
 					Self::deposit_event(RawEvent::KillRequest(request_identifier));
+					Self::deposit_event(RawEvent::RequestRefunded(request_identifier, requester, refunded));
 				}
 			}
 		}
 
 	}
 }
+
+impl<T: Trait> Module<T> {
+	// The requests waiting for a result of `operator`, for operators to recover the requests they missed
+	// Aggregated requests `operator` didn't answer yet are included as requests to `operator` with the fee of a single Operator
+	pub fn pending_requests(operator: T::AccountId) -> Vec<(RequestIdentifier, RequestOf<T>)> {
+		let aggregated = AggregatedRequests::<T>::iter()
+			.filter(|(_, request)| request.operators.contains(&operator) && !request.results.iter().any(|(answered, _)| *answered == operator))
+			.map(|(request_id, request)| (request_id, Request {
+				operator: operator.clone(),
+				requester: request.requester,
+				spec_index: request.spec_index,
+				data_version: request.data_version,
+				data: request.data,
+				callback: request.callback,
+				requested_at: request.requested_at,
+				fee: request.fee,
+			}));
+		Requests::<T>::iter()
+			.filter(|(_, request)| request.operator == operator)
+			.chain(aggregated)
+			.collect()
+	}
+
+	// Check a request against the metadata of `operator`
+	fn check_operator_info(operator: &T::AccountId, spec_index: &SpecIndex, data_version: DataVersion, fee: BalanceOf<T>) -> DispatchResult {
+		let info = OperatorInfos::<T>::get(operator).ok_or(Error::<T>::UnsupportedSpec)?;
+		let (_, minimum_fee) = info.specs.iter().find(|(spec, _)| spec == spec_index).ok_or(Error::<T>::UnsupportedSpec)?;
+		ensure!(info.data_version == data_version, Error::<T>::UnsupportedDataVersion);
+		ensure!(fee >= *minimum_fee, Error::<T>::FeeBelowMinimum);
+		Ok(())
+	}
+
+	fn next_request_identifier() -> RequestIdentifier {
+		let request_id = NextRequestIdentifier::get();
+		// REVIEW: This can overflow. You can make a maximum of `u64::max_value()` requests.
+		//         Default behavior for `u64` is to wrap around to 0, but you might want to
+		//         make this explicit.
+		//         I think using `wrapping_add` could be fine here, because it should be fine to
+		//         start at 0 when you reach `u64::max_value()`.
+		NextRequestIdentifier::put(request_id + 1);
+		request_id
+	}
+
+	// Record the result of `operator` to an aggregated request, dispatching the median once the quorum is reached
+	fn answer_aggregated(operator: T::AccountId, request_id: RequestIdentifier, result: Vec<u8>) -> DispatchResultWithPostInfo {
+		let mut request = <AggregatedRequests<T>>::get(&request_id).ok_or(Error::<T>::UnknownRequest)?;
+		ensure!(request.operators.contains(&operator), Error::<T>::WrongOperator);
+		ensure!(!request.results.iter().any(|(answered, _)| *answered == operator), Error::<T>::AlreadyAnswered);
+		let value = NumericResult::decode(&mut &result[..]).map_err(|_| Error::<T>::NonNumericResult)?;
+		request.results.push((operator.clone(), value));
+
+		if (request.results.len() as u32) < request.quorum {
+			AggregatedRequests::<T>::insert(request_id, request);
+			Self::deposit_event(RawEvent::OraclePartialAnswer(operator, request_id, value));
+			return Ok(Some(CALLBACK_BASE_WEIGHT).into());
+		}
+
+		let answer = median(request.results.iter().map(|(_, value)| *value).collect());
+		// Formats with typed results receive the median as a `TypedResult` like the results of single requests
+		let encoded_answer = match DataFormat::from_version(request.data_version) {
+			Some(format) if format.has_typed_results() => TypedResult::Int(answer).encode(),
+			_ => answer.encode(),
+		};
+		let callback = request.callback.with_result(encoded_answer).ok_or(Error::<T>::UnknownCallback)?;
+
+		// The request is answered, it must neither be answered again nor expire
+		AggregatedRequests::<T>::remove(request_id);
+		Self::deposit_event(RawEvent::OraclePartialAnswer(operator.clone(), request_id, value));
+
+		let (succeeded, callback_weight) = Self::dispatch_callback(request_id, &operator, &request.requester, callback);
+		let (paid, _) = Self::settle_aggregated(&request, Self::pays_operators(succeeded));
+		request.operators.iter().for_each(Self::release_operator);
+
+		Self::deposit_event(RawEvent::OracleAggregatedAnswer(request_id, answer, paid));
+
+		Ok(Some(CALLBACK_BASE_WEIGHT.saturating_add(callback_weight)).into())
+	}
+
+	// Dispatch the result to the original callback registered by the requester, reverting its changes if it fails
+	// Returns whether the callback succeeded and its actual weight
+	fn dispatch_callback(request_id: RequestIdentifier, operator: &T::AccountId, requester: &T::AccountId, callback: T::Callback) -> (bool, Weight) {
+		let callback_info = callback.get_dispatch_info();
+		let response_origin = RawOrigin::ChainlinkResponse { request_id, operator: operator.clone(), requester: requester.clone() };
+		let dispatched = with_transaction(|| {
+			match callback.dispatch_bypass_filter(response_origin.into()) {
+				Ok(post_info) => TransactionOutcome::Commit(Ok(post_info)),
+				Err(e) => TransactionOutcome::Rollback(Err(e)),
+			}
+		});
+		match dispatched {
+			Ok(post_info) => (true, post_info.calc_actual_weight(&callback_info)),
+			Err(e) => {
+				Self::deposit_event(RawEvent::CallbackFailed(request_id, e.error));
+				(false, e.post_info.calc_actual_weight(&callback_info))
+			}
+		}
+	}
+
+	// Whether the Operators are paid for a result whose callback succeeded (or not)
+	fn pays_operators(callback_succeeded: bool) -> bool {
+		callback_succeeded || T::CallbackFailurePolicy::get() == CallbackFailurePolicy::PayOperator
+	}
+
+	// Remove an expired or cancelled request (of any kind) and settle its fees, returning its requester and the refunded amount
+	// The Operators that did not answer are penalized if `expired`
+	fn remove_request(request_id: RequestIdentifier, expired: bool) -> Option<(T::AccountId, BalanceOf<T>)> {
+		if let Some(Request { operator, requester, fee, .. }) = Requests::<T>::take(request_id) {
+			let refunded = Self::refund(&requester, fee);
+			Self::release_operator(&operator);
+			if expired {
+				Self::penalize(&operator, request_id);
+			}
+			return Some((requester, refunded));
+		}
+		let request = AggregatedRequests::<T>::take(request_id)?;
+		// The Operators that answered are paid for their results
+		let (_, refunded) = Self::settle_aggregated(&request, true);
+		for operator in &request.operators {
+			Self::release_operator(operator);
+			if expired && !request.results.iter().any(|(answered, _)| answered == operator) {
+				Self::penalize(operator, request_id);
+			}
+		}
+		Some((request.requester, refunded))
+	}
+
+	// Stop sending requests to the unregistered `operator`, its deposit can be withdrawn after `UnregistrationDelay`
+	fn start_unregistration(operator: T::AccountId) {
+		OperatorInfos::<T>::remove(&operator);
+		let withdrawable_at = frame_system::Module::<T>::block_number().saturating_add(T::UnregistrationDelay::get());
+		Unregistrations::<T>::insert(&operator, withdrawable_at);
+		Self::deposit_event(RawEvent::OperatorUnregistering(operator, withdrawable_at));
+	}
+
+	// A request of `operator` has been answered or removed
+	fn release_operator(operator: &T::AccountId) {
+		PendingRequestCounts::<T>::mutate(operator, |count| *count = count.saturating_sub(1));
+	}
+
+	// Slash `MissedRequestPenalty` from the deposit of `operator` for letting `request_id` expire
+	// The penalty is capped at what is left of the deposit in `OperatorDeposits`, which is decreased by the slashed amount.
+	// `slash_reserved` draws from the whole reserved balance of the Operator though: the cap keeps the total slashed below the
+	// deposit, but if other pallets reduced the reserved balance, funds the Operator reserved for other purposes can be slashed.
+	fn penalize(operator: &T::AccountId, request_id: RequestIdentifier) {
+		let deposit = OperatorDeposits::<T>::get(operator);
+		let penalty = T::MissedRequestPenalty::get().min(deposit);
+		if penalty.is_zero() {
+			return;
+		}
+		let (imbalance, remaining) = T::Currency::slash_reserved(operator, penalty);
+		let slashed = penalty.saturating_sub(remaining);
+		OperatorDeposits::<T>::insert(operator, deposit.saturating_sub(slashed));
+		T::Slash::on_unbalanced(imbalance);
+		Self::deposit_event(RawEvent::OperatorPenalized(operator.clone(), request_id, slashed));
+	}
+
+	// Pay the fee of each Operator that answered `request` (if `pay`) and refund the other fees.
+	// Returns the paid and the refunded amount
+	fn settle_aggregated(request: &AggregatedRequestOf<T>, pay: bool) -> (BalanceOf<T>, BalanceOf<T>) {
+		let mut paid = BalanceOf::<T>::zero();
+		if pay {
+			for (operator, _) in &request.results {
+				paid = paid.saturating_add(Self::pay(&request.requester, operator, request.fee));
+			}
+		}
+		let unpaid = Self::total_fee(request.fee, request.operators.len()).saturating_sub(paid);
+		(paid, Self::refund(&request.requester, unpaid))
+	}
+
+	// Transfer `fee` from the reserved balance of `requester` to `operator`, returning the amount actually paid
+	fn pay(requester: &T::AccountId, operator: &T::AccountId, fee: BalanceOf<T>) -> BalanceOf<T> {
+		match T::Currency::repatriate_reserved(requester, operator, fee, BalanceStatus::Free) {
+			Ok(remaining) => fee.saturating_sub(remaining),
+			Err(_) => BalanceOf::<T>::zero(),
+		}
+	}
+
+	// The fees of `operators` Operators
+	fn total_fee(fee: BalanceOf<T>, operators: usize) -> BalanceOf<T> {
+		fee.saturating_mul((operators as u32).into())
+	}
+
+	// Whether a request made in block `requested_at` has expired at block `now`
+	fn is_expired(requested_at: T::BlockNumber, now: T::BlockNumber) -> bool {
+		now >= Self::expiry(requested_at)
+	}
+
+	// The first block in which a request made in block `requested_at` can be cancelled by its requester
+	fn cancellable_from(requested_at: T::BlockNumber) -> T::BlockNumber {
+		requested_at.saturating_add(T::ValidityPeriod::get())
+	}
+
+	// The first block in which a request made in block `requested_at` is expired
+	fn expiry(requested_at: T::BlockNumber) -> T::BlockNumber {
+		requested_at.saturating_add(T::ValidityPeriod::get()).saturating_add(1u32.into())
+	}
+
+	// Unreserve the `fee` reserved by `requester` and return the amount actually refunded
+	fn refund(requester: &T::AccountId, fee: BalanceOf<T>) -> BalanceOf<T> {
+		let remaining = T::Currency::unreserve(requester, fee);
+		fee.saturating_sub(remaining)
+	}
+}
+
+// The median of `values`, the mean of the two middle values for an even number of values
+fn median(mut values: Vec<NumericResult>) -> NumericResult {
+	values.sort_unstable();
+	let middle = values.len() / 2;
+	if values.len() % 2 == 0 {
+		let (low, high) = (values[middle - 1], values[middle]);
+		// `(low + high) / 2` could overflow
+		low / 2 + high / 2 + (low % 2 + high % 2) / 2
+	} else {
+		values[middle]
+	}
+}
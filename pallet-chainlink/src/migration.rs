@@ -0,0 +1,94 @@
+// Storage migrations of the chainlink pallet
+
+use frame_support::{debug, traits::{Get, ReservableCurrency}, weights::Weight, IterableStorageMap, StorageMap, StorageValue};
+use sp_std::{cell::Cell, collections::btree_map::BTreeMap, prelude::*};
+
+use crate::{
+	AggregatedRequests, BalanceOf, Module, OperatorDeposits, Operators, PendingRequestCounts, Releases, Requests, StorageVersion, Trait,
+};
+
+// A request as stored in `V1`: `(operator, callbacks, block the request was made in, fee)`
+type OldRequest<T> = (
+	<T as frame_system::Trait>::AccountId,
+	Vec<<T as Trait>::Callback>,
+	<T as frame_system::Trait>::BlockNumber,
+	BalanceOf<T>,
+);
+
+// Remove the tuples stored in `Requests`.
+//
+// `V1` did not store the requester of a request, so the fee it reserved can neither be refunded nor
+// used to pay the operator (the `V1` callback took the fee from the reserved balance of the operator).
+// The requests are dropped and each one is logged with its operator and fee: the requester of a request
+// is only known from its `OracleRequest` event, so its fee has to be unreserved out of band.
+pub fn migrate_to_v2<T: Trait>() -> Weight {
+	let dropped = Cell::new(0u64);
+	Requests::<T>::translate::<OldRequest<T>, _>(|request_id, (operator, _, requested_at, fee)| {
+		dropped.set(dropped.get() + 1);
+		debug::warn!(
+			"Dropping request {} to operator {:?} made in block {:?} without a requester, its fee of {:?} stays reserved",
+			request_id, operator, requested_at, fee
+		);
+		None
+	});
+	StorageVersion::put(Releases::V2);
+
+	let dropped = dropped.get();
+	T::DbWeight::get().reads_writes(1 + dropped, 1 + dropped)
+}
+
+// Count the pending requests of each Operator and reserve the deposits of the registered Operators.
+//
+// Operators registered before deposits were required are asked for `OperatorDeposit` now. Operators that can't
+// afford it are unregistered: they still have to answer their pending requests (without a deposit to slash) and
+// can register again once `UnregistrationDelay` passed and their requests were answered or expired.
+//
+// Deposits are only reserved by `V3` code, so storage holding any of them already has the `V3` layout (e.g. a chain
+// started without `StorageVersion`): it is left alone instead of reserving and counting everything a second time.
+pub fn migrate_to_v3<T: Trait>() -> Weight {
+	if OperatorDeposits::<T>::iter().next().is_some() {
+		StorageVersion::put(Releases::V3);
+		return T::DbWeight::get().reads_writes(1, 1);
+	}
+
+	let mut counts = BTreeMap::<T::AccountId, u32>::new();
+	let mut requests = 0u64;
+	for (_, request) in Requests::<T>::iter() {
+		requests += 1;
+		*counts.entry(request.operator).or_default() += 1;
+	}
+	for (_, request) in AggregatedRequests::<T>::iter() {
+		requests += 1;
+		for operator in request.operators {
+			if !request.results.iter().any(|(answered, _)| *answered == operator) {
+				*counts.entry(operator).or_default() += 1;
+			}
+		}
+	}
+	let counted = counts.len() as u64;
+	for (operator, count) in counts {
+		PendingRequestCounts::<T>::insert(operator, count);
+	}
+
+	let deposit = T::OperatorDeposit::get();
+	// collect first so Operators can be unregistered while going through them
+	let registered: Vec<(T::AccountId, bool)> = Operators::<T>::iter().collect();
+	let operators = registered.len() as u64;
+	for (operator, registered) in registered {
+		if !registered || OperatorDeposits::<T>::contains_key(&operator) {
+			continue;
+		}
+		if T::Currency::reserve(&operator, deposit).is_ok() {
+			OperatorDeposits::<T>::insert(&operator, deposit);
+		} else {
+			debug::warn!("Unregistering operator {:?} that can't afford the deposit of {:?}", operator, deposit);
+			Operators::<T>::remove(&operator);
+			Module::<T>::start_unregistration(operator);
+		}
+	}
+	StorageVersion::put(Releases::V3);
+
+	// Checking for deposits, then per registered Operator: reading its deposit and its account, then either reserving
+	// the deposit (account and deposit) or unregistering it (registration, metadata, unregistration and the event)
+	T::DbWeight::get().reads_writes(4 + requests + 3 * operators, 1 + counted + 4 * operators)
+}
@@ -3,7 +3,7 @@
 use super::*;
 use frame_support::{
 	impl_outer_origin, impl_outer_event, parameter_types, weights::Weight,
-	traits::{OnFinalize}
+	traits::{OnFinalize, OnInitialize, OnRuntimeUpgrade}, weights::constants::RocksDbWeight
 };
 use sp_core::H256;
 use sp_runtime::{
@@ -12,11 +12,13 @@ use sp_runtime::{
 	traits::{BlakeTwo256, IdentityLookup},
 };
 use codec::{Decode, Encode};
-use crate::sp_api_hidden_includes_decl_storage::hidden_include::StorageValue;
+use crate::sp_api_hidden_includes_decl_storage::hidden_include::{StorageMap, StorageValue};
 use frame_system as system;
 
 impl_outer_origin! {
-	pub enum Origin for Test where system = frame_system {}
+	pub enum Origin for Test where system = frame_system {
+		chainlink<T>,
+	}
 }
 
 pub mod chainlink {
@@ -55,7 +57,7 @@ impl system::Trait for Test {
 	type Event = TestEvent;
 	type BlockHashCount = BlockHashCount;
 	type MaximumBlockWeight = MaximumBlockWeight;
-	type DbWeight = ();
+	type DbWeight = RocksDbWeight;
 	type BlockExecutionWeight = ();
 	type ExtrinsicBaseWeight = ();
 	type MaximumExtrinsicWeight = MaximumBlockWeight;
@@ -83,13 +85,51 @@ impl pallet_balances::Trait for Test {
 impl chainlink::Trait for Test {
 	type Event = TestEvent;
 	type Currency = pallet_balances::Module<Test>;
+	type Origin = Origin;
 	type Callback = module2::Call<Test>;
 	type ValidityPeriod = ValidityPeriod;
+	type MaxCallbackWeight = MaxCallbackWeight;
+	type CallbackFailurePolicy = TestCallbackFailurePolicy;
+	type MaxOperatorsPerRequest = MaxOperatorsPerRequest;
+	type OperatorDeposit = TestOperatorDeposit;
+	type MissedRequestPenalty = MissedRequestPenalty;
+	type Slash = ();
+	type UnregistrationDelay = UnregistrationDelay;
+	type MaxOperatorSpecs = MaxOperatorSpecs;
+	type MaxOperatorInfoLength = MaxOperatorInfoLength;
 }
 impl module2::Trait for Test {
+	type ResponseOrigin = EnsureChainlinkResponse<u128>;
 }
 parameter_types! {
 	pub const ValidityPeriod: u64 = 10;
+	pub const MaxCallbackWeight: Weight = 1_000;
+	pub const MaxOperatorsPerRequest: u32 = 3;
+	pub const MissedRequestPenalty: u64 = 30;
+	pub const UnregistrationDelay: u64 = 5;
+	pub const MaxOperatorSpecs: u32 = 4;
+	pub const MaxOperatorInfoLength: u32 = 32;
+}
+
+thread_local! {
+	static CALLBACK_FAILURE_POLICY: std::cell::Cell<CallbackFailurePolicy> = std::cell::Cell::new(CallbackFailurePolicy::PayOperator);
+}
+pub struct TestCallbackFailurePolicy;
+impl Get<CallbackFailurePolicy> for TestCallbackFailurePolicy {
+	fn get() -> CallbackFailurePolicy {
+		CALLBACK_FAILURE_POLICY.with(|policy| policy.get())
+	}
+}
+
+thread_local! {
+	// No deposit by default, so that the balances of the operators only reflect their fees
+	static OPERATOR_DEPOSIT: std::cell::Cell<u64> = std::cell::Cell::new(0);
+}
+pub struct TestOperatorDeposit;
+impl Get<u64> for TestOperatorDeposit {
+	fn get() -> u64 {
+		OPERATOR_DEPOSIT.with(|deposit| deposit.get())
+	}
 }
 
 type System = frame_system::Module<Test>;
@@ -99,12 +139,27 @@ pub fn new_test_ext() -> sp_io::TestExternalities {
 	let mut t = system::GenesisConfig::default().build_storage::<Test>().unwrap();
 	pallet_balances::GenesisConfig::<Test>{
 		// Total issuance will be 200 with treasury account initialized at ED.
-		balances: vec![(0, 100000), (1, 100000), (2, 100000)],
+		balances: vec![(0, 100000), (1, 100000), (2, 100000), (3, 100000), (4, 100000)],
 	}.assimilate_storage(&mut t).unwrap();
+	GenesisConfig::default().assimilate_storage::<Test>(&mut t).unwrap();
 	t.into()
 }
 
-pub fn last_event() -> RawEvent<u128, u64> {
+// Metadata supporting the specifications used in the tests with a minimum fee of 1
+pub fn operator_info() -> OperatorInfo<u64> {
+	OperatorInfo {
+		specs: vec![(vec![], 1), (vec![1], 1), (vec![2], 1), (vec![3], 1)],
+		data_version: 1,
+		endpoint: b"https://operator.example".to_vec(),
+	}
+}
+
+pub fn register_operator(operator: u128) {
+	assert!(<Chainlink>::register_operator(Origin::signed(operator)).is_ok());
+	assert!(<Chainlink>::set_operator_info(Origin::signed(operator), operator_info()).is_ok());
+}
+
+pub fn last_event() -> RawEvent<u128, u64, u64> {
 	System::events().into_iter().map(|r| r.event)
 		.filter_map(|e| {
 			if let TestEvent::chainlink(inner) = e { Some(inner) } else { None }
@@ -117,16 +172,39 @@ pub fn last_event() -> RawEvent<u128, u64> {
 pub mod module2 {
 	use super::*;
 
-	pub trait Trait: frame_system::Trait {}
+	pub trait Trait: frame_system::Trait {
+		type ResponseOrigin: EnsureOrigin<Self::Origin, Success = (RequestIdentifier, Self::AccountId, Self::AccountId)>;
+	}
 
 	frame_support::decl_module! {
 		pub struct Module<T: Trait> for enum Call
 			where origin: <T as frame_system::Trait>::Origin
 		{
 			#[weight = 0]
-			pub fn callback(_origin, result: Vec<u8>) -> frame_support::dispatch::DispatchResult {
+			pub fn callback(origin, result: Vec<u8>) -> frame_support::dispatch::DispatchResult {
+				let (request_id, _, _) = T::ResponseOrigin::ensure_origin(origin)?;
 				let r : u128 = u128::decode(&mut &result[..]).map_err(|err| err.what())?;
 				<Result>::put(r);
+				<LastRequest>::put(request_id);
+				Ok(())
+			}
+
+			#[weight = 100]
+			pub fn failing_callback(_origin, result: Vec<u8>) -> frame_support::dispatch::DispatchResult {
+				<Result>::put(result.len() as u128);
+				Err("callback failed".into())
+			}
+
+			#[weight = 10_000]
+			pub fn heavy_callback(_origin, _result: Vec<u8>) -> frame_support::dispatch::DispatchResult {
+				Ok(())
+			}
+
+			#[weight = 0]
+			pub fn typed_callback(origin, result: i128) -> frame_support::dispatch::DispatchResult {
+				let (request_id, _, _) = T::ResponseOrigin::ensure_origin(origin)?;
+				<Result>::put(result as u128);
+				<LastRequest>::put(request_id);
 				Ok(())
 			}
 		}
@@ -135,6 +213,7 @@ pub mod module2 {
 	frame_support::decl_storage! {
 		trait Store for Module<T: Trait> as TestStorage {
 			pub Result: u128;
+			pub LastRequest: RequestIdentifier;
 		}
 	}
 
@@ -142,6 +221,9 @@ pub mod module2 {
 		fn with_result(&self, result: Vec<u8>) -> Option<Self> {
 			match *self {
 				Call::callback(_) => Some(Call::callback(result)),
+				Call::failing_callback(_) => Some(Call::failing_callback(result)),
+				Call::heavy_callback(_) => Some(Call::heavy_callback(result)),
+				Call::typed_callback(_) => encoding::typed_callback(&result, Call::typed_callback),
 				_ => None
 			}
 		}
@@ -159,6 +241,9 @@ fn operators_can_be_registered() {
 		assert!(<Chainlink>::operator(1));
 		assert!(<Chainlink>::unregister_operator(Origin::signed(1)).is_ok());
 		assert!(!<Chainlink>::operator(1));
+		assert_eq!(last_event(),RawEvent::OperatorUnregistering(1, 6));
+		System::set_block_number(6);
+		assert!(<Chainlink>::withdraw_deposit(Origin::signed(1)).is_ok());
 		assert_eq!(last_event(),RawEvent::OperatorUnregistered(1));
 	});
 
@@ -173,7 +258,7 @@ fn operators_can_be_registered() {
 fn initiate_requests() {
 
 	new_test_ext().execute_with(|| {
-		assert!(<Chainlink>::register_operator(Origin::signed(1)).is_ok());
+		register_operator(1);
 		assert!(<Chainlink>::initiate_request(Origin::signed(2), 1, vec![], 1, vec![], 0, module2::Call::<Test>::callback(vec![]).into()).is_err());
 	});
 
@@ -182,7 +267,7 @@ fn initiate_requests() {
 	});
 
 	new_test_ext().execute_with(|| {
-		assert!(<Chainlink>::register_operator(Origin::signed(1)).is_ok());
+		register_operator(1);
 		assert!(<Chainlink>::initiate_request(Origin::signed(2), 1, vec![], 1, vec![], 2, module2::Call::<Test>::callback(vec![]).into()).is_ok());
 		assert!(<Chainlink>::callback(Origin::signed(3), 0, 10.encode()).is_err());
 	});
@@ -195,6 +280,7 @@ fn initiate_requests() {
 		System::set_block_number(1);
 		assert!(<Chainlink>::register_operator(Origin::signed(1)).is_ok());
 		assert_eq!(last_event(),RawEvent::OperatorRegistered(1));
+		assert!(<Chainlink>::set_operator_info(Origin::signed(1), operator_info()).is_ok());
 
 		let parameters = ("a", "b");
 		let data = parameters.encode();
@@ -207,6 +293,11 @@ fn initiate_requests() {
 		let result = 10;
 		assert!(<Chainlink>::callback(Origin::signed(1), 0, result.encode()).is_ok());
 		assert_eq!(module2::Result::get(), result);
+
+		// The fee has been paid to the operator and the request can't be answered twice
+		assert_eq!(pallet_balances::Module::<Test>::free_balance(1), 100002);
+		assert_eq!(pallet_balances::Module::<Test>::total_balance(&2), 99998);
+		assert!(<Chainlink>::callback(Origin::signed(1), 0, result.encode()).is_err());
 	});
 
 }
@@ -215,12 +306,606 @@ fn initiate_requests() {
 pub fn on_finalize() {
 
 	new_test_ext().execute_with(|| {
-		assert!(<Chainlink>::register_operator(Origin::signed(1)).is_ok());
+		System::set_block_number(1);
+		register_operator(1);
 		assert!(<Chainlink>::initiate_request(Origin::signed(2), 1, vec![], 1, vec![], 2, module2::Call::<Test>::callback(vec![]).into()).is_ok());
-		<Chainlink as OnFinalize<u64>>::on_finalize(20);
+		assert_eq!(pallet_balances::Module::<Test>::reserved_balance(2), 2);
+		// The request expires in block 1 + ValidityPeriod + 1
+		<Chainlink as OnFinalize<u64>>::on_finalize(11);
+		assert!(Requests::<Test>::contains_key(0));
+		<Chainlink as OnFinalize<u64>>::on_finalize(12);
 		// Request has been killed, too old
 		assert!(<Chainlink>::callback(Origin::signed(1), 0, 10.encode()).is_err());
+		assert!(System::events().into_iter().any(|r| r.event == TestEvent::chainlink(RawEvent::KillRequest(0))));
+		// The fee has been refunded to the requester
+		assert_eq!(last_event(),RawEvent::RequestRefunded(0, 2, 2));
+		assert_eq!(pallet_balances::Module::<Test>::reserved_balance(2), 0);
+		assert_eq!(pallet_balances::Module::<Test>::free_balance(2), 100000);
+	});
+
+}
+
+#[test]
+fn event_indices() {
+	// New events are appended, the existing ones keep their index and encoding
+	assert_eq!(RawEvent::<u128, u64, u64>::OperatorRegistered(1).encode(), (2u8, 1u128).encode());
+	assert_eq!(RawEvent::<u128, u64, u64>::OperatorUnregistered(1).encode(), (3u8, 1u128).encode());
+	assert_eq!(RawEvent::<u128, u64, u64>::KillRequest(0).encode(), (4u8, 0u64).encode());
+}
+
+#[test]
+fn cancel_request() {
+
+	new_test_ext().execute_with(|| {
+		System::set_block_number(1);
+		register_operator(1);
+		assert!(<Chainlink>::initiate_request(Origin::signed(2), 1, vec![], 1, vec![], 2, module2::Call::<Test>::callback(vec![]).into()).is_ok());
+
+		// Still within its validity period
+		System::set_block_number(10);
+		assert_eq!(<Chainlink>::cancel_request(Origin::signed(2), 0), Err(Error::<Test>::CancellationTooEarly.into()));
+
+		// Cancellable once its validity period passed, before the request expires in `on_finalize`
+		System::set_block_number(11);
+		let deposit = OperatorDeposits::<Test>::get(1);
+		// Only the requester can cancel
+		assert_eq!(<Chainlink>::cancel_request(Origin::signed(1), 0), Err(Error::<Test>::WrongRequester.into()));
+		assert!(<Chainlink>::cancel_request(Origin::signed(2), 0).is_ok());
+		assert_eq!(last_event(),RawEvent::CancelRequest(0, 2, 2));
+		assert_eq!(pallet_balances::Module::<Test>::reserved_balance(2), 0);
+		assert_eq!(pallet_balances::Module::<Test>::free_balance(2), 100000);
+		// The request did not expire yet, the operator is not penalized
+		assert_eq!(OperatorDeposits::<Test>::get(1), deposit);
+		assert_eq!(PendingRequestCounts::<Test>::get(1), 0);
+
+		// The request is gone
+		assert_eq!(<Chainlink>::cancel_request(Origin::signed(2), 0), Err(Error::<Test>::UnknownRequest.into()));
+		assert!(<Chainlink>::callback(Origin::signed(1), 0, 10.encode()).is_err());
+	});
+
+}
+
+#[test]
+fn expiry_queue() {
+
+	new_test_ext().execute_with(|| {
+		System::set_block_number(1);
+		register_operator(1);
+		assert!(<Chainlink>::initiate_request(Origin::signed(2), 1, vec![], 1, vec![], 2, module2::Call::<Test>::callback(vec![]).into()).is_ok());
+		assert!(<Chainlink>::initiate_request(Origin::signed(2), 1, vec![], 1, vec![], 3, module2::Call::<Test>::callback(vec![]).into()).is_ok());
+		System::set_block_number(2);
+		assert!(<Chainlink>::initiate_request(Origin::signed(2), 1, vec![], 1, vec![], 4, module2::Call::<Test>::callback(vec![]).into()).is_ok());
+		assert_eq!(<Chainlink>::expiries(12), vec![0, 1]);
+		assert_eq!(<Chainlink>::expiries(13), vec![2]);
+
+		// Nothing expires in this block
+		assert_eq!(<Chainlink as OnInitialize<u64>>::on_initialize(11), RocksDbWeight::get().reads_writes(2, 1));
+
+		// Answered requests are skipped on expiry
+		assert!(<Chainlink>::callback(Origin::signed(1), 1, 10.encode()).is_ok());
+		assert_eq!(<Chainlink as OnInitialize<u64>>::on_initialize(12), RocksDbWeight::get().reads_writes(24, 23));
+		<Chainlink as OnFinalize<u64>>::on_finalize(12);
+		assert_eq!(last_event(),RawEvent::RequestRefunded(0, 2, 2));
+		assert!(<Chainlink>::expiries(12).is_empty());
+		assert!(!Requests::<Test>::contains_key(0));
+		assert!(Requests::<Test>::contains_key(2));
+		assert_eq!(pallet_balances::Module::<Test>::reserved_balance(2), 4);
+
+		<Chainlink as OnFinalize<u64>>::on_finalize(13);
+		assert_eq!(last_event(),RawEvent::RequestRefunded(2, 2, 4));
+		assert!(!Requests::<Test>::contains_key(2));
+		assert_eq!(pallet_balances::Module::<Test>::reserved_balance(2), 0);
+	});
+
+}
+
+#[test]
+fn pending_requests() {
+
+	new_test_ext().execute_with(|| {
+		System::set_block_number(1);
+		register_operator(1);
+		register_operator(3);
+		let data = ("a", "b").encode();
+		assert!(<Chainlink>::initiate_request(Origin::signed(2), 1, vec![1], 1, data.clone(), 2, module2::Call::<Test>::callback(vec![]).into()).is_ok());
+		assert!(<Chainlink>::initiate_request(Origin::signed(2), 3, vec![2], 1, vec![], 3, module2::Call::<Test>::callback(vec![]).into()).is_ok());
+		assert!(<Chainlink>::initiate_request(Origin::signed(0), 1, vec![3], 1, vec![], 4, module2::Call::<Test>::callback(vec![]).into()).is_ok());
+
+		// The request details are stored
+		let request = Request {
+			operator: 1,
+			requester: 2,
+			spec_index: vec![1],
+			data_version: 1,
+			data,
+			callback: module2::Call::<Test>::callback(vec![]),
+			requested_at: 1,
+			fee: 2,
+		};
+		assert_eq!(<Chainlink>::request(0), Some(request.clone()));
+
+		let mut pending = <Chainlink>::pending_requests(1);
+		pending.sort_by_key(|(request_id, _)| *request_id);
+		assert_eq!(pending.iter().map(|(request_id, _)| *request_id).collect::<Vec<_>>(), vec![0, 2]);
+		assert_eq!(pending[0].1, request);
+		assert_eq!(<Chainlink>::pending_requests(3).len(), 1);
+
+		// Answered requests are not pending anymore
+		assert!(<Chainlink>::callback(Origin::signed(1), 0, 10u128.encode()).is_ok());
+		assert_eq!(<Chainlink>::pending_requests(1).into_iter().map(|(request_id, _)| request_id).collect::<Vec<_>>(), vec![2]);
+		assert!(<Chainlink>::pending_requests(2).is_empty());
+	});
+
+}
+
+#[test]
+fn migrate_requests_to_v2() {
+
+	new_test_ext().execute_with(|| {
+		System::set_block_number(15);
+		let callback = module2::Call::<Test>::callback(vec![]);
+		let put = |request_id: RequestIdentifier, value: Vec<u8>| {
+			frame_support::storage::unhashed::put_raw(&Requests::<Test>::hashed_key_for(request_id), &value);
+		};
+		put(0, (1u128, vec![callback.clone()], 10u64, 2u64).encode());
+		put(1, (1u128, vec![callback.clone()], 1u64, 3u64).encode());
+		// A chain started before the layout was tracked
+		StorageVersion::kill();
+		assert_eq!(<Chainlink>::storage_version(), Releases::V1);
+
+		// The requests were stored without their requester, they are dropped (followed by the migration to `V3`)
+		assert_eq!(<Chainlink as OnRuntimeUpgrade>::on_runtime_upgrade(), RocksDbWeight::get().reads_writes(8, 4));
+		assert_eq!(<Chainlink>::storage_version(), Releases::V3);
+		assert_eq!(<Chainlink>::request(0), None);
+		assert_eq!(<Chainlink>::request(1), None);
+
+		// Only migrated once
+		assert_eq!(<Chainlink as OnRuntimeUpgrade>::on_runtime_upgrade(), RocksDbWeight::get().reads(1));
+	});
+
+}
+
+#[test]
+fn genesis_storage_is_not_migrated() {
+
+	new_test_ext().execute_with(|| {
+		System::set_block_number(1);
+		OPERATOR_DEPOSIT.with(|deposit| deposit.set(100));
+		register_operator(1);
+		assert!(<Chainlink>::initiate_request(Origin::signed(2), 1, vec![], 1, vec![], 2, module2::Call::<Test>::callback(vec![]).into()).is_ok());
+		assert_eq!(<Chainlink>::storage_version(), Releases::V3);
+
+		// New chains start with the current layout, their requests and reserves are left alone
+		assert_eq!(<Chainlink as OnRuntimeUpgrade>::on_runtime_upgrade(), RocksDbWeight::get().reads(1));
+		assert!(<Chainlink>::request(0).is_some());
+		assert_eq!(<Chainlink>::pending_request_count(1), 1);
+		assert_eq!(<Chainlink>::operator_deposit(1), 100);
+		assert_eq!(pallet_balances::Module::<Test>::reserved_balance(1), 100);
+		assert_eq!(pallet_balances::Module::<Test>::reserved_balance(2), 2);
+	});
+
+}
+
+#[test]
+fn migrate_operators_to_v3() {
+
+	new_test_ext().execute_with(|| {
+		System::set_block_number(1);
+		for operator in &[1, 3, 9] {
+			register_operator(*operator);
+		}
+		assert!(<Chainlink>::initiate_request(Origin::signed(2), 1, vec![], 1, vec![], 2, module2::Call::<Test>::callback(vec![]).into()).is_ok());
+		assert!(initiate_aggregated_request(vec![1, 3], 2).is_ok());
+		assert!(<Chainlink>::callback(Origin::signed(3), 1, 10i128.encode()).is_ok());
+
+		// The state before deposits were required and pending requests were counted
+		StorageVersion::put(Releases::V2);
+		for operator in &[1, 3, 9] {
+			OperatorDeposits::<Test>::remove(operator);
+			PendingRequestCounts::<Test>::remove(operator);
+		}
+		OPERATOR_DEPOSIT.with(|deposit| deposit.set(100));
+
+		<Chainlink as OnRuntimeUpgrade>::on_runtime_upgrade();
+		assert_eq!(<Chainlink>::storage_version(), Releases::V3);
+		assert_eq!(<Chainlink>::pending_request_count(1), 2);
+		assert_eq!(<Chainlink>::pending_request_count(3), 0);
+		assert_eq!(<Chainlink>::pending_request_count(9), 0);
+
+		// Operators that can afford the deposit reserve it
+		assert_eq!(<Chainlink>::operator_deposit(1), 100);
+		assert_eq!(pallet_balances::Module::<Test>::reserved_balance(1), 100);
+		assert!(<Chainlink>::operator(1));
+
+		// The others are unregistered
+		assert!(!<Chainlink>::operator(9));
+		assert_eq!(<Chainlink>::unregistration(9), Some(6));
+		assert_eq!(last_event(), RawEvent::OperatorUnregistering(9, 6));
+	});
+
+}
+
+#[test]
+fn migrate_to_v3_keeps_reserved_deposits() {
+
+	new_test_ext().execute_with(|| {
+		System::set_block_number(1);
+		OPERATOR_DEPOSIT.with(|deposit| deposit.set(100));
+		register_operator(1);
+		assert!(<Chainlink>::initiate_request(Origin::signed(2), 1, vec![], 1, vec![], 2, module2::Call::<Test>::callback(vec![]).into()).is_ok());
+
+		// Storage with reserved deposits is already in the `V3` layout, only the version is updated
+		StorageVersion::put(Releases::V2);
+		assert_eq!(<Chainlink as OnRuntimeUpgrade>::on_runtime_upgrade(), RocksDbWeight::get().reads_writes(2, 1));
+		assert_eq!(<Chainlink>::storage_version(), Releases::V3);
+		assert_eq!(<Chainlink>::pending_request_count(1), 1);
+		assert_eq!(<Chainlink>::operator_deposit(1), 100);
+		assert_eq!(pallet_balances::Module::<Test>::reserved_balance(1), 100);
+		assert!(<Chainlink>::operator(1));
 	});
 
 }
 
+#[test]
+fn callback_failures() {
+
+	new_test_ext().execute_with(|| {
+		System::set_block_number(1);
+		register_operator(1);
+
+		// Callbacks heavier than `MaxCallbackWeight` are rejected
+		assert_eq!(<Chainlink>::initiate_request(Origin::signed(2), 1, vec![], 1, vec![], 2, module2::Call::<Test>::heavy_callback(vec![]).into()), Err(Error::<Test>::CallbackTooHeavy.into()));
+
+		// A successful callback is charged its weight
+		assert!(<Chainlink>::initiate_request(Origin::signed(2), 1, vec![], 1, vec![], 2, module2::Call::<Test>::callback(vec![]).into()).is_ok());
+		assert_eq!(<Chainlink>::callback(Origin::signed(1), 0, 10u128.encode()).unwrap().actual_weight, Some(CALLBACK_BASE_WEIGHT));
+
+		// A failing callback is reported and reverted, the operator is still paid
+		assert!(<Chainlink>::initiate_request(Origin::signed(2), 1, vec![], 1, vec![], 3, module2::Call::<Test>::failing_callback(vec![]).into()).is_ok());
+		assert_eq!(<Chainlink>::callback(Origin::signed(1), 1, vec![1, 2, 3]).unwrap().actual_weight, Some(CALLBACK_BASE_WEIGHT + 100));
+		assert!(System::events().iter().any(|r| r.event == TestEvent::chainlink(RawEvent::CallbackFailed(1, "callback failed".into()))));
+		assert_eq!(last_event(), RawEvent::OracleAnswer(1, 1, 1, vec![1, 2, 3], 3));
+		assert_eq!(module2::Result::get(), 10);
+		assert_eq!(pallet_balances::Module::<Test>::free_balance(1), 100005);
+		assert!(!Requests::<Test>::contains_key(1));
+
+		// Only the part of the fee that is still reserved can be paid, the answer reports the amount actually paid
+		assert!(<Chainlink>::initiate_request(Origin::signed(2), 1, vec![], 1, vec![], 3, module2::Call::<Test>::callback(vec![]).into()).is_ok());
+		pallet_balances::Module::<Test>::unreserve(&2, 1);
+		assert!(<Chainlink>::callback(Origin::signed(1), 2, 10u128.encode()).is_ok());
+		assert_eq!(last_event(), RawEvent::OracleAnswer(1, 2, 1, 10u128.encode(), 2));
+		assert_eq!(pallet_balances::Module::<Test>::free_balance(1), 100007);
+		assert_eq!(pallet_balances::Module::<Test>::reserved_balance(2), 0);
+	});
+
+	new_test_ext().execute_with(|| {
+		System::set_block_number(1);
+		CALLBACK_FAILURE_POLICY.with(|policy| policy.set(CallbackFailurePolicy::RefundRequester));
+		register_operator(1);
+
+		// The requester is refunded when the callback fails
+		assert!(<Chainlink>::initiate_request(Origin::signed(2), 1, vec![], 1, vec![], 3, module2::Call::<Test>::failing_callback(vec![]).into()).is_ok());
+		assert!(<Chainlink>::callback(Origin::signed(1), 0, vec![1]).is_ok());
+		assert_eq!(last_event(), RawEvent::OracleAnswer(1, 0, 1, vec![1], 0));
+		assert_eq!(pallet_balances::Module::<Test>::free_balance(1), 100000);
+		assert_eq!(pallet_balances::Module::<Test>::free_balance(2), 100000);
+		assert_eq!(pallet_balances::Module::<Test>::reserved_balance(2), 0);
+
+		// Successful callbacks are paid regardless
+		assert!(<Chainlink>::initiate_request(Origin::signed(2), 1, vec![], 1, vec![], 2, module2::Call::<Test>::callback(vec![]).into()).is_ok());
+		assert!(<Chainlink>::callback(Origin::signed(1), 1, 10u128.encode()).is_ok());
+		assert_eq!(pallet_balances::Module::<Test>::free_balance(1), 100002);
+		CALLBACK_FAILURE_POLICY.with(|policy| policy.set(CallbackFailurePolicy::PayOperator));
+	});
+
+}
+
+#[test]
+fn callbacks_are_dispatched_with_the_response_origin() {
+
+	new_test_ext().execute_with(|| {
+		System::set_block_number(1);
+		register_operator(1);
+		assert!(<Chainlink>::initiate_request(Origin::signed(2), 1, vec![], 1, vec![], 2, module2::Call::<Test>::callback(vec![]).into()).is_ok());
+		assert!(<Chainlink>::initiate_request(Origin::signed(2), 1, vec![], 1, vec![], 2, module2::Call::<Test>::callback(vec![]).into()).is_ok());
+		assert!(<Chainlink>::callback(Origin::signed(1), 1, 10u128.encode()).is_ok());
+		assert_eq!(module2::Result::get(), 10);
+		assert_eq!(module2::LastRequest::get(), 1);
+
+		// Callbacks can't be called with any other origin
+		let callback = module2::Call::<Test>::callback(20u128.encode());
+		assert!(callback.clone().dispatch_bypass_filter(Origin::root()).is_err());
+		assert!(callback.dispatch_bypass_filter(Origin::signed(1)).is_err());
+		assert_eq!(module2::Result::get(), 10);
+
+		let response = RawOrigin::ChainlinkResponse { request_id: 3, operator: 1, requester: 2 };
+		assert_eq!(ensure_chainlink_response(Origin::from(response)), Ok((3, 1, 2)));
+		assert_eq!(ensure_chainlink_response::<_, u128>(Origin::signed(2)), Err(BadOrigin));
+	});
+
+}
+
+fn initiate_aggregated_request(operators: Vec<u128>, quorum: u32) -> frame_support::dispatch::DispatchResult {
+	// The results of data version 1 are typed, so is the median
+	<Chainlink>::initiate_aggregated_request(Origin::signed(0), operators, quorum, vec![], 1, vec![], 2, module2::Call::<Test>::typed_callback(0).into())
+}
+
+#[test]
+fn initiate_aggregated_requests() {
+
+	new_test_ext().execute_with(|| {
+		System::set_block_number(1);
+		for operator in 1..=4 {
+			register_operator(operator);
+		}
+
+		assert_eq!(initiate_aggregated_request(vec![1, 2, 3, 4], 2), Err(Error::<Test>::TooManyOperators.into()));
+		assert_eq!(initiate_aggregated_request(vec![1, 2], 0), Err(Error::<Test>::InvalidQuorum.into()));
+		assert_eq!(initiate_aggregated_request(vec![1, 2], 3), Err(Error::<Test>::InvalidQuorum.into()));
+		assert_eq!(initiate_aggregated_request(vec![1, 2, 1], 2), Err(Error::<Test>::DuplicateOperator.into()));
+		assert_eq!(initiate_aggregated_request(vec![1, 5], 2), Err(Error::<Test>::UnknownOperator.into()));
+
+		assert!(initiate_aggregated_request(vec![1, 2, 3], 2).is_ok());
+		// Each operator is notified and paid
+		assert_eq!(last_event(), RawEvent::OracleRequest(3, vec![], 0, 0, 1, vec![], "Chainlink.callback".into(), 2));
+		assert_eq!(pallet_balances::Module::<Test>::reserved_balance(0), 6);
+		assert_eq!(<Chainlink>::expiries(12), vec![0]);
+		assert_eq!(<Chainlink>::pending_requests(2).len(), 1);
+	});
+
+}
+
+#[test]
+fn aggregated_requests_are_answered_at_quorum() {
+
+	new_test_ext().execute_with(|| {
+		System::set_block_number(1);
+		for operator in 1..=4 {
+			register_operator(operator);
+		}
+		assert!(initiate_aggregated_request(vec![1, 2, 3], 2).is_ok());
+
+		assert_eq!(<Chainlink>::callback(Origin::signed(4), 0, 10i128.encode()).map_err(|e| e.error), Err(Error::<Test>::WrongOperator.into()));
+		assert_eq!(<Chainlink>::callback(Origin::signed(1), 0, vec![1]).map_err(|e| e.error), Err(Error::<Test>::NonNumericResult.into()));
+
+		assert!(<Chainlink>::callback(Origin::signed(1), 0, 10i128.encode()).is_ok());
+		assert_eq!(last_event(), RawEvent::OraclePartialAnswer(1, 0, 10));
+		assert_eq!(<Chainlink>::callback(Origin::signed(1), 0, 10i128.encode()).map_err(|e| e.error), Err(Error::<Test>::AlreadyAnswered.into()));
+		// Answered operators don't see the request as pending anymore
+		assert!(<Chainlink>::pending_requests(1).is_empty());
+		assert_eq!(module2::Result::get(), 0);
+
+		// The median is dispatched once the quorum is reached
+		assert!(<Chainlink>::callback(Origin::signed(3), 0, 15i128.encode()).is_ok());
+		assert_eq!(last_event(), RawEvent::OracleAggregatedAnswer(0, 12, 4));
+		assert_eq!(module2::Result::get(), 12);
+		assert_eq!(module2::LastRequest::get(), 0);
+
+		// The answering operators are paid, the other fee is refunded
+		assert_eq!(pallet_balances::Module::<Test>::free_balance(1), 100002);
+		assert_eq!(pallet_balances::Module::<Test>::free_balance(2), 100000);
+		assert_eq!(pallet_balances::Module::<Test>::free_balance(3), 100002);
+		assert_eq!(pallet_balances::Module::<Test>::reserved_balance(0), 0);
+		assert_eq!(pallet_balances::Module::<Test>::free_balance(0), 99996);
+
+		// Late results are rejected
+		assert_eq!(<Chainlink>::callback(Origin::signed(2), 0, 10i128.encode()).map_err(|e| e.error), Err(Error::<Test>::UnknownRequest.into()));
+		assert!(!AggregatedRequests::<Test>::contains_key(0));
+	});
+
+}
+
+#[test]
+fn expired_aggregated_requests_pay_partial_results() {
+
+	new_test_ext().execute_with(|| {
+		System::set_block_number(1);
+		for operator in 1..=3 {
+			register_operator(operator);
+		}
+		assert!(initiate_aggregated_request(vec![1, 2, 3], 3).is_ok());
+		assert!(<Chainlink>::callback(Origin::signed(2), 0, 10i128.encode()).is_ok());
+
+		<Chainlink as OnFinalize<u64>>::on_finalize(12);
+		assert_eq!(last_event(), RawEvent::RequestRefunded(0, 0, 4));
+		assert_eq!(pallet_balances::Module::<Test>::free_balance(2), 100002);
+		assert_eq!(pallet_balances::Module::<Test>::free_balance(0), 99998);
+		assert_eq!(pallet_balances::Module::<Test>::reserved_balance(0), 0);
+		assert!(!AggregatedRequests::<Test>::contains_key(0));
+		assert_eq!(module2::Result::get(), 0);
+	});
+
+	new_test_ext().execute_with(|| {
+		System::set_block_number(1);
+		for operator in 1..=3 {
+			register_operator(operator);
+		}
+		assert!(initiate_aggregated_request(vec![1, 2, 3], 3).is_ok());
+
+		// Expired aggregated requests can be cancelled too
+		System::set_block_number(12);
+		assert!(<Chainlink>::cancel_request(Origin::signed(0), 0).is_ok());
+		assert_eq!(last_event(), RawEvent::CancelRequest(0, 0, 6));
+		assert_eq!(pallet_balances::Module::<Test>::free_balance(0), 100000);
+	});
+
+}
+
+#[test]
+fn median() {
+	assert_eq!(super::median(vec![3]), 3);
+	assert_eq!(super::median(vec![5, 1, 3]), 3);
+	assert_eq!(super::median(vec![4, 1, 3, 2]), 2);
+	assert_eq!(super::median(vec![-1, -2]), -1);
+	assert_eq!(super::median(vec![i128::max_value(), i128::max_value() - 2]), i128::max_value() - 1);
+}
+
+#[test]
+fn operators_announce_their_metadata() {
+
+	new_test_ext().execute_with(|| {
+		System::set_block_number(1);
+		let callback = || module2::Call::<Test>::callback(vec![]);
+		assert_eq!(<Chainlink>::set_operator_info(Origin::signed(1), operator_info()), Err(Error::<Test>::UnknownOperator.into()));
+
+		// Requests are rejected until the operator announced its metadata
+		assert!(<Chainlink>::register_operator(Origin::signed(1)).is_ok());
+		assert_eq!(<Chainlink>::initiate_request(Origin::signed(2), 1, vec![], 1, vec![], 2, callback()), Err(Error::<Test>::UnsupportedSpec.into()));
+
+		let info = OperatorInfo { specs: vec![(vec![1], 5), (vec![2], 0)], data_version: 2, endpoint: b"endpoint".to_vec() };
+		assert!(<Chainlink>::set_operator_info(Origin::signed(1), info.clone()).is_ok());
+		assert_eq!(last_event(), RawEvent::OperatorInfoUpdated(1));
+		assert_eq!(<Chainlink>::operator_info(1), Some(info));
+
+		// The metadata is bounded
+		let too_many_specs = OperatorInfo { specs: (0..5).map(|i| (vec![i], 1)).collect(), ..operator_info() };
+		assert_eq!(<Chainlink>::set_operator_info(Origin::signed(1), too_many_specs), Err(Error::<Test>::TooManySpecs.into()));
+		let long_endpoint = OperatorInfo { endpoint: vec![b'a'; 33], ..operator_info() };
+		assert_eq!(<Chainlink>::set_operator_info(Origin::signed(1), long_endpoint), Err(Error::<Test>::OperatorInfoTooLong.into()));
+		let long_spec = OperatorInfo { specs: vec![(vec![1; 33], 1)], ..operator_info() };
+		assert_eq!(<Chainlink>::set_operator_info(Origin::signed(1), long_spec), Err(Error::<Test>::OperatorInfoTooLong.into()));
+		assert_eq!(<Chainlink>::operator_info(1), Some(OperatorInfo { specs: vec![(vec![1], 5), (vec![2], 0)], data_version: 2, endpoint: b"endpoint".to_vec() }));
+
+		assert_eq!(<Chainlink>::initiate_request(Origin::signed(2), 1, vec![3], 2, vec![], 5, callback()), Err(Error::<Test>::UnsupportedSpec.into()));
+		assert_eq!(<Chainlink>::initiate_request(Origin::signed(2), 1, vec![1], 1, vec![], 5, callback()), Err(Error::<Test>::UnsupportedDataVersion.into()));
+		assert_eq!(<Chainlink>::initiate_request(Origin::signed(2), 1, vec![1], 2, vec![], 4, callback()), Err(Error::<Test>::FeeBelowMinimum.into()));
+		assert!(<Chainlink>::initiate_request(Origin::signed(2), 1, vec![1], 2, vec![], 5, callback()).is_ok());
+		assert!(<Chainlink>::initiate_request(Origin::signed(2), 1, vec![2], 2, vec![], 0, callback()).is_ok());
+
+		// Aggregated requests are checked against the metadata of each operator
+		register_operator(3);
+		assert_eq!(<Chainlink>::initiate_aggregated_request(Origin::signed(2), vec![1, 3], 1, vec![1], 2, vec![], 5, callback()), Err(Error::<Test>::UnsupportedDataVersion.into()));
+
+		// The metadata is removed with the operator
+		assert!(<Chainlink>::unregister_operator(Origin::signed(1)).is_ok());
+		assert_eq!(<Chainlink>::operator_info(1), None);
+	});
+
+}
+
+#[test]
+fn operator_deposits() {
+
+	new_test_ext().execute_with(|| {
+		System::set_block_number(1);
+		OPERATOR_DEPOSIT.with(|deposit| deposit.set(100));
+		let callback = || module2::Call::<Test>::callback(vec![]);
+
+		// The deposit is reserved at registration
+		register_operator(1);
+		assert_eq!(<Chainlink>::operator_deposit(1), 100);
+		assert_eq!(pallet_balances::Module::<Test>::reserved_balance(1), 100);
+
+		assert!(<Chainlink>::initiate_request(Origin::signed(2), 1, vec![], 1, vec![], 2, callback()).is_ok());
+		assert!(<Chainlink>::initiate_request(Origin::signed(2), 1, vec![], 1, vec![], 2, callback()).is_ok());
+		assert_eq!(<Chainlink>::pending_request_count(1), 2);
+
+		// Unregistered operators receive no new requests but must still answer the pending ones
+		System::set_block_number(2);
+		assert!(<Chainlink>::unregister_operator(Origin::signed(1)).is_ok());
+		assert_eq!(last_event(), RawEvent::OperatorUnregistering(1, 7));
+		assert_eq!(<Chainlink>::register_operator(Origin::signed(1)), Err(Error::<Test>::OperatorUnregistering.into()));
+		assert_eq!(<Chainlink>::initiate_request(Origin::signed(2), 1, vec![], 1, vec![], 2, callback()), Err(Error::<Test>::UnknownOperator.into()));
+		assert_eq!(<Chainlink>::withdraw_deposit(Origin::signed(1)), Err(Error::<Test>::UnregistrationDelayed.into()));
+		assert!(<Chainlink>::callback(Origin::signed(1), 0, 10u128.encode()).is_ok());
+
+		System::set_block_number(7);
+		assert_eq!(<Chainlink>::withdraw_deposit(Origin::signed(1)), Err(Error::<Test>::PendingRequests.into()));
+
+		// The unanswered request expires and part of the deposit is slashed
+		<Chainlink as OnFinalize<u64>>::on_finalize(12);
+		assert!(System::events().iter().any(|r| r.event == TestEvent::chainlink(RawEvent::OperatorPenalized(1, 1, 30))));
+		assert_eq!(last_event(), RawEvent::RequestRefunded(1, 2, 2));
+		assert_eq!(<Chainlink>::operator_deposit(1), 70);
+		assert_eq!(pallet_balances::Module::<Test>::reserved_balance(1), 70);
+
+		// The rest of the deposit is returned
+		assert!(<Chainlink>::withdraw_deposit(Origin::signed(1)).is_ok());
+		assert_eq!(last_event(), RawEvent::OperatorUnregistered(1));
+		assert_eq!(pallet_balances::Module::<Test>::reserved_balance(1), 0);
+		assert_eq!(pallet_balances::Module::<Test>::free_balance(1), 100000 + 2 - 30);
+		assert_eq!(<Chainlink>::withdraw_deposit(Origin::signed(1)), Err(Error::<Test>::NotUnregistering.into()));
+	});
+
+	new_test_ext().execute_with(|| {
+		System::set_block_number(1);
+		OPERATOR_DEPOSIT.with(|deposit| deposit.set(40));
+		register_operator(1);
+		register_operator(3);
+		assert!(initiate_aggregated_request(vec![1, 3], 1).is_ok());
+		assert!(initiate_aggregated_request(vec![1, 3], 2).is_ok());
+		assert!(<Chainlink>::callback(Origin::signed(1), 0, 10i128.encode()).is_ok());
+		assert!(<Chainlink>::callback(Origin::signed(1), 1, 10i128.encode()).is_ok());
+
+		// Operators without a result to an expiring aggregated request are penalized, up to their deposit
+		<Chainlink as OnFinalize<u64>>::on_finalize(12);
+		assert_eq!(<Chainlink>::operator_deposit(1), 40);
+		assert_eq!(<Chainlink>::operator_deposit(3), 10);
+		assert!(initiate_aggregated_request(vec![3], 1).is_ok());
+		<Chainlink as OnFinalize<u64>>::on_finalize(12);
+		assert_eq!(<Chainlink>::operator_deposit(3), 0);
+		assert_eq!(pallet_balances::Module::<Test>::reserved_balance(3), 0);
+		assert_eq!(<Chainlink>::pending_request_count(1), 0);
+		assert_eq!(<Chainlink>::pending_request_count(3), 0);
+	});
+
+}
+
+#[test]
+fn data_formats() {
+	use encoding::*;
+
+	for format in vec![DataFormat::Opaque, DataFormat::ChainlinkCbor, DataFormat::Scale] {
+		assert_eq!(DataFormat::from_version(format.version()), Some(format));
+	}
+	assert_eq!(DataFormat::from_version(3), None);
+	assert!(!DataFormat::Opaque.has_typed_results());
+	assert!(DataFormat::ChainlinkCbor.has_typed_results());
+}
+
+#[test]
+fn typed_results() {
+	use encoding::*;
+
+	let int = TypedResult::Int(-5).encode();
+	assert_eq!(decode_result::<i128>(&int), Some(-5));
+	assert_eq!(decode_result::<u128>(&int), None);
+	assert_eq!(decode_result::<FixedPoint>(&int), Some(FixedPoint { value: -5, decimals: 0 }));
+	assert_eq!(decode_result::<u64>(&TypedResult::UInt(5).encode()), Some(5));
+	assert_eq!(decode_result::<u32>(&TypedResult::UInt(u128::max_value()).encode()), None);
+
+	let fixed = FixedPoint { value: 314, decimals: 2 };
+	assert_eq!(decode_result::<FixedPoint>(&TypedResult::Fixed(fixed).encode()), Some(fixed));
+	assert_eq!(decode_result::<i128>(&TypedResult::Fixed(fixed).encode()), None);
+	assert_eq!(decode_result::<Vec<u8>>(&TypedResult::Bytes(vec![1, 2]).encode()), Some(vec![1, 2]));
+	assert_eq!(decode_result::<bool>(&TypedResult::Bool(true).encode()), Some(true));
+	assert_eq!(decode_result::<TypedResult>(&int), Some(TypedResult::Int(-5)));
+
+	// Raw values and trailing bytes are rejected
+	assert_eq!(decode_result::<i128>(&(-5i128).encode()), None);
+	assert_eq!(decode_result::<i128>(&[&int[..], &[0]].concat()), None);
+
+	new_test_ext().execute_with(|| {
+		System::set_block_number(1);
+		register_operator(1);
+		let callback = module2::Call::<Test>::typed_callback(0);
+		assert!(<Chainlink>::initiate_request(Origin::signed(2), 1, vec![], 1, vec![], 2, callback).is_ok());
+
+		// Results that can't be converted are rejected, the operator can answer again
+		assert_eq!(<Chainlink>::callback(Origin::signed(1), 0, TypedResult::Bool(true).encode()).map_err(|e| e.error), Err(Error::<Test>::UnknownCallback.into()));
+		assert!(<Chainlink>::callback(Origin::signed(1), 0, TypedResult::UInt(42).encode()).is_ok());
+		assert_eq!(module2::Result::get(), 42);
+
+		// Unknown data versions are rejected
+		let callback = module2::Call::<Test>::callback(vec![]);
+		assert_eq!(<Chainlink>::initiate_request(Origin::signed(2), 1, vec![], 3, vec![], 2, callback.clone()), Err(Error::<Test>::UnknownDataVersion.into()));
+		assert_eq!(<Chainlink>::initiate_aggregated_request(Origin::signed(2), vec![1], 1, vec![], 3, vec![], 2, callback.clone()), Err(Error::<Test>::UnknownDataVersion.into()));
+
+		// The median of an aggregated request in an opaque format is passed as a plain `NumericResult`
+		let info = OperatorInfo { data_version: 0, ..operator_info() };
+		assert!(<Chainlink>::set_operator_info(Origin::signed(1), info).is_ok());
+		assert!(<Chainlink>::initiate_aggregated_request(Origin::signed(2), vec![1], 1, vec![], 0, vec![], 2, callback).is_ok());
+		assert!(<Chainlink>::callback(Origin::signed(1), 1, 7i128.encode()).is_ok());
+		assert_eq!(module2::Result::get(), 7);
+	});
+}
@@ -0,0 +1,79 @@
+//! Signing the extrinsics the mock operator submits.
+
+use codec::{Encode, Output};
+use node_template_runtime::{AccountId, Hash, Index, Runtime, SignedExtra};
+use sp_core::{sr25519, Pair};
+use sp_runtime::{
+	generic::{Era, SignedPayload, UncheckedExtrinsic},
+	traits::IdentifyAccount,
+	MultiSignature, MultiSigner,
+};
+
+/// A call that is already encoded, e.g. for a pallet the runtime crate does not know about.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct EncodedCall(pub Vec<u8>);
+
+impl Encode for EncodedCall {
+	fn size_hint(&self) -> usize {
+		self.0.len()
+	}
+
+	fn encode_to<T: Output + ?Sized>(&self, dest: &mut T) {
+		dest.write(&self.0)
+	}
+}
+
+/// What an extrinsic is signed against.
+#[derive(Clone, Copy)]
+pub struct SigningContext {
+	pub genesis_hash: Hash,
+	pub spec_version: u32,
+	pub transaction_version: u32,
+}
+
+/// The account of the key `pair`.
+pub fn account_of(pair: &sr25519::Pair) -> AccountId {
+	MultiSigner::from(pair.public()).into_account()
+}
+
+/// Create an immortal extrinsic dispatching `call`, signed by `pair`.
+///
+/// Uses the signed extensions of the example runtime, which the runtimes using the legacy
+/// `pallet-chainlink` share.
+pub fn create_extrinsic<Call: Encode + Clone>(
+	pair: &sr25519::Pair,
+	call: Call,
+	nonce: Index,
+	context: SigningContext,
+) -> Vec<u8> {
+	let extra: SignedExtra = (
+		frame_system::CheckSpecVersion::<Runtime>::new(),
+		frame_system::CheckTxVersion::<Runtime>::new(),
+		frame_system::CheckGenesis::<Runtime>::new(),
+		frame_system::CheckEra::<Runtime>::from(Era::Immortal),
+		frame_system::CheckNonce::<Runtime>::from(nonce),
+		frame_system::CheckWeight::<Runtime>::new(),
+		pallet_transaction_payment::ChargeTransactionPayment::<Runtime>::from(0),
+	);
+	let payload = SignedPayload::from_raw(
+		call.clone(),
+		extra.clone(),
+		(
+			context.spec_version,
+			context.transaction_version,
+			context.genesis_hash,
+			context.genesis_hash,
+			(),
+			(),
+			(),
+		),
+	);
+	let signature: MultiSignature = payload.using_encoded(|e| pair.sign(e)).into();
+	UncheckedExtrinsic::<AccountId, Call, MultiSignature, SignedExtra>::new_signed(
+		call,
+		account_of(pair),
+		signature,
+		extra,
+	)
+	.encode()
+}
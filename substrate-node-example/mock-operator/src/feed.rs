@@ -0,0 +1,117 @@
+//! Submitting to the rounds of `pallet-chainlink-feed`.
+
+use std::collections::HashSet;
+
+use codec::Decode;
+use node_template_runtime::{pallet_chainlink_feed, AccountId, Call, Event, FeedId, Hash, RoundId};
+use sp_core::sr25519;
+
+use crate::{
+	extrinsic::{account_of, create_extrinsic, SigningContext},
+	generator::Generators,
+	rpc::{system_events_key, Rpc},
+	Error, ExitCondition,
+};
+
+type EventRecord = frame_system::EventRecord<Event, Hash>;
+
+/// Submit to the eligible rounds of all feeds on behalf of `oracles` until `exit` is met.
+///
+/// Submits to the rounds that are open at startup, then reacts to the `NewRound` events of each
+/// block. Answers count towards `exit` when they update the answer of a feed.
+pub async fn run(
+	rpc: &Rpc,
+	context: SigningContext,
+	oracles: &[sr25519::Pair],
+	mut generators: Generators,
+	exit: ExitCondition,
+) -> Result<(), Error> {
+	let mut submitted = HashSet::new();
+	submit_eligible(rpc, context, oracles, &mut generators, &mut submitted).await?;
+
+	let mut blocks = rpc.subscribe_storage(system_events_key()).await?;
+	let (mut answers, mut block_count) = (0, 0);
+	while let Some(change) = blocks.next().await? {
+		let events = match change.changes.into_iter().next().and_then(|(_, data)| data) {
+			Some(data) => Vec::<EventRecord>::decode(&mut &data.0[..])?,
+			None => Vec::new(),
+		};
+		let mut new_rounds = false;
+		for record in events {
+			match record.event {
+				Event::ChainlinkFeed(pallet_chainlink_feed::Event::NewRound(
+					feed_id,
+					round_id,
+					started_by,
+					_,
+				)) => {
+					log::info!("Feed {} started round {} by {}", feed_id, round_id, started_by);
+					new_rounds = true;
+				}
+				Event::ChainlinkFeed(pallet_chainlink_feed::Event::AnswerUpdated(
+					feed_id,
+					round_id,
+					value,
+					_,
+				)) => {
+					log::info!("Feed {} answered round {} with {}", feed_id, round_id, value);
+					answers += 1;
+				}
+				_ => {}
+			}
+		}
+		if new_rounds {
+			submit_eligible(rpc, context, oracles, &mut generators, &mut submitted).await?;
+		}
+
+		block_count += 1;
+		if exit.check(answers, block_count)? {
+			return Ok(())
+		}
+	}
+	Err("The node closed the subscription".into())
+}
+
+/// Submit to the rounds each of the `oracles` is eligible for, skipping the ones already
+/// `submitted` to (which may not be included in a block, yet).
+async fn submit_eligible(
+	rpc: &Rpc,
+	context: SigningContext,
+	oracles: &[sr25519::Pair],
+	generators: &mut Generators,
+	submitted: &mut HashSet<(AccountId, FeedId, RoundId)>,
+) -> Result<(), Error> {
+	for pair in oracles {
+		let oracle = account_of(pair);
+		let rounds: Vec<(FeedId, RoundId)> =
+			rpc.runtime_call("ChainlinkFeedApi_eligible_rounds", &oracle).await?;
+		for (feed_id, round_id) in rounds {
+			if !submitted.insert((oracle.clone(), feed_id, round_id)) {
+				continue
+			}
+			let value = generators.next_value(feed_id);
+			let call =
+				Call::ChainlinkFeed(pallet_chainlink_feed::Call::submit(feed_id, round_id, value));
+			let nonce = rpc.next_nonce(&oracle).await?;
+			match rpc.submit(create_extrinsic(pair, call, nonce, context)).await {
+				Ok(hash) => log::info!(
+					"{} submitted {} to round {} of feed {} ({:?})",
+					oracle,
+					value,
+					round_id,
+					feed_id,
+					hash
+				),
+				// a rejected submission should not stop the others
+				Err(e) => log::warn!(
+					"{} failed to submit to round {} of feed {}: {}",
+					oracle,
+					round_id,
+					feed_id,
+					e
+				),
+			}
+		}
+	}
+	Ok(())
+}
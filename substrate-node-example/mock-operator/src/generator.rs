@@ -0,0 +1,132 @@
+//! Generators of the values the mock operator submits.
+
+use std::str::FromStr;
+
+use node_template_runtime::{FeedId, Value};
+use rand::Rng;
+
+/// Generates the values submitted for a feed.
+///
+/// Parsed from `constant:<value>`, `random:<min>:<max>`, `walk:<start>:<max step in basis
+/// points>` or `sequence:<value>,<value>,...` (repeats at its end).
+#[derive(Clone, Debug, PartialEq)]
+pub enum ValueGenerator {
+	Constant(Value),
+	Random(Value, Value),
+	Walk { current: Value, max_step_basis_points: u32 },
+	Sequence { values: Vec<Value>, next: usize },
+}
+
+impl ValueGenerator {
+	/// Return the next value to submit.
+	pub fn next_value(&mut self) -> Value {
+		match self {
+			ValueGenerator::Constant(value) => *value,
+			ValueGenerator::Random(min, max) => rand::thread_rng().gen_range(*min..=*max),
+			ValueGenerator::Walk { current, max_step_basis_points } => {
+				let step = *max_step_basis_points as i64;
+				let step = rand::thread_rng().gen_range(-step..=step);
+				let delta = *current / 10_000 * step.unsigned_abs() as Value;
+				*current = if step < 0 {
+					current.saturating_sub(delta).max(1)
+				} else {
+					current.saturating_add(delta)
+				};
+				*current
+			}
+			ValueGenerator::Sequence { values, next } => {
+				let value = values[*next % values.len()];
+				*next = next.wrapping_add(1);
+				value
+			}
+		}
+	}
+}
+
+impl FromStr for ValueGenerator {
+	type Err = String;
+
+	fn from_str(s: &str) -> Result<Self, Self::Err> {
+		let parse = |value: &str| {
+			value.trim().parse::<Value>().map_err(|e| format!("Invalid value {:?}: {}", value, e))
+		};
+		let (kind, args) = s.split_once(':').unwrap_or((s, ""));
+		let args: Vec<&str> = args.split(':').collect();
+		match (kind, args.as_slice()) {
+			("constant", [value]) => Ok(ValueGenerator::Constant(parse(value)?)),
+			("random", [min, max]) => {
+				let (min, max) = (parse(min)?, parse(max)?);
+				if min > max {
+					return Err(format!("The minimum {} exceeds the maximum {}", min, max));
+				}
+				Ok(ValueGenerator::Random(min, max))
+			}
+			("walk", [start, step]) => Ok(ValueGenerator::Walk {
+				current: parse(start)?,
+				max_step_basis_points: step
+					.parse()
+					.map_err(|e| format!("Invalid step {:?}: {}", step, e))?,
+			}),
+			("sequence", [values]) => Ok(ValueGenerator::Sequence {
+				values: values.split(',').map(parse).collect::<Result<_, _>>()?,
+				next: 0,
+			}),
+			_ => Err(format!(
+				"Invalid generator {:?}, expected constant:<value>, random:<min>:<max>, \
+				walk:<start>:<step> or sequence:<values>",
+				s
+			)),
+		}
+	}
+}
+
+/// A generator for one feed (`<feed id>=<generator>`) or all feeds (`<generator>`).
+#[derive(Clone, Debug, PartialEq)]
+pub struct FeedGenerator {
+	pub feed_id: Option<FeedId>,
+	pub generator: ValueGenerator,
+}
+
+impl FromStr for FeedGenerator {
+	type Err = String;
+
+	fn from_str(s: &str) -> Result<Self, Self::Err> {
+		match s.split_once('=') {
+			Some((feed_id, generator)) => Ok(FeedGenerator {
+				feed_id: Some(
+					feed_id.parse().map_err(|e| format!("Invalid feed id {:?}: {}", feed_id, e))?,
+				),
+				generator: generator.parse()?,
+			}),
+			None => Ok(FeedGenerator { feed_id: None, generator: s.parse()? }),
+		}
+	}
+}
+
+/// The generators of all feeds.
+pub struct Generators {
+	per_feed: Vec<FeedGenerator>,
+	default: ValueGenerator,
+}
+
+impl Generators {
+	/// Use the generators given for specific feeds and the last one given for all feeds (or
+	/// `default` if there is none) for the others.
+	pub fn new(generators: Vec<FeedGenerator>, default: ValueGenerator) -> Self {
+		let default = generators
+			.iter()
+			.rev()
+			.find(|g| g.feed_id.is_none())
+			.map_or(default, |g| g.generator.clone());
+		let per_feed = generators.into_iter().filter(|g| g.feed_id.is_some()).collect();
+		Self { per_feed, default }
+	}
+
+	/// The next value to submit to `feed_id`.
+	pub fn next_value(&mut self, feed_id: FeedId) -> Value {
+		match self.per_feed.iter_mut().find(|g| g.feed_id == Some(feed_id)) {
+			Some(g) => g.generator.next_value(),
+			None => self.default.next_value(),
+		}
+	}
+}
@@ -0,0 +1,208 @@
+//! Answering the requests of the legacy `pallet-chainlink`.
+//!
+//! The example runtime does not include the pallet, so its calls are encoded by hand and its
+//! storage is read raw. This assumes a runtime with the signed extensions and account type of the
+//! example runtime.
+
+use std::collections::{BTreeSet, HashSet};
+
+use codec::{Decode, Encode};
+use node_template_runtime::{AccountId, Balance, FeedId};
+use sp_core::{blake2_128, sr25519, twox_128};
+
+use crate::{
+	extrinsic::{account_of, create_extrinsic, EncodedCall, SigningContext},
+	generator::Generators,
+	rpc::{system_events_key, Rpc},
+	Error, ExitCondition,
+};
+
+/// `pallet_chainlink::RequestIdentifier`
+type RequestIdentifier = u64;
+
+/// The index of `register_operator` in the calls of the pallet.
+const REGISTER_OPERATOR_CALL: u8 = 0;
+/// The index of `callback` in the calls of the pallet.
+const CALLBACK_CALL: u8 = 3;
+/// The index of `set_operator_info` in the calls of the pallet.
+const SET_OPERATOR_INFO_CALL: u8 = 6;
+/// Legacy requests carry no feed id, their values come from the generator of this feed.
+const LEGACY_FEED_ID: FeedId = 0;
+/// `pallet_chainlink::encoding::OPAQUE_DATA_VERSION`, other versions expect typed results.
+const OPAQUE_DATA_VERSION: u64 = 0;
+/// The index of the `Int` variant of `pallet_chainlink::encoding::TypedResult`.
+const TYPED_RESULT_INT: u8 = 0;
+
+/// A request waiting for the results of some of the operators.
+struct PendingRequest {
+	request_id: RequestIdentifier,
+	operators: Vec<AccountId>,
+	/// Whether to answer with a `TypedResult` instead of a raw `i128`.
+	typed_result: bool,
+}
+
+impl PendingRequest {
+	fn result(&self, value: i128) -> Vec<u8> {
+		if self.typed_result {
+			(TYPED_RESULT_INT, value).encode()
+		} else {
+			value.encode()
+		}
+	}
+}
+
+/// `pallet_chainlink::OperatorInfo`, announced by the operators on startup.
+#[derive(Clone, Debug, Encode)]
+pub struct OperatorInfo {
+	pub specs: Vec<(Vec<u8>, Balance)>,
+	pub data_version: u64,
+	pub endpoint: Vec<u8>,
+}
+
+/// Where the legacy pallet is declared in the runtime.
+pub struct LegacyPallet {
+	/// The name in `construct_runtime!`, prefixing its storage.
+	pub name: Vec<u8>,
+	/// The index in `construct_runtime!`, prefixing its calls.
+	pub index: u8,
+}
+
+impl LegacyPallet {
+	fn storage_prefix(&self, storage_name: &[u8]) -> Vec<u8> {
+		[twox_128(&self.name), twox_128(storage_name)].concat()
+	}
+
+	/// The key of `Operators(operator)`.
+	fn operator_key(&self, operator: &AccountId) -> Vec<u8> {
+		[self.storage_prefix(b"Operators"), blake2_128_concat(&operator.encode())].concat()
+	}
+
+	/// The request id of a key of `Requests` or `AggregatedRequests`.
+	fn request_id(prefix: &[u8], key: &[u8]) -> Option<RequestIdentifier> {
+		// prefix, then the `blake2_128_concat` hash of the id
+		let encoded_id = key.get(prefix.len() + 16..)?;
+		RequestIdentifier::decode(&mut &encoded_id[..]).ok()
+	}
+
+	/// The pending requests, read from the leading fields of the requests (`operator` to
+	/// `data_version` of `Request`, `operators` of `AggregatedRequest`). Aggregated requests
+	/// always expect raw `i128` results.
+	async fn pending_requests(&self, rpc: &Rpc) -> Result<Vec<PendingRequest>, Error> {
+		let mut pending = Vec::new();
+		for (storage_name, aggregated) in [(&b"Requests"[..], false), (b"AggregatedRequests", true)] {
+			let prefix = self.storage_prefix(storage_name);
+			for key in rpc.storage_keys(prefix.clone()).await? {
+				let request_id = match Self::request_id(&prefix, &key) {
+					Some(request_id) => request_id,
+					None => continue,
+				};
+				let data = match rpc.storage(key).await? {
+					Some(data) => data,
+					None => continue,
+				};
+				let (operators, typed_result) = if aggregated {
+					(Vec::<AccountId>::decode(&mut &data[..])?, false)
+				} else {
+					let (operator, _requester, _spec_index, data_version) =
+						<(AccountId, AccountId, Vec<u8>, u64)>::decode(&mut &data[..])?;
+					(vec![operator], data_version != OPAQUE_DATA_VERSION)
+				};
+				pending.push(PendingRequest { request_id, operators, typed_result });
+			}
+		}
+		Ok(pending)
+	}
+
+	fn call(&self, call_index: u8, args: impl Encode) -> EncodedCall {
+		EncodedCall([&[self.index, call_index][..], &args.encode()].concat())
+	}
+}
+
+/// Register `operators` with `info` and answer the requests made to them until `exit` is met.
+///
+/// Answers (single and aggregated requests) with the values of the generator of feed 0, as
+/// `TypedResult::Int` or raw `i128` depending on the request. Answers count towards `exit` once the request is removed from storage.
+pub async fn run(
+	rpc: &Rpc,
+	context: SigningContext,
+	operators: &[sr25519::Pair],
+	mut generators: Generators,
+	pallet: LegacyPallet,
+	info: OperatorInfo,
+	exit: ExitCondition,
+) -> Result<(), Error> {
+	for pair in operators {
+		let operator = account_of(pair);
+		let registered = match rpc.storage(pallet.operator_key(&operator)).await? {
+			Some(data) => bool::decode(&mut &data[..])?,
+			None => false,
+		};
+		if !registered {
+			let call = pallet.call(REGISTER_OPERATOR_CALL, ());
+			let nonce = rpc.next_nonce(&operator).await?;
+			rpc.submit(create_extrinsic(pair, call, nonce, context)).await?;
+			log::info!("Registering operator {}", operator);
+		}
+		let call = pallet.call(SET_OPERATOR_INFO_CALL, &info);
+		let nonce = rpc.next_nonce(&operator).await?;
+		rpc.submit(create_extrinsic(pair, call, nonce, context)).await?;
+	}
+
+	// the answered requests with the operators that answered them
+	let mut answered = HashSet::new();
+	let mut blocks = rpc.subscribe_storage(system_events_key()).await?;
+	let (mut answers, mut block_count) = (0, 0);
+	while blocks.next().await?.is_some() {
+		let pending = pallet.pending_requests(rpc).await?;
+		for request in &pending {
+			let request_id = &request.request_id;
+			for operator in &request.operators {
+				if answered.contains(&(*request_id, operator.clone())) {
+					continue
+				}
+				let pair = match operators.iter().find(|pair| account_of(pair) == *operator) {
+					Some(pair) => pair,
+					None => continue,
+				};
+
+				let value = generators.next_value(LEGACY_FEED_ID) as i128;
+				let call = pallet.call(CALLBACK_CALL, (request_id, request.result(value)));
+				let nonce = rpc.next_nonce(operator).await?;
+				match rpc.submit(create_extrinsic(pair, call, nonce, context)).await {
+					Ok(hash) => {
+						log::info!(
+							"{} answered request {} with {} ({:?})",
+							operator,
+							request_id,
+							value,
+							hash
+						);
+						answered.insert((*request_id, operator.clone()));
+					}
+					Err(e) => {
+						log::warn!("{} failed to answer request {}: {}", operator, request_id, e)
+					}
+				}
+			}
+		}
+		// answered requests are removed by the callback (or when they expire)
+		let pending: BTreeSet<_> = pending.into_iter().map(|request| request.request_id).collect();
+		let done: BTreeSet<_> = answered
+			.iter()
+			.map(|(request_id, _)| *request_id)
+			.filter(|request_id| !pending.contains(request_id))
+			.collect();
+		answered.retain(|(request_id, _)| pending.contains(request_id));
+		answers += done.len() as u32;
+
+		block_count += 1;
+		if exit.check(answers, block_count)? {
+			return Ok(())
+		}
+	}
+	Err("The node closed the subscription".into())
+}
+
+fn blake2_128_concat(data: &[u8]) -> Vec<u8> {
+	[&blake2_128(data)[..], data].concat()
+}
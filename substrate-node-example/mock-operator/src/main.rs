@@ -0,0 +1,165 @@
+//! A mock Chainlink operator for local development and end-to-end tests.
+//!
+//! Connects to a node and answers on behalf of a set of oracle keys, either by submitting to the
+//! rounds of `pallet-chainlink-feed` or by answering the requests of the legacy
+//! `pallet-chainlink`.
+
+mod extrinsic;
+mod feed;
+mod generator;
+mod legacy;
+mod rpc;
+
+use sp_core::{sr25519, Pair};
+use structopt::StructOpt;
+
+use crate::{
+	extrinsic::SigningContext,
+	generator::{FeedGenerator, Generators, ValueGenerator},
+	rpc::Rpc,
+};
+
+/// The error type of the mock operator.
+pub type Error = Box<dyn std::error::Error + Send + Sync>;
+
+/// The oracles used if none are given.
+const DEFAULT_ORACLES: [&str; 3] = ["//Alice", "//Bob", "//Charlie"];
+/// The generator used for the feeds without one.
+const DEFAULT_GENERATOR: &str = "walk:100000000:50";
+
+#[derive(Debug, StructOpt)]
+#[structopt(name = "mock-operator", about = "A mock Chainlink operator.")]
+struct Cli {
+	/// The websocket RPC endpoint of the node.
+	#[structopt(long, default_value = "ws://127.0.0.1:9944")]
+	url: String,
+
+	/// The secret URI of an oracle (or operator) to answer with, can be repeated.
+	///
+	/// Defaults to `//Alice`, `//Bob` and `//Charlie`.
+	#[structopt(long = "oracle", value_name = "SURI")]
+	oracles: Vec<String>,
+
+	/// The generator of the submitted values as `[<feed id>=]<generator>`, can be repeated.
+	///
+	/// Generators are `constant:<value>`, `random:<min>:<max>`, `walk:<start>:<max step in basis
+	/// points>` and `sequence:<value>,<value>,...`. A generator without a feed id applies to all
+	/// feeds without their own.
+	#[structopt(long = "generator", value_name = "GENERATOR")]
+	generators: Vec<FeedGenerator>,
+
+	/// Answer the requests of the legacy `pallet-chainlink` declared at this index in
+	/// `construct_runtime!` instead of submitting to feeds.
+	#[structopt(long, value_name = "PALLET_INDEX")]
+	legacy: Option<u8>,
+
+	/// The name the legacy `pallet-chainlink` is declared with in `construct_runtime!`.
+	#[structopt(long, default_value = "Chainlink")]
+	legacy_pallet_name: String,
+
+	/// A spec index (as UTF-8 bytes) the legacy operators announce support for, can be repeated.
+	#[structopt(long = "legacy-spec", value_name = "SPEC")]
+	legacy_specs: Vec<String>,
+
+	/// The minimum fee the legacy operators announce for each of their specs.
+	#[structopt(long, default_value = "0")]
+	legacy_minimum_fee: u128,
+
+	/// The data version the legacy operators announce.
+	#[structopt(long, default_value = "1")]
+	legacy_data_version: u64,
+
+	/// Exit successfully after this many answers were accepted (feed answers updated or legacy
+	/// requests answered).
+	#[structopt(long, value_name = "COUNT")]
+	exit_after: Option<u32>,
+
+	/// Exit with an error if `--exit-after` is not reached within this many blocks.
+	#[structopt(long, value_name = "BLOCKS")]
+	timeout_blocks: Option<u32>,
+}
+
+/// When the mock operator should stop.
+#[derive(Clone, Copy, Debug)]
+pub struct ExitCondition {
+	exit_after: Option<u32>,
+	timeout_blocks: Option<u32>,
+}
+
+impl ExitCondition {
+	/// Whether to exit after seeing `answers` accepted answers, erroring if `blocks` blocks were
+	/// imported without reaching the expected number of answers.
+	pub fn check(&self, answers: u32, blocks: u32) -> Result<bool, Error> {
+		if self.exit_after.map_or(false, |expected| answers >= expected) {
+			return Ok(true)
+		}
+		match self.timeout_blocks {
+			Some(timeout) if blocks >= timeout => Err(format!(
+				"Timed out after {} blocks with {} of {} answers",
+				blocks,
+				answers,
+				self.exit_after.unwrap_or_default()
+			)
+			.into()),
+			_ => Ok(false),
+		}
+	}
+}
+
+#[tokio::main]
+async fn main() {
+	env_logger::Builder::from_env(env_logger::Env::default().default_filter_or("info")).init();
+
+	if let Err(e) = run(Cli::from_args()).await {
+		log::error!("{}", e);
+		std::process::exit(1);
+	}
+}
+
+async fn run(cli: Cli) -> Result<(), Error> {
+	let oracles = if cli.oracles.is_empty() {
+		DEFAULT_ORACLES.iter().map(|s| s.to_string()).collect()
+	} else {
+		cli.oracles
+	};
+	let oracles = oracles
+		.iter()
+		.map(|suri| {
+			sr25519::Pair::from_string(suri, None)
+				.map_err(|e| format!("Invalid oracle {:?}: {:?}", suri, e).into())
+		})
+		.collect::<Result<Vec<_>, Error>>()?;
+	let default_generator: ValueGenerator =
+		DEFAULT_GENERATOR.parse().expect("the default generator is valid");
+	let generators = Generators::new(cli.generators, default_generator);
+	let exit = ExitCondition { exit_after: cli.exit_after, timeout_blocks: cli.timeout_blocks };
+
+	let rpc = Rpc::connect(&cli.url).await?;
+	let version = rpc.runtime_version().await?;
+	let context = SigningContext {
+		genesis_hash: rpc.genesis_hash().await?,
+		spec_version: version.spec_version,
+		transaction_version: version.transaction_version,
+	};
+	log::info!("Connected to {} (spec version {})", cli.url, version.spec_version);
+
+	match cli.legacy {
+		Some(pallet_index) => {
+			let pallet = legacy::LegacyPallet {
+				name: cli.legacy_pallet_name.into_bytes(),
+				index: pallet_index,
+			};
+			let info = legacy::OperatorInfo {
+				specs: cli
+					.legacy_specs
+					.into_iter()
+					.map(|spec| (spec.into_bytes(), cli.legacy_minimum_fee))
+					.collect(),
+				data_version: cli.legacy_data_version,
+				endpoint: cli.url.into_bytes(),
+			};
+			legacy::run(&rpc, context, &oracles, generators, pallet, info, exit).await
+		}
+		None => feed::run(&rpc, context, &oracles, generators, exit).await,
+	}
+}
@@ -0,0 +1,111 @@
+//! The RPC calls the mock operator makes to the node.
+
+use codec::{Decode, Encode};
+use jsonrpsee_ws_client::{
+	types::{
+		traits::{Client, SubscriptionClient},
+		v2::params::JsonRpcParams,
+		Subscription,
+	},
+	WsClient, WsClientBuilder,
+};
+use node_template_runtime::{AccountId, Hash, Index};
+use serde::{de::DeserializeOwned, Serialize};
+use sp_core::{
+	storage::{StorageChangeSet, StorageData, StorageKey},
+	twox_128, Bytes,
+};
+use sp_version::RuntimeVersion;
+
+use crate::Error;
+
+/// A connection to a node.
+pub struct Rpc {
+	client: WsClient,
+}
+
+impl Rpc {
+	/// Connect to the node listening at `url`.
+	pub async fn connect(url: &str) -> Result<Self, Error> {
+		let client = WsClientBuilder::default().build(url).await?;
+		Ok(Self { client })
+	}
+
+	async fn request<R: DeserializeOwned>(
+		&self,
+		method: &str,
+		params: Vec<serde_json::Value>,
+	) -> Result<R, Error> {
+		Ok(self.client.request(method, JsonRpcParams::Array(params)).await?)
+	}
+
+	/// The hash of the genesis block.
+	pub async fn genesis_hash(&self) -> Result<Hash, Error> {
+		let hash: Option<Hash> = self.request("chain_getBlockHash", vec![to_value(0u32)?]).await?;
+		hash.ok_or_else(|| "The node has no genesis block".into())
+	}
+
+	/// The version of the runtime at the best block.
+	pub async fn runtime_version(&self) -> Result<RuntimeVersion, Error> {
+		self.request("state_getRuntimeVersion", vec![]).await
+	}
+
+	/// The next nonce of `account` (including the transactions in the pool).
+	pub async fn next_nonce(&self, account: &AccountId) -> Result<Index, Error> {
+		self.request("system_accountNextIndex", vec![to_value(account)?]).await
+	}
+
+	/// Submit an encoded extrinsic and return its hash.
+	pub async fn submit(&self, extrinsic: Vec<u8>) -> Result<Hash, Error> {
+		self.request("author_submitExtrinsic", vec![to_value(Bytes(extrinsic))?]).await
+	}
+
+	/// Call a runtime API function at the best block and decode its result.
+	pub async fn runtime_call<R: Decode>(
+		&self,
+		method: &str,
+		args: impl Encode,
+	) -> Result<R, Error> {
+		let args = to_value(Bytes(args.encode()))?;
+		let result: Bytes = self.request("state_call", vec![to_value(method)?, args]).await?;
+		Ok(R::decode(&mut &result[..])?)
+	}
+
+	/// The raw value stored under `key` at the best block.
+	pub async fn storage(&self, key: Vec<u8>) -> Result<Option<Vec<u8>>, Error> {
+		let data: Option<StorageData> =
+			self.request("state_getStorage", vec![to_value(StorageKey(key))?]).await?;
+		Ok(data.map(|data| data.0))
+	}
+
+	/// The keys starting with `prefix` at the best block.
+	pub async fn storage_keys(&self, prefix: Vec<u8>) -> Result<Vec<Vec<u8>>, Error> {
+		let keys: Vec<StorageKey> =
+			self.request("state_getKeys", vec![to_value(StorageKey(prefix))?]).await?;
+		Ok(keys.into_iter().map(|key| key.0).collect())
+	}
+
+	/// Subscribe to the changes of the value stored under `key`.
+	pub async fn subscribe_storage(
+		&self,
+		key: Vec<u8>,
+	) -> Result<Subscription<StorageChangeSet<Hash>>, Error> {
+		Ok(self
+			.client
+			.subscribe(
+				"state_subscribeStorage",
+				JsonRpcParams::Array(vec![to_value(vec![StorageKey(key)])?]),
+				"state_unsubscribeStorage",
+			)
+			.await?)
+	}
+}
+
+/// The storage key of the events of the current block (`System::Events`).
+pub fn system_events_key() -> Vec<u8> {
+	[twox_128(b"System"), twox_128(b"Events")].concat()
+}
+
+fn to_value(value: impl Serialize) -> Result<serde_json::Value, Error> {
+	Ok(serde_json::to_value(value)?)
+}
@@ -1,11 +1,12 @@
 use sp_core::{Pair, Public, sr25519};
 use node_template_runtime::{
-	AccountId, AuraConfig, BalancesConfig, ChainlinkFeedConfig, GenesisConfig, GrandpaConfig,
-	SudoConfig, SystemConfig, WASM_BINARY, Signature
+	AccountId, AuraConfig, BalancesConfig, ChainlinkFeedConfig, FeedPalletId, GenesisConfig,
+	GrandpaConfig, Runtime, SudoConfig, SystemConfig, Value, WASM_BINARY, Signature,
+	pallet_chainlink_feed::{GenesisFeed, GenesisFeedOf, RoundTimeout},
 };
 use sp_consensus_aura::sr25519::AuthorityId as AuraId;
 use sp_finality_grandpa::AuthorityId as GrandpaId;
-use sp_runtime::traits::{Verify, IdentifyAccount};
+use sp_runtime::traits::{AccountIdConversion, Verify, IdentifyAccount};
 use sc_service::ChainType;
 
 // The URL for the telemetry server.
@@ -62,6 +63,7 @@ pub fn development_config() -> Result<ChainSpec, String> {
 				get_account_id_from_seed::<sr25519::Public>("Alice//stash"),
 				get_account_id_from_seed::<sr25519::Public>("Bob//stash"),
 			],
+			vec![],
 			true,
 		),
 		// Bootnodes
@@ -110,6 +112,7 @@ pub fn local_testnet_config() -> Result<ChainSpec, String> {
 				get_account_id_from_seed::<sr25519::Public>("Eve//stash"),
 				get_account_id_from_seed::<sr25519::Public>("Ferdie//stash"),
 			],
+			vec![],
 			true,
 		),
 		// Bootnodes
@@ -125,37 +128,114 @@ pub fn local_testnet_config() -> Result<ChainSpec, String> {
 	))
 }
 
+/// The feeds of the `oracle-dev` chain as `(description, initial answer)` with 8 decimals.
+///
+/// The feed ids correspond to the position in the list.
+pub const ORACLE_DEV_FEEDS: &[(&str, Value)] = &[
+	("LINK / USD", 25_00000000),
+	("ETH / USD", 3_000_00000000),
+	("BTC / USD", 45_000_00000000),
+];
+
+/// The seeds of the oracles submitting to the feeds of the `oracle-dev` chain.
+pub const ORACLE_DEV_ORACLES: &[&str] = &["Alice", "Bob", "Charlie"];
+
+pub fn oracle_dev_config() -> Result<ChainSpec, String> {
+	let wasm_binary = WASM_BINARY.ok_or("Development wasm binary not available".to_string())?;
+
+	Ok(ChainSpec::from_genesis(
+		// Name
+		"Oracle Development",
+		// ID
+		"oracle_dev",
+		ChainType::Development,
+		move || {
+			let root_key = get_account_id_from_seed::<sr25519::Public>("Alice");
+			let oracles: Vec<AccountId> = ORACLE_DEV_ORACLES
+				.iter()
+				.map(|seed| get_account_id_from_seed::<sr25519::Public>(seed))
+				.collect();
+			let feeds = ORACLE_DEV_FEEDS
+				.iter()
+				.map(|(description, initial_answer)| GenesisFeed {
+					owner: root_key.clone(),
+					payment: 1_000,
+					timeout: RoundTimeout::Blocks(10),
+					submission_value_bounds: (1, Value::max_value()),
+					min_submissions: 2,
+					decimals: 8,
+					description: description.as_bytes().to_vec(),
+					restart_delay: 0,
+					// every oracle is its own admin
+					oracles: oracles.iter().cloned().map(|o| (o.clone(), o)).collect(),
+					requesters: vec![(root_key.clone(), 0)],
+					initial_answer: Some(*initial_answer),
+				})
+				.collect();
+			let mut endowed_accounts = oracles.clone();
+			endowed_accounts.push(get_account_id_from_seed::<sr25519::Public>("Dave"));
+			// the fund account paying the oracles
+			endowed_accounts.push(FeedPalletId::get().into_account());
+			testnet_genesis(
+				wasm_binary,
+				// Initial PoA authorities
+				vec![
+					authority_keys_from_seed("Alice"),
+				],
+				// Sudo account
+				root_key,
+				// Pre-funded accounts
+				endowed_accounts,
+				feeds,
+				true,
+			)
+		},
+		// Bootnodes
+		vec![],
+		// Telemetry
+		None,
+		// Protocol ID
+		None,
+		// Properties
+		None,
+		// Extensions
+		None,
+	))
+}
+
 /// Configure initial storage state for FRAME modules.
 fn testnet_genesis(
 	wasm_binary: &[u8],
 	initial_authorities: Vec<(AuraId, GrandpaId)>,
 	root_key: AccountId,
 	endowed_accounts: Vec<AccountId>,
+	feeds: Vec<GenesisFeedOf<Runtime>>,
 	_enable_println: bool,
 ) -> GenesisConfig {
 	GenesisConfig {
-		frame_system: Some(SystemConfig {
+		system: SystemConfig {
 			// Add Wasm runtime to storage.
 			code: wasm_binary.to_vec(),
 			changes_trie_config: Default::default(),
-		}),
-		pallet_balances: Some(BalancesConfig {
+		},
+		balances: BalancesConfig {
 			// Configure endowed accounts with initial balance of 1 << 60.
 			balances: endowed_accounts.iter().cloned().map(|k|(k, 1 << 60)).collect(),
-		}),
-		pallet_aura: Some(AuraConfig {
+		},
+		aura: AuraConfig {
 			authorities: initial_authorities.iter().map(|x| (x.0.clone())).collect(),
-		}),
-		pallet_grandpa: Some(GrandpaConfig {
+		},
+		grandpa: GrandpaConfig {
 			authorities: initial_authorities.iter().map(|x| (x.1.clone(), 1)).collect(),
-		}),
-		pallet_sudo: Some(SudoConfig {
+		},
+		sudo: SudoConfig {
 			// Assign network admin rights.
 			key: root_key.clone(),
-		}),
-		pallet_chainlink_feed: Some(ChainlinkFeedConfig {
+		},
+		chainlink_feed: ChainlinkFeedConfig {
 			pallet_admin: root_key,
 			feed_creators: endowed_accounts.clone(),
-		}),
+			feeds,
+		},
 	}
 }
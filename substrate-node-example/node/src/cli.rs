@@ -1,3 +1,5 @@
+use std::path::PathBuf;
+
 use structopt::StructOpt;
 use sc_cli::RunCmd;
 
@@ -8,6 +10,11 @@ pub struct Cli {
 
 	#[structopt(flatten)]
 	pub run: RunCmd,
+
+	/// CSV or JSON file with the values the simulated oracles of the `oracle-dev` chain submit,
+	/// one row of feed values per block. Values follow a random walk if not given.
+	#[structopt(long, parse(from_os_str))]
+	pub oracle_script: Option<PathBuf>,
 }
 
 #[derive(Debug, StructOpt)]
@@ -33,6 +40,12 @@ pub enum Subcommand {
 	/// Revert the chain to a previous state.
 	Revert(sc_cli::RevertCmd),
 
+	/// Print or export the state of a feed from the chain database.
+	Feed(crate::feed::FeedCmd),
+
+	/// Replay a price history into the oracle-dev chain and report the produced answers.
+	ReplayPrices(crate::replay::ReplayPricesCmd),
+
 	/// The custom benchmark subcommmand benchmarking runtime pallets.
 	#[structopt(name = "benchmark", about = "Benchmark runtime pallets.")]
 	Benchmark(frame_benchmarking_cli::BenchmarkCmd),
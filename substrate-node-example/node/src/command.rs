@@ -17,6 +17,7 @@
 
 use crate::{chain_spec, service};
 use crate::cli::{Cli, Subcommand};
+use crate::simulated_oracles::ValueSource;
 use sc_cli::{SubstrateCli, RuntimeVersion, Role, ChainSpec};
 use sc_service::PartialComponents;
 use node_template_runtime::Block;
@@ -49,6 +50,7 @@ impl SubstrateCli for Cli {
 	fn load_spec(&self, id: &str) -> Result<Box<dyn sc_service::ChainSpec>, String> {
 		Ok(match id {
 			"dev" => Box::new(chain_spec::development_config()?),
+			"oracle-dev" => Box::new(chain_spec::oracle_dev_config()?),
 			"" | "local" => Box::new(chain_spec::local_testnet_config()?),
 			path => Box::new(chain_spec::ChainSpec::from_json_file(
 				std::path::PathBuf::from(path),
@@ -114,6 +116,27 @@ pub fn run() -> sc_cli::Result<()> {
 				Ok((cmd.run(client, backend), task_manager))
 			})
 		},
+		Some(Subcommand::Feed(cmd)) => {
+			let runner = cli.create_runner(cmd)?;
+			runner.sync_run(|config| {
+				let PartialComponents { client, .. } = service::new_partial(&config)?;
+				cmd.run(&client)
+			})
+		},
+		Some(Subcommand::ReplayPrices(cmd)) => {
+			let history = cmd.read_history()?;
+			let runner = cli.create_runner(&cmd.run)?;
+			runner.async_run(|config| {
+				if config.chain_spec.id() != "oracle_dev" {
+					return Err(
+						"Price histories can only be replayed into the oracle-dev chain.".into()
+					);
+				}
+				let service::NewFullBase { task_manager, client, transaction_pool } =
+					service::new_full_base(config, None)?;
+				Ok((cmd.run(client, transaction_pool, history), task_manager))
+			})
+		},
 		Some(Subcommand::Benchmark(cmd)) => {
 			if cfg!(feature = "runtime-benchmarks") {
 				let runner = cli.create_runner(cmd)?;
@@ -126,9 +149,20 @@ pub fn run() -> sc_cli::Result<()> {
 		},
 		None => {
 			let runner = cli.create_runner(&cli.run)?;
-			runner.run_node_until_exit(|config| match config.role {
-				Role::Light => service::new_light(config),
-				_ => service::new_full(config),
+			let oracle_script = cli.oracle_script.clone();
+			runner.run_node_until_exit(|config| async move {
+				let simulated_oracles = if config.chain_spec.id() == "oracle_dev" {
+					Some(match oracle_script {
+						Some(path) => ValueSource::from_script(&path).map_err(sc_cli::Error::Input)?,
+						None => ValueSource::RandomWalk,
+					})
+				} else {
+					None
+				};
+				match config.role {
+					Role::Light => service::new_light(config),
+					_ => service::new_full(config, simulated_oracles),
+				}.map_err(sc_cli::Error::Service)
 			})
 		}
 	}
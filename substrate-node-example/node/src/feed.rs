@@ -0,0 +1,336 @@
+//! Inspect the state of a feed in the chain database.
+//!
+//! The storage of the feed pallet is read from the database without starting the node and
+//! decoded with the pallet's own types, so it can be printed or exported as JSON or CSV.
+
+use std::{fs::File, io::Write, path::PathBuf};
+
+use codec::{Decode, Encode};
+use frame_support::{
+	storage::StoragePrefixedMap, Blake2_128Concat, ReversibleStorageHasher, StorageHasher,
+	Twox64Concat,
+};
+use node_template_runtime::{
+	opaque::Block, pallet_chainlink_feed, AccountId, Balance, FeedId, Hash, RoundId, Runtime,
+};
+use pallet_chainlink_feed::{
+	Debt, Details, FeedConfigOf, Feeds, OracleMetaOf, OracleStatusOf, OracleStatuses, Oracles,
+	Requester, Requesters, RoundDetailsOf, RoundOf, Rounds,
+};
+use sc_cli::{BlockNumberOrHash, CliConfiguration, DatabaseParams, PruningParams, SharedParams};
+use sc_client_api::StorageProvider;
+use serde::Serialize;
+use sp_blockchain::HeaderBackend;
+use sp_core::storage::StorageKey;
+use sp_runtime::generic::BlockId;
+use structopt::StructOpt;
+
+use crate::service::FullClient;
+
+/// The `feed` command used to inspect the state of a feed.
+#[derive(Debug, StructOpt)]
+pub struct FeedCmd {
+	/// The id of the feed to inspect.
+	pub feed_id: FeedId,
+
+	/// Block hash or number to read the state at. Defaults to the best block.
+	#[structopt(long, value_name = "HASH or NUMBER")]
+	pub at: Option<BlockNumberOrHash>,
+
+	/// Only include rounds starting with this one.
+	#[structopt(long)]
+	pub from_round: Option<RoundId>,
+
+	/// Only include rounds up to and including this one.
+	#[structopt(long)]
+	pub to_round: Option<RoundId>,
+
+	/// The format to print or export the state in.
+	#[structopt(long, default_value = "json", possible_values = &["json", "csv"])]
+	pub format: String,
+
+	/// Export the state to this file instead of printing it.
+	#[structopt(long, parse(from_os_str))]
+	pub output: Option<PathBuf>,
+
+	#[allow(missing_docs)]
+	#[structopt(flatten)]
+	pub shared_params: SharedParams,
+
+	#[allow(missing_docs)]
+	#[structopt(flatten)]
+	pub pruning_params: PruningParams,
+
+	#[allow(missing_docs)]
+	#[structopt(flatten)]
+	pub database_params: DatabaseParams,
+}
+
+/// The state of a feed at a block.
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+struct FeedState {
+	feed_id: FeedId,
+	block_hash: Hash,
+	config: FeedConfigOf<Runtime>,
+	/// The debt of the pallet towards all oracles (not only the ones of this feed).
+	debt: Balance,
+	oracles: Vec<OracleEntry>,
+	requesters: Vec<RequesterEntry>,
+	rounds: Vec<RoundEntry>,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+struct OracleEntry {
+	account: AccountId,
+	status: OracleStatusOf<Runtime>,
+	/// The admin and withdrawable payment of the oracle.
+	meta: Option<OracleMetaOf<Runtime>>,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+struct RequesterEntry {
+	account: AccountId,
+	requester: Requester,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+struct RoundEntry {
+	round_id: RoundId,
+	round: RoundOf<Runtime>,
+	/// Only present while the round accepts submissions.
+	details: Option<RoundDetailsOf<Runtime>>,
+}
+
+impl FeedCmd {
+	/// Read the state of the feed from the database of `client` and print or export it.
+	pub fn run(&self, client: &FullClient) -> sc_cli::Result<()> {
+		let block_hash = match &self.at {
+			Some(at) => {
+				let id = at.parse::<Block>()?;
+				client
+					.block_hash_from_id(&id)
+					.map_err(|e| format!("Unable to look up the block: {:?}", e))?
+					.ok_or_else(|| format!("Unknown block {:?}", id))?
+			}
+			None => client.info().best_hash,
+		};
+		let state = read_feed_state(client, self.feed_id, block_hash)?;
+		let in_range = |round_id: &RoundId| {
+			self.from_round.map_or(true, |from| *round_id >= from)
+				&& self.to_round.map_or(true, |to| *round_id <= to)
+		};
+		let state = FeedState {
+			rounds: state.rounds.into_iter().filter(|r| in_range(&r.round_id)).collect(),
+			..state
+		};
+
+		let mut out: Box<dyn Write> = match &self.output {
+			Some(path) => Box::new(File::create(path)?),
+			None => Box::new(std::io::stdout()),
+		};
+		let state = serde_json::to_value(&state).map_err(|e| e.to_string())?;
+		match self.format.as_str() {
+			"csv" => write_csv(&mut out, &state)?,
+			_ => {
+				serde_json::to_writer_pretty(&mut out, &state).map_err(|e| e.to_string())?;
+				writeln!(out)?;
+			}
+		}
+		Ok(())
+	}
+}
+
+impl CliConfiguration for FeedCmd {
+	fn shared_params(&self) -> &SharedParams {
+		&self.shared_params
+	}
+
+	fn pruning_params(&self) -> Option<&PruningParams> {
+		Some(&self.pruning_params)
+	}
+
+	fn database_params(&self) -> Option<&DatabaseParams> {
+		Some(&self.database_params)
+	}
+}
+
+/// Read the state of the feed `feed_id` at the block `block_hash`.
+fn read_feed_state(
+	client: &FullClient,
+	feed_id: FeedId,
+	block_hash: Hash,
+) -> Result<FeedState, String> {
+	let at = BlockId::Hash(block_hash);
+	let config = read(client, &at, Feeds::<Runtime>::hashed_key_for(feed_id))?
+		.ok_or_else(|| format!("Feed {} does not exist at block {}", feed_id, block_hash))?;
+	let debt = read(client, &at, Debt::<Runtime>::hashed_key().to_vec())?.unwrap_or_default();
+
+	let oracles = read_prefix::<AccountId, OracleStatusOf<Runtime>, Blake2_128Concat>(
+		client,
+		&at,
+		feed_prefix(&OracleStatuses::<Runtime>::final_prefix(), feed_id),
+	)?
+	.into_iter()
+	.map(|(account, status)| {
+		let meta = read(client, &at, Oracles::<Runtime>::hashed_key_for(&account))?;
+		Ok(OracleEntry { account, status, meta })
+	})
+	.collect::<Result<_, String>>()?;
+
+	let requesters = read_prefix::<AccountId, Requester, Blake2_128Concat>(
+		client,
+		&at,
+		feed_prefix(&Requesters::<Runtime>::final_prefix(), feed_id),
+	)?
+	.into_iter()
+	.map(|(account, requester)| RequesterEntry { account, requester })
+	.collect();
+
+	let mut rounds = read_prefix::<RoundId, RoundOf<Runtime>, Twox64Concat>(
+		client,
+		&at,
+		feed_prefix(&Rounds::<Runtime>::final_prefix(), feed_id),
+	)?
+	.into_iter()
+	.map(|(round_id, round)| {
+		let key = Details::<Runtime>::hashed_key_for(feed_id, round_id);
+		let details = read(client, &at, key)?;
+		Ok(RoundEntry { round_id, round, details })
+	})
+	.collect::<Result<Vec<_>, String>>()?;
+	rounds.sort_by_key(|r| r.round_id);
+
+	Ok(FeedState { feed_id, block_hash, config, debt, oracles, requesters, rounds })
+}
+
+/// The prefix of the entries of a double map keyed by feed first.
+fn feed_prefix(final_prefix: &[u8], feed_id: FeedId) -> Vec<u8> {
+	[final_prefix, &Twox64Concat::hash(&feed_id.encode())[..]].concat()
+}
+
+/// Read and decode the value stored under `key`.
+fn read<V: Decode>(
+	client: &FullClient,
+	at: &BlockId<Block>,
+	key: Vec<u8>,
+) -> Result<Option<V>, String> {
+	client
+		.storage(at, &StorageKey(key))
+		.map_err(|e| format!("Unable to read the storage: {:?}", e))?
+		.map(|data| V::decode(&mut &data.0[..]))
+		.transpose()
+		.map_err(|e| format!("Unable to decode the storage: {:?}", e))
+}
+
+/// Read and decode all entries stored under `prefix`, with the rest of their keys hashed with `H`.
+fn read_prefix<K, V, H>(
+	client: &FullClient,
+	at: &BlockId<Block>,
+	prefix: Vec<u8>,
+) -> Result<Vec<(K, V)>, String>
+where
+	K: Decode,
+	V: Decode,
+	H: ReversibleStorageHasher,
+{
+	client
+		.storage_keys(at, &StorageKey(prefix.clone()))
+		.map_err(|e| format!("Unable to read the storage keys: {:?}", e))?
+		.into_iter()
+		.map(|key| {
+			let k = K::decode(&mut H::reverse(&key.0[prefix.len()..]))
+				.map_err(|e| format!("Unable to decode the storage key: {:?}", e))?;
+			let v = read(client, at, key.0)?
+				.ok_or_else(|| "Storage changed while reading it".to_string())?;
+			Ok((k, v))
+		})
+		.collect()
+}
+
+/// Write the state as sections of comma separated values.
+///
+/// The feed itself is written as `key,value` lines, the oracles, requesters and rounds as one
+/// line per entry. Nested values are flattened into dot separated column names.
+fn write_csv(out: &mut dyn Write, state: &serde_json::Value) -> std::io::Result<()> {
+	let mut feed = Vec::new();
+	for key in &["feedId", "blockHash", "debt"] {
+		feed.push((key.to_string(), csv_field(&state[*key])));
+	}
+	flatten("config", &state["config"], &mut feed);
+	writeln!(out, "# feed")?;
+	writeln!(out, "key,value")?;
+	for (key, value) in feed {
+		writeln!(out, "{},{}", key, value)?;
+	}
+
+	for section in &["oracles", "requesters", "rounds"] {
+		let rows: Vec<Vec<(String, String)>> = state[*section]
+			.as_array()
+			.map(|entries| {
+				entries
+					.iter()
+					.map(|entry| {
+						let mut row = Vec::new();
+						flatten("", entry, &mut row);
+						row
+					})
+					.collect()
+			})
+			.unwrap_or_default();
+		// entries may lack optional fields, so the columns are the union of all their fields
+		let mut header: Vec<&str> = Vec::new();
+		for (key, _) in rows.iter().flatten() {
+			if !header.contains(&key.as_str()) {
+				header.push(key);
+			}
+		}
+		writeln!(out)?;
+		writeln!(out, "# {}", section)?;
+		writeln!(out, "{}", header.join(","))?;
+		for row in &rows {
+			let values: Vec<&str> = header
+				.iter()
+				.map(|column| {
+					row.iter()
+						.find(|(key, _)| key == column)
+						.map_or("", |(_, value)| value.as_str())
+				})
+				.collect();
+			writeln!(out, "{}", values.join(","))?;
+		}
+	}
+	Ok(())
+}
+
+/// Flatten the objects in `value` into `(column, field)` pairs, skipping absent values.
+fn flatten(prefix: &str, value: &serde_json::Value, row: &mut Vec<(String, String)>) {
+	match value {
+		serde_json::Value::Null => {}
+		serde_json::Value::Object(fields) => {
+			for (key, field) in fields {
+				let column =
+					if prefix.is_empty() { key.clone() } else { format!("{}.{}", prefix, key) };
+				flatten(&column, field, row);
+			}
+		}
+		_ => row.push((prefix.to_string(), csv_field(value))),
+	}
+}
+
+/// Render a JSON value as a CSV field, quoting it if necessary.
+fn csv_field(value: &serde_json::Value) -> String {
+	let field = match value {
+		serde_json::Value::Null => String::new(),
+		serde_json::Value::String(s) => s.clone(),
+		other => other.to_string(),
+	};
+	if field.contains(|c: char| c == ',' || c == '"' || c == '\n') {
+		format!("\"{}\"", field.replace('"', "\"\""))
+	} else {
+		field
+	}
+}
@@ -0,0 +1,133 @@
+//! Prometheus metrics on the health of the price feeds.
+//!
+//! Reads the state of all feeds through the `ChainlinkFeedApi` on every new best block, so stale
+//! feeds and an underfunded pallet can be alerted on without polling the node from the outside.
+
+use std::sync::Arc;
+
+use futures::StreamExt;
+use node_template_runtime::{opaque::Block, AccountId, Balance, BlockNumber, FeedId};
+use pallet_chainlink_feed_runtime_api::ChainlinkFeedApi;
+use sc_client_api::BlockchainEvents;
+use sp_api::ProvideRuntimeApi;
+use sp_runtime::{generic::BlockId, traits::Header as HeaderT};
+use substrate_prometheus_endpoint::{
+	register, Gauge, GaugeVec, Opts, PrometheusError, Registry, F64, U64,
+};
+
+/// The gauges exported for the feeds.
+pub struct FeedMetrics {
+	latest_round: GaugeVec<U64>,
+	answer_age: GaugeVec<U64>,
+	pending_submissions: GaugeVec<U64>,
+	fund_balance: Gauge<F64>,
+	debt: Gauge<F64>,
+}
+
+impl FeedMetrics {
+	/// Create the gauges and register them with `registry`.
+	pub fn register(registry: &Registry) -> Result<Self, PrometheusError> {
+		Ok(Self {
+			latest_round: register(
+				GaugeVec::new(
+					Opts::new("chainlink_feed_latest_round", "Latest answered round of a feed"),
+					&["feed_id"],
+				)?,
+				registry,
+			)?,
+			answer_age: register(
+				GaugeVec::new(
+					Opts::new(
+						"chainlink_feed_answer_age_blocks",
+						"Number of blocks since the latest answer of a feed was updated",
+					),
+					&["feed_id"],
+				)?,
+				registry,
+			)?,
+			pending_submissions: register(
+				GaugeVec::new(
+					Opts::new(
+						"chainlink_feed_pending_submissions",
+						"Submissions to the reporting round of a feed that is not answered yet",
+					),
+					&["feed_id"],
+				)?,
+				registry,
+			)?,
+			fund_balance: register(
+				Gauge::new(
+					"chainlink_feed_fund_balance",
+					"Free balance of the feed pallet fund account",
+				)?,
+				registry,
+			)?,
+			debt: register(
+				Gauge::new("chainlink_feed_debt", "Debt of the feed pallet towards the oracles")?,
+				registry,
+			)?,
+		})
+	}
+}
+
+/// Update `metrics` with the state of the feeds on each new best block.
+pub async fn run<C>(client: Arc<C>, metrics: FeedMetrics)
+where
+	C: ProvideRuntimeApi<Block> + BlockchainEvents<Block> + Send + Sync + 'static,
+	C::Api: ChainlinkFeedApi<Block, AccountId, FeedId, BlockNumber, Balance>,
+{
+	let mut notifications = client.import_notification_stream();
+	while let Some(notification) = notifications.next().await {
+		if !notification.is_new_best {
+			continue;
+		}
+		let best_number: BlockNumber = *notification.header.number();
+		if let Err(e) = update(&*client, &metrics, BlockId::Hash(notification.hash), best_number)
+		{
+			log::warn!("Unable to update the feed metrics: {:?}", e);
+		}
+	}
+}
+
+/// Set the gauges to the state of the feeds at block `at`.
+fn update<C>(
+	client: &C,
+	metrics: &FeedMetrics,
+	at: BlockId<Block>,
+	best_number: BlockNumber,
+) -> Result<(), sp_api::ApiError>
+where
+	C: ProvideRuntimeApi<Block>,
+	C::Api: ChainlinkFeedApi<Block, AccountId, FeedId, BlockNumber, Balance>,
+{
+	let runtime_api = client.runtime_api();
+
+	// drop the series of removed feeds
+	metrics.latest_round.reset();
+	metrics.answer_age.reset();
+	metrics.pending_submissions.reset();
+	for feed_id in runtime_api.feed_ids(&at)? {
+		let health = match runtime_api.feed_health(&at, feed_id)? {
+			Some(health) => health,
+			None => continue,
+		};
+		let label = feed_id.to_string();
+		let labels = [label.as_str()];
+		metrics.latest_round.with_label_values(&labels).set(health.latest_round.into());
+		metrics
+			.pending_submissions
+			.with_label_values(&labels)
+			.set(health.pending_submissions.into());
+		if let Some(updated_at) = health.updated_at {
+			metrics
+				.answer_age
+				.with_label_values(&labels)
+				.set(best_number.saturating_sub(updated_at).into());
+		}
+	}
+
+	let fund = runtime_api.fund_health(&at)?;
+	metrics.fund_balance.set(fund.free_balance as f64);
+	metrics.debt.set(fund.debt as f64);
+	Ok(())
+}
@@ -1,3 +1,7 @@
 pub mod chain_spec;
+pub mod feed;
+pub mod feed_metrics;
 pub mod service;
 pub mod rpc;
+pub mod replay;
+pub mod simulated_oracles;
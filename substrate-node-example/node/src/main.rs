@@ -6,7 +6,11 @@ mod chain_spec;
 mod service;
 mod cli;
 mod command;
+mod feed;
+mod feed_metrics;
 mod rpc;
+mod replay;
+mod simulated_oracles;
 
 fn main() -> sc_cli::Result<()> {
 	command::run()
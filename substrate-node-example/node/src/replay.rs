@@ -0,0 +1,189 @@
+//! Replay a historical price series into the `oracle-dev` chain.
+//!
+//! Each row of the history is submitted by the dev oracles (see
+//! `chain_spec::ORACLE_DEV_ORACLES`) as the answer of one round, so consumer pallets can be
+//! tested against real market movements. Once every row was answered, the `AnswerUpdated` events
+//! produced by the replay are reported.
+
+use std::{collections::HashMap, fs::File, io::Write, path::PathBuf, sync::Arc};
+
+use codec::Decode;
+use futures::StreamExt;
+use node_template_runtime::{
+	opaque::Block, pallet_chainlink_feed, Event, FeedId, Hash, RoundId, Value,
+};
+use sc_cli::RunCmd;
+use sc_client_api::{BlockchainEvents, StorageProvider};
+use sc_transaction_pool_api::{TransactionPool, TransactionSource};
+use sp_blockchain::HeaderBackend;
+use sp_core::storage::StorageKey;
+use sp_runtime::{
+	generic::BlockId,
+	traits::{Header as HeaderT, SaturatedConversion},
+};
+use structopt::StructOpt;
+
+use crate::{
+	chain_spec::ORACLE_DEV_FEEDS,
+	service::FullClient,
+	simulated_oracles::{create_submissions, dev_oracles, read_value_table},
+};
+
+/// The `replay-prices` command used to replay a price history into the `oracle-dev` chain.
+#[derive(Debug, StructOpt)]
+pub struct ReplayPricesCmd {
+	/// CSV or JSON file with the price history, one row of feed values per round (the column is
+	/// the feed id).
+	#[structopt(parse(from_os_str))]
+	pub history: PathBuf,
+
+	/// Write the report of the produced answers to this file instead of stdout.
+	#[structopt(long, parse(from_os_str))]
+	pub report: Option<PathBuf>,
+
+	#[allow(missing_docs)]
+	#[structopt(flatten)]
+	pub run: RunCmd,
+}
+
+/// An answer produced by the replay, taken from an `AnswerUpdated` event.
+struct Answer {
+	block: u64,
+	feed_id: FeedId,
+	round_id: RoundId,
+	value: Value,
+}
+
+impl ReplayPricesCmd {
+	/// Read the price history and check that the `oracle-dev` chain has a feed for every column.
+	pub fn read_history(&self) -> Result<Vec<Vec<Value>>, String> {
+		let history = read_value_table(&self.history)?;
+		let feeds = history[0].len();
+		if feeds > ORACLE_DEV_FEEDS.len() {
+			return Err(format!(
+				"The history has values for {} feeds but the oracle-dev chain only has {}.",
+				feeds,
+				ORACLE_DEV_FEEDS.len()
+			));
+		}
+		Ok(history)
+	}
+
+	/// Submit the `history` round by round and report the answers once all rows were answered.
+	pub async fn run<P>(
+		&self,
+		client: Arc<FullClient>,
+		pool: Arc<P>,
+		history: Vec<Vec<Value>>,
+	) -> sc_cli::Result<()>
+	where
+		P: TransactionPool<Block = Block> + 'static,
+	{
+		let oracles = dev_oracles();
+		let genesis_hash = client.info().genesis_hash;
+		let feeds = history[0].len();
+		let last_offset = history.len() as RoundId - 1;
+		// the round answered with the first row of the history for each feed
+		let mut first_rounds: HashMap<FeedId, RoundId> = HashMap::new();
+		let mut answers = Vec::new();
+		let mut notifications = client.import_notification_stream();
+
+		log::info!("Replaying {} rounds of {} feeds.", history.len(), feeds);
+		while let Some(notification) = notifications.next().await {
+			if !notification.is_new_best {
+				continue;
+			}
+			let at = BlockId::Hash(notification.hash);
+			let best_number: u64 = (*notification.header.number()).saturated_into();
+
+			answers.extend(answers_at(&client, &at, best_number)?.into_iter().filter(|a| {
+				first_rounds.get(&a.feed_id).map_or(false, |first| {
+					a.round_id >= *first && a.round_id - first <= last_offset
+				})
+			}));
+			let done = (0..feeds as FeedId).all(|feed_id| {
+				first_rounds.get(&feed_id).map_or(false, |first| {
+					answers.iter().any(|a| a.feed_id == feed_id && a.round_id == first + last_offset)
+				})
+			});
+			if done {
+				break;
+			}
+
+			let submissions = create_submissions(
+				&*client,
+				&oracles,
+				|feed_id, round_id| {
+					let first = *first_rounds.get(&feed_id).unwrap_or(&round_id);
+					let offset = round_id.checked_sub(first)?;
+					history.get(offset as usize)?.get(feed_id as usize).copied()
+				},
+				genesis_hash,
+				(notification.hash, best_number),
+			)
+			.map_err(|e| format!("Unable to query the chain: {:?}", e))?;
+			for (feed_id, round_id, xt) in submissions {
+				first_rounds.entry(feed_id).or_insert(round_id);
+				if let Err(e) = pool.submit_one(&at, TransactionSource::Local, xt).await {
+					log::debug!(
+						"Could not submit the replayed value of feed {} round {}: {:?}",
+						feed_id,
+						round_id,
+						e
+					);
+				}
+			}
+		}
+
+		self.write_report(&answers).map_err(Into::into)
+	}
+
+	/// Write the `answers` as comma separated values.
+	fn write_report(&self, answers: &[Answer]) -> std::io::Result<()> {
+		let mut out: Box<dyn Write> = match &self.report {
+			Some(path) => Box::new(File::create(path)?),
+			None => Box::new(std::io::stdout()),
+		};
+		writeln!(out, "block,feed_id,round_id,answer")?;
+		for answer in answers {
+			writeln!(
+				out,
+				"{},{},{},{}",
+				answer.block, answer.feed_id, answer.round_id, answer.value
+			)?;
+		}
+		Ok(())
+	}
+}
+
+/// The answers updated in the block `at` according to its `AnswerUpdated` events.
+fn answers_at(
+	client: &FullClient,
+	at: &BlockId<Block>,
+	block: u64,
+) -> Result<Vec<Answer>, String> {
+	let key = [sp_core::twox_128(b"System"), sp_core::twox_128(b"Events")].concat();
+	let events = match client
+		.storage(at, &StorageKey(key))
+		.map_err(|e| format!("Unable to read the events: {:?}", e))?
+	{
+		Some(data) => {
+			Vec::<frame_system::EventRecord<Event, Hash>>::decode(&mut &data.0[..])
+				.map_err(|e| format!("Unable to decode the events: {:?}", e))?
+		}
+		None => return Ok(Vec::new()),
+	};
+
+	Ok(events
+		.into_iter()
+		.filter_map(|record| match record.event {
+			Event::ChainlinkFeed(pallet_chainlink_feed::Event::AnswerUpdated(
+				feed_id,
+				round_id,
+				value,
+				_,
+			)) => Some(Answer { block, feed_id, round_id, value }),
+			_ => None,
+		})
+		.collect())
+}
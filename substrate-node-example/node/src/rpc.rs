@@ -7,44 +7,61 @@
 
 use std::sync::Arc;
 
-use node_template_runtime::{opaque::Block, AccountId, Balance, Index};
+use node_template_runtime::{opaque::Block, AccountId, Balance, BlockNumber, FeedId, Index, Value};
+use sc_client_api::{Backend, BlockchainEvents, ProofProvider};
+use sc_rpc::SubscriptionTaskExecutor;
+use jsonrpc_pubsub::manager::SubscriptionManager;
 use sp_api::ProvideRuntimeApi;
 use sp_blockchain::{Error as BlockChainError, HeaderMetadata, HeaderBackend};
 use sp_block_builder::BlockBuilder;
+use pallet_chainlink_feed_runtime_api::ChainlinkFeedRoundApi;
 pub use sc_rpc_api::DenyUnsafe;
-use sp_transaction_pool::TransactionPool;
+use sc_transaction_pool_api::TransactionPool;
 
 
 /// Full client dependencies.
-pub struct FullDeps<C, P> {
+pub struct FullDeps<C, P, B> {
 	/// The client instance to use.
 	pub client: Arc<C>,
+	/// The backend instance, used to access the offchain storage.
+	pub backend: Arc<B>,
 	/// Transaction pool instance.
 	pub pool: Arc<P>,
 	/// Whether to deny unsafe calls
 	pub deny_unsafe: DenyUnsafe,
+	/// Executor to run the subscriptions on.
+	pub subscription_executor: SubscriptionTaskExecutor,
 }
 
 /// Instantiate all full RPC extensions.
-pub fn create_full<C, P>(
-	deps: FullDeps<C, P>,
+pub fn create_full<C, P, B>(
+	deps: FullDeps<C, P, B>,
 ) -> jsonrpc_core::IoHandler<sc_rpc::Metadata> where
 	C: ProvideRuntimeApi<Block>,
 	C: HeaderBackend<Block> + HeaderMetadata<Block, Error=BlockChainError> + 'static,
+	C: BlockchainEvents<Block> + ProofProvider<Block>,
 	C: Send + Sync + 'static,
 	C::Api: substrate_frame_rpc_system::AccountNonceApi<Block, AccountId, Index>,
 	C::Api: pallet_transaction_payment_rpc::TransactionPaymentRuntimeApi<Block, Balance>,
 	C::Api: BlockBuilder<Block>,
+	C::Api: ChainlinkFeedRoundApi<Block, FeedId, BlockNumber, Value>,
 	P: TransactionPool + 'static,
+	B: Backend<Block> + Send + Sync + 'static,
 {
 	use substrate_frame_rpc_system::{FullSystem, SystemApi};
 	use pallet_transaction_payment_rpc::{TransactionPayment, TransactionPaymentApi};
+	use pallet_chainlink_feed_rpc::{
+		ChainlinkFeed, ChainlinkFeedAnswers, ChainlinkFeedAnswersApi, ChainlinkFeedApi,
+		ChainlinkFeedProofApi, ChainlinkFeedProofs,
+	};
 
 	let mut io = jsonrpc_core::IoHandler::default();
 	let FullDeps {
 		client,
+		backend,
 		pool,
 		deny_unsafe,
+		subscription_executor,
 	} = deps;
 
 	io.extend_with(
@@ -55,6 +72,20 @@ pub fn create_full<C, P>(
 		TransactionPaymentApi::to_delegate(TransactionPayment::new(client.clone()))
 	);
 
+	// The feed round history is only available if the node has an offchain storage.
+	if let Some(storage) = backend.offchain_storage() {
+		io.extend_with(
+			ChainlinkFeedApi::<FeedId, BlockNumber, Value>::to_delegate(ChainlinkFeed::new(storage))
+		);
+	}
+
+	io.extend_with(ChainlinkFeedAnswersApi::to_delegate(ChainlinkFeedAnswers::new(
+		client.clone(),
+		SubscriptionManager::new(Arc::new(subscription_executor)),
+	)));
+
+	io.extend_with(ChainlinkFeedProofApi::to_delegate(ChainlinkFeedProofs::new(client.clone())));
+
 	// Extend this RPC with a custom API by using the following syntax.
 	// `YourRpcStruct` should have a reference to a client, which is needed
 	// to call into the runtime.
@@ -0,0 +1,281 @@
+//! Simulated oracles for the `oracle-dev` chain.
+//!
+//! Submits values to the feeds of the chain on behalf of the dev oracles (see
+//! `chain_spec::ORACLE_DEV_ORACLES`) on every new best block, so front-end and consumer pallet
+//! developers can work against live feeds without running a Chainlink node.
+
+use std::{collections::HashMap, path::Path, sync::Arc};
+
+use codec::Encode;
+use futures::StreamExt;
+use node_template_runtime::{
+	opaque::Block, pallet_chainlink_feed, AccountId, Balance, BlockHashCount, BlockNumber, Call,
+	FeedId, Hash, Index, RoundId, Runtime, SignedExtra, UncheckedExtrinsic, Value,
+};
+use pallet_chainlink_feed_runtime_api::ChainlinkFeedApi;
+use rand::Rng;
+use sc_client_api::BlockchainEvents;
+use sc_transaction_pool_api::{TransactionPool, TransactionSource};
+use sp_api::{Core, ProvideRuntimeApi};
+use sp_blockchain::HeaderBackend;
+use sp_core::{sr25519, Pair};
+use sp_runtime::{
+	generic::{BlockId, Era, SignedPayload},
+	traits::{Header as HeaderT, IdentifyAccount, SaturatedConversion},
+	MultiSigner, OpaqueExtrinsic,
+};
+use substrate_frame_rpc_system::AccountNonceApi;
+
+use crate::chain_spec::{ORACLE_DEV_FEEDS, ORACLE_DEV_ORACLES};
+
+/// The maximum change of a random walk step in basis points.
+const MAX_STEP_BASIS_POINTS: i64 = 50;
+
+/// Where the simulated oracles take the submitted values from.
+pub enum ValueSource {
+	/// The values change by a random step of up to 0.5% per block, starting at the genesis
+	/// answers of the `oracle-dev` feeds.
+	RandomWalk,
+	/// The values are read from a script with one row of feed values per block (the column is
+	/// the feed id). Starts over at the end of the script.
+	Scripted(Vec<Vec<Value>>),
+}
+
+impl ValueSource {
+	/// Read a script of values from the file at `path` (see [`read_value_table`]).
+	pub fn from_script(path: &Path) -> Result<Self, String> {
+		read_value_table(path).map(ValueSource::Scripted)
+	}
+}
+
+/// Read a table of feed values with one row per block (or round) and one column per feed id.
+///
+/// Files with a `.json` extension hold an array of rows, other files are read as comma separated
+/// values where empty lines and lines starting with `#` are ignored.
+pub fn read_value_table(path: &Path) -> Result<Vec<Vec<Value>>, String> {
+	let content = std::fs::read_to_string(path)
+		.map_err(|e| format!("Unable to read {}: {}", path.display(), e))?;
+	let rows: Vec<Vec<Value>> = if path.extension().map_or(false, |ext| ext == "json") {
+		serde_json::from_str(&content)
+			.map_err(|e| format!("Invalid JSON in {}: {}", path.display(), e))?
+	} else {
+		content
+			.lines()
+			.map(str::trim)
+			.filter(|line| !line.is_empty() && !line.starts_with('#'))
+			.map(|line| {
+				line.split(',')
+					.map(|value| {
+						value.trim().parse::<Value>().map_err(|e| {
+							format!("Invalid value {:?} in {}: {}", value, path.display(), e)
+						})
+					})
+					.collect::<Result<Vec<_>, _>>()
+			})
+			.collect::<Result<_, _>>()?
+	};
+	let columns = match rows.first() {
+		Some(row) => row.len(),
+		None => return Err(format!("{} does not contain any values.", path.display())),
+	};
+	if let Some(index) = rows.iter().position(|row| row.len() != columns) {
+		return Err(format!(
+			"Row {} of {} has {} values, expected {}.",
+			index + 1,
+			path.display(),
+			rows[index].len(),
+			columns
+		));
+	}
+	Ok(rows)
+}
+
+/// The values to submit in the current block.
+struct Values {
+	source: ValueSource,
+	block: usize,
+	latest: HashMap<FeedId, Value>,
+}
+
+impl Values {
+	fn new(source: ValueSource) -> Self {
+		let latest = ORACLE_DEV_FEEDS
+			.iter()
+			.enumerate()
+			.map(|(id, (_, answer))| (id as FeedId, *answer))
+			.collect();
+		Self { source, block: 0, latest }
+	}
+
+	/// Move on to the values of the next block.
+	fn advance(&mut self) {
+		match &self.source {
+			ValueSource::RandomWalk => {
+				let mut rng = rand::thread_rng();
+				for value in self.latest.values_mut() {
+					let step = rng.gen_range(-MAX_STEP_BASIS_POINTS..=MAX_STEP_BASIS_POINTS);
+					let delta = *value / 10_000 * step.unsigned_abs() as Value;
+					*value = if step < 0 {
+						value.saturating_sub(delta).max(1)
+					} else {
+						value.saturating_add(delta)
+					};
+				}
+			}
+			ValueSource::Scripted(script) => {
+				let line = &script[self.block % script.len()];
+				for (id, value) in line.iter().enumerate() {
+					self.latest.insert(id as FeedId, *value);
+				}
+			}
+		}
+		self.block = self.block.wrapping_add(1);
+	}
+
+	/// The value to submit to the given feed (if any).
+	fn get(&self, feed_id: FeedId) -> Option<Value> {
+		self.latest.get(&feed_id).copied()
+	}
+}
+
+/// Submit values for the dev oracles to every round they are eligible for on each new best block.
+pub async fn run<C, P>(client: Arc<C>, pool: Arc<P>, source: ValueSource)
+where
+	C: ProvideRuntimeApi<Block> + HeaderBackend<Block> + BlockchainEvents<Block>,
+	C: Send + Sync + 'static,
+	C::Api: Core<Block>,
+	C::Api: ChainlinkFeedApi<Block, AccountId, FeedId, BlockNumber, Balance>,
+	C::Api: AccountNonceApi<Block, AccountId, Index>,
+	P: TransactionPool<Block = Block> + 'static,
+{
+	let oracles = dev_oracles();
+	let genesis_hash = client.info().genesis_hash;
+	let mut values = Values::new(source);
+	let mut notifications = client.import_notification_stream();
+
+	while let Some(notification) = notifications.next().await {
+		if !notification.is_new_best {
+			continue;
+		}
+		values.advance();
+
+		let at = BlockId::Hash(notification.hash);
+		let best_number: u64 = (*notification.header.number()).saturated_into();
+		let submissions = match create_submissions(
+			&*client,
+			&oracles,
+			|feed_id, _| values.get(feed_id),
+			genesis_hash,
+			(notification.hash, best_number),
+		) {
+			Ok(submissions) => submissions,
+			Err(e) => {
+				log::warn!("Simulated oracles could not query the chain: {:?}", e);
+				continue;
+			}
+		};
+		for (feed_id, round_id, xt) in submissions {
+			if let Err(e) = pool.submit_one(&at, TransactionSource::Local, xt).await {
+				// e.g. the submission of the previous block is still in the pool
+				log::debug!(
+					"Simulated oracle could not submit to feed {} round {}: {:?}",
+					feed_id,
+					round_id,
+					e
+				);
+			}
+		}
+	}
+}
+
+/// The key pairs of the `oracle-dev` oracles.
+pub(crate) fn dev_oracles() -> Vec<sr25519::Pair> {
+	ORACLE_DEV_ORACLES
+		.iter()
+		.map(|seed| {
+			sr25519::Pair::from_string(&format!("//{}", seed), None)
+				.expect("static values are valid; qed")
+		})
+		.collect()
+}
+
+/// Create the signed submissions of all `oracles` for the rounds they are eligible for at
+/// the given best block, skipping the rounds `value` returns `None` for.
+pub(crate) fn create_submissions<C>(
+	client: &C,
+	oracles: &[sr25519::Pair],
+	value: impl Fn(FeedId, RoundId) -> Option<Value>,
+	genesis_hash: Hash,
+	(best_hash, best_number): (Hash, u64),
+) -> Result<Vec<(FeedId, RoundId, OpaqueExtrinsic)>, sp_api::ApiError>
+where
+	C: ProvideRuntimeApi<Block>,
+	C::Api: Core<Block>,
+	C::Api: ChainlinkFeedApi<Block, AccountId, FeedId, BlockNumber, Balance>,
+	C::Api: AccountNonceApi<Block, AccountId, Index>,
+{
+	let at = BlockId::Hash(best_hash);
+	let runtime_api = client.runtime_api();
+	let version = runtime_api.version(&at)?;
+
+	let mut submissions = Vec::new();
+	for pair in oracles {
+		let oracle = MultiSigner::from(pair.public()).into_account();
+		let mut nonce = runtime_api.account_nonce(&at, oracle.clone())?;
+		for (feed_id, round_id) in runtime_api.eligible_rounds(&at, oracle)? {
+			let value = match value(feed_id, round_id) {
+				Some(value) => value,
+				None => continue,
+			};
+			let call = Call::ChainlinkFeed(pallet_chainlink_feed::Call::submit(
+				feed_id, round_id, value,
+			));
+			let xt = create_extrinsic(
+				pair,
+				call,
+				nonce,
+				(version.spec_version, version.transaction_version),
+				genesis_hash,
+				(best_hash, best_number),
+			);
+			submissions.push((feed_id, round_id, xt));
+			nonce += 1;
+		}
+	}
+	Ok(submissions)
+}
+
+/// Create a signed extrinsic dispatching `call` from the account of `pair`.
+fn create_extrinsic(
+	pair: &sr25519::Pair,
+	call: Call,
+	nonce: Index,
+	(spec_version, transaction_version): (u32, u32),
+	genesis_hash: Hash,
+	(best_hash, best_number): (Hash, u64),
+) -> OpaqueExtrinsic {
+	let period = BlockHashCount::get()
+		.checked_next_power_of_two()
+		.map(|c| c / 2)
+		.unwrap_or(2) as u64;
+	let extra: SignedExtra = (
+		frame_system::CheckSpecVersion::<Runtime>::new(),
+		frame_system::CheckTxVersion::<Runtime>::new(),
+		frame_system::CheckGenesis::<Runtime>::new(),
+		frame_system::CheckEra::<Runtime>::from(Era::mortal(period, best_number)),
+		frame_system::CheckNonce::<Runtime>::from(nonce),
+		frame_system::CheckWeight::<Runtime>::new(),
+		pallet_transaction_payment::ChargeTransactionPayment::<Runtime>::from(0),
+	);
+	let payload = SignedPayload::from_raw(
+		call.clone(),
+		extra.clone(),
+		(spec_version, transaction_version, genesis_hash, best_hash, (), (), ()),
+	);
+	let signature = payload.using_encoded(|e| pair.sign(e));
+	let signer: AccountId = MultiSigner::from(pair.public()).into_account();
+	let xt = UncheckedExtrinsic::new_signed(call, signer, signature.into(), extra);
+
+	OpaqueExtrinsic::from_bytes(&xt.encode())
+		.expect("a valid extrinsic is a valid opaque extrinsic; qed")
+}